@@ -55,8 +55,8 @@ fn main() {
         .booster_params(booster_params)
         .boost_rounds(2)
         .evaluation_sets(Some(&evaluation_sets))
-        .custom_objective_fn(Some(log_reg_obj))
-        .custom_evaluation_fn(Some(eval_error))
+        .custom_objective_fn(Some(&log_reg_obj))
+        .custom_evaluation_fn(Some(&eval_error))
         .build().unwrap();
 
     // train booster model, and print evaluation metrics
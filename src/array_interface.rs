@@ -0,0 +1,177 @@
+//! Helpers for passing memory to XGBoost's C API using the
+//! [array interface protocol](https://numpy.org/doc/stable/reference/arrays.interface.html), which XGBoost uses to
+//! read dense arrays, CSR matrices and columnar data without copying them.
+use std::ffi;
+
+/// Element types which can be described to XGBoost using the array interface protocol.
+pub(crate) trait ArrayElement {
+    /// Type kind and size of the element, without the byte order prefix (e.g. `f4` for `f32`).
+    const KIND: &'static str;
+}
+
+impl ArrayElement for f32 {
+    const KIND: &'static str = "f4";
+}
+
+impl ArrayElement for f64 {
+    const KIND: &'static str = "f8";
+}
+
+impl ArrayElement for i32 {
+    const KIND: &'static str = "i4";
+}
+
+impl ArrayElement for i64 {
+    const KIND: &'static str = "i8";
+}
+
+impl ArrayElement for u32 {
+    const KIND: &'static str = "u4";
+}
+
+impl ArrayElement for u64 {
+    const KIND: &'static str = "u8";
+}
+
+impl ArrayElement for usize {
+    #[cfg(target_pointer_width = "64")]
+    const KIND: &'static str = "u8";
+    #[cfg(target_pointer_width = "32")]
+    const KIND: &'static str = "u4";
+}
+
+/// Get the array interface type string for `T` in native byte order, e.g. `<f4` for `f32` on little endian
+/// platforms.
+pub(crate) fn typestr<T: ArrayElement>() -> String {
+    let byte_order = if cfg!(target_endian = "little") { '<' } else { '>' };
+    format!("{}{}", byte_order, T::KIND)
+}
+
+/// Describe a contiguous, row-major array with given shape as an array interface JSON object.
+///
+/// The returned JSON refers to `data` by address, so `data` must outlive any use of it by XGBoost.
+pub(crate) fn array_interface_json<T: ArrayElement>(data: &[T], shape: &[usize]) -> String {
+    debug_assert_eq!(shape.iter().product::<usize>(), data.len());
+    interface_json(data.as_ptr() as usize, shape, &typestr::<T>(), None)
+}
+
+/// Build an array interface JSON object from its parts, with an optional validity mask given as another array
+/// interface JSON object.
+fn interface_json(address: usize, shape: &[usize], typestr: &str, mask: Option<&str>) -> String {
+    let shape: Vec<String> = shape.iter().map(|dim| dim.to_string()).collect();
+    let mask = mask.map(|mask| format!(r#","mask":{}"#, mask)).unwrap_or_default();
+    format!(
+        r#"{{"data":[{},true]{},"shape":[{}],"typestr":"{}","version":3}}"#,
+        address,
+        mask,
+        shape.join(","),
+        typestr
+    )
+}
+
+/// Describe a contiguous, row-major array with given shape as a null terminated array interface JSON string.
+///
+/// See [`array_interface_json`].
+pub(crate) fn array_interface<T: ArrayElement>(data: &[T], shape: &[usize]) -> ffi::CString {
+    ffi::CString::new(array_interface_json(data, shape)).unwrap()
+}
+
+/// Array interface description of an Arrow array, which refers to the array's memory without copying values.
+#[cfg(feature = "arrow")]
+pub(crate) struct ArrowColumn {
+    pub(crate) json: String,
+
+    /// Validity bitmap referred to by `json`, kept alive here as it's copied when not byte aligned.
+    _mask: Option<arrow_buffer::Buffer>,
+}
+
+/// Describe a numeric Arrow array using the array interface protocol, with its null bitmap (if any) as the
+/// validity mask so null entries are treated as missing by XGBoost.
+#[cfg(feature = "arrow")]
+pub(crate) fn arrow_column(array: &dyn arrow_array::Array) -> Result<ArrowColumn, String> {
+    use arrow_array::cast::AsArray;
+    use arrow_array::types::{Float32Type, Float64Type, Int32Type, Int64Type, UInt32Type, UInt64Type};
+    use arrow_schema::DataType;
+
+    // values are already offset into the underlying buffers when sliced
+    let (address, typestr) = match array.data_type() {
+        DataType::Float32 => arrow_values(array.as_primitive::<Float32Type>().values()),
+        DataType::Float64 => arrow_values(array.as_primitive::<Float64Type>().values()),
+        DataType::Int32 => arrow_values(array.as_primitive::<Int32Type>().values()),
+        DataType::Int64 => arrow_values(array.as_primitive::<Int64Type>().values()),
+        DataType::UInt32 => arrow_values(array.as_primitive::<UInt32Type>().values()),
+        DataType::UInt64 => arrow_values(array.as_primitive::<UInt64Type>().values()),
+        data_type => return Err(format!("Unsupported Arrow data type for XGBoost: {}", data_type)),
+    };
+
+    let mask = array
+        .nulls()
+        .filter(|nulls| nulls.null_count() > 0)
+        .map(|nulls| nulls.inner().sliced());
+    let mask_json = mask
+        .as_ref()
+        .map(|mask| interface_json(mask.as_ptr() as usize, &[array.len()], "|t1", None));
+    Ok(ArrowColumn {
+        json: interface_json(address, &[array.len()], &typestr, mask_json.as_deref()),
+        _mask: mask,
+    })
+}
+
+#[cfg(feature = "arrow")]
+fn arrow_values<T: ArrayElement>(values: &[T]) -> (usize, String) {
+    (values.as_ptr() as usize, typestr::<T>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn array_interface_format() {
+        let data = [1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let json = array_interface_json(&data, &[2, 3]);
+        let expected = format!(
+            r#"{{"data":[{},true],"shape":[2,3],"typestr":"<f4","version":3}}"#,
+            data.as_ptr() as usize
+        );
+        if cfg!(target_endian = "little") {
+            assert_eq!(json, expected);
+        }
+
+        assert_eq!(typestr::<u32>()[1..], *"u4");
+        assert_eq!(typestr::<i64>()[1..], *"i8");
+    }
+
+    #[test]
+    #[cfg(feature = "arrow")]
+    fn arrow_column_format() {
+        use arrow_array::{Array, Float32Array, StringArray};
+
+        let array = Float32Array::from(vec![Some(1.0), None, Some(3.0), Some(4.0)]);
+        let column = arrow_column(&array).unwrap();
+        let mask = array.nulls().unwrap().inner().sliced();
+        let expected = format!(
+            r#"{{"data":[{},true],"mask":{{"data":[{},true],"shape":[4],"typestr":"|t1","version":3}},"shape":[4],"typestr":"<f4","version":3}}"#,
+            array.values().as_ptr() as usize,
+            mask.as_ptr() as usize
+        );
+        if cfg!(target_endian = "little") {
+            assert_eq!(column.json, expected);
+        }
+
+        // slices refer to the offset values, and realign the validity bitmap
+        let sliced = array.slice(1, 3);
+        let column = arrow_column(&sliced).unwrap();
+        assert!(column.json.starts_with(&format!(
+            r#"{{"data":[{},true],"mask""#,
+            array.values()[1..].as_ptr() as usize
+        )));
+        assert_eq!(column._mask.as_ref().unwrap().as_slice()[0] & 0b111, 0b110);
+
+        // no mask without nulls
+        let array = Float32Array::from(vec![1.0, 2.0]);
+        assert!(!arrow_column(&array).unwrap().json.contains("mask"));
+
+        assert!(arrow_column(&StringArray::from(vec!["a"])).is_err());
+    }
+}
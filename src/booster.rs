@@ -5,14 +5,125 @@ use std::io::{self, BufRead, BufReader, Write};
 use std::os::raw;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use std::sync::{Mutex, MutexGuard, OnceLock};
 use std::{ffi, fmt, fs::File, ptr, slice};
 
 use indexmap::IndexMap;
 
 use super::XGBResult;
-use crate::parameters::{BoosterParameters, TrainingParameters};
+use crate::array_interface;
+use crate::callback::CallbackAction;
+use crate::model::{Tree, TreeModel};
+use crate::parameters::{
+    learning, BoosterParameters, BoosterType, EarlyStoppingParameters, EvaluationOutput, TrainingParameters,
+};
+
+/// Custom objective function used for training, as an alternative to XGBoost's built-in objectives.
+///
+/// Implemented for any closure or function of the form `Fn(&[f32], &DMatrix) -> (Vec<f32>, Vec<f32>)`, so
+/// parameterised objectives can capture their state (e.g. class weights), e.g.:
+///
+/// ```
+/// use xgb::DMatrix;
+///
+/// let gamma = 2.0;
+/// let focal_loss = move |preds: &[f32], dtrain: &DMatrix| {
+///     // compute gradient and hessian using gamma...
+///     # let _ = (gamma, dtrain);
+///     (vec![0.0; preds.len()], vec![1.0; preds.len()])
+/// };
+/// ```
+///
+/// Objectives for models with more than one output (e.g. multiclass or multi-target models) can use
+/// [`MultiOutputObjective`](struct.MultiOutputObjective.html) to also be given the shape of the predictions.
+///
+/// Objectives which need to update their state between rounds can use interior mutability (e.g.
+/// [`Cell`](std::cell::Cell)).
+pub trait CustomObjective {
+    /// Compute the first and second order gradients of the loss for each margin prediction in `margins`, which are
+    /// made for the rows of `dtrain`.
+    ///
+    /// `margins` are in row-major order, with `shape` (number of rows, number of model outputs). Returns a tuple of
+    /// (gradient, hessian), each of the same shape as `margins`.
+    fn gradient_hessian(&self, margins: &[f32], shape: (usize, usize), dtrain: &DMatrix) -> (Vec<f32>, Vec<f32>);
+}
+
+impl<F: Fn(&[f32], &DMatrix) -> (Vec<f32>, Vec<f32>)> CustomObjective for F {
+    fn gradient_hessian(&self, margins: &[f32], _shape: (usize, usize), dtrain: &DMatrix) -> (Vec<f32>, Vec<f32>) {
+        self(margins, dtrain)
+    }
+}
+
+/// Custom objective for models with more than one output, created from a closure or function of the form
+/// `Fn(&[f32], (usize, usize), &DMatrix) -> (Vec<f32>, Vec<f32>)` which is also given the shape of the margin
+/// predictions as (number of rows, number of model outputs).
+///
+/// # Example
+///
+/// Softmax objective for a multiclass model:
+///
+/// ```
+/// use xgb::{DMatrix, MultiOutputObjective};
+///
+/// let softmax_obj = MultiOutputObjective::new(|margins: &[f32], (num_rows, num_classes), dtrain: &DMatrix| {
+///     let labels = dtrain.get_labels().unwrap();
+///     let mut gradient = Vec::with_capacity(margins.len());
+///     let mut hessian = Vec::with_capacity(margins.len());
+///     for row in 0..num_rows {
+///         let row_margins = &margins[row * num_classes..(row + 1) * num_classes];
+///         let max = row_margins.iter().cloned().fold(f32::MIN, f32::max);
+///         let sum: f32 = row_margins.iter().map(|m| (m - max).exp()).sum();
+///         for (class, margin) in row_margins.iter().enumerate() {
+///             let p = (margin - max).exp() / sum;
+///             let target = if labels[row] as usize == class { 1.0 } else { 0.0 };
+///             gradient.push(p - target);
+///             hessian.push((2.0 * p * (1.0 - p)).max(1e-6));
+///         }
+///     }
+///     (gradient, hessian)
+/// });
+/// ```
+pub struct MultiOutputObjective<F>(F);
+
+impl<F: Fn(&[f32], (usize, usize), &DMatrix) -> (Vec<f32>, Vec<f32>)> MultiOutputObjective<F> {
+    /// Create a new objective from given function.
+    pub fn new(objective_fn: F) -> Self {
+        MultiOutputObjective(objective_fn)
+    }
+}
+
+impl<F: Fn(&[f32], (usize, usize), &DMatrix) -> (Vec<f32>, Vec<f32>)> CustomObjective for MultiOutputObjective<F> {
+    fn gradient_hessian(&self, margins: &[f32], shape: (usize, usize), dtrain: &DMatrix) -> (Vec<f32>, Vec<f32>) {
+        (self.0)(margins, shape, dtrain)
+    }
+}
+
+/// Custom evaluation metric reported during training, in addition to XGBoost's built-in metrics.
+///
+/// Implemented for any closure or function of the form `Fn(&[f32], &DMatrix) -> f32`, which is reported under the
+/// name `"custom"`.
+pub trait CustomEvaluation {
+    /// Compute the metric for margin predictions `preds`, which are made for the rows of `dmat`.
+    fn evaluate(&self, preds: &[f32], dmat: &DMatrix) -> f32;
+
+    /// Name under which the metric is reported in evaluation results.
+    fn name(&self) -> &str {
+        "custom"
+    }
+}
 
-pub type CustomObjective = fn(&[f32], &DMatrix) -> (Vec<f32>, Vec<f32>);
+impl<F: Fn(&[f32], &DMatrix) -> f32> CustomEvaluation for F {
+    fn evaluate(&self, preds: &[f32], dmat: &DMatrix) -> f32 {
+        self(preds, dmat)
+    }
+}
+
+/// Evaluation results of a single training round, as a map of evaluation set name -> (metric name -> score).
+pub type EvaluationResults = IndexMap<String, IndexMap<String, f32>>;
+
+/// Evaluation results recorded during training, as a map of evaluation set name -> (metric name -> score of each
+/// round).
+pub type EvaluationHistory = IndexMap<String, IndexMap<String, Vec<f32>>>;
 
 /// Used to control the return type of predictions made by C Booster API.
 enum PredictOption {
@@ -23,24 +134,54 @@ enum PredictOption {
     PredictInteractions,
 }
 
-#[derive(Default, Debug, Clone)]
+/// Kind of prediction to make, see [`PredictConfig`](struct.PredictConfig.html).
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PredictType {
+    /// Transformed predictions, e.g. probabilities for classification objectives.
     #[default]
     Normal = 0,
+    /// Untransformed margin predictions.
     OutputMargin = 1,
+    /// Feature contributions (SHAP values), with the bias term as the last feature.
     PredictContribitions = 2,
+    /// Approximate feature contributions, which are much faster to compute than exact SHAP values.
     PredictApproximateContributions = 3,
+    /// SHAP interaction values for each pair of features, with the bias terms as the last feature.
     PredictFeatureInteractions = 4,
+    /// Approximate SHAP interaction values.
     PredictApproximateFeatureInteractions = 5,
+    /// Index of the leaf each sample ends up in, for each tree.
     PredictLeafTraining = 6,
 }
 
-#[derive(Default)]
+/// Configuration of a prediction, used by [`Booster::predict_with`](struct.Booster.html#method.predict_with) and
+/// inplace predictions.
+///
+/// # Example
+///
+/// Approximate feature contributions of the first 10 boosting rounds:
+///
+/// ```
+/// use xgb::{PredictConfig, PredictType};
+///
+/// let config = PredictConfig {
+///     _type: PredictType::PredictApproximateContributions,
+///     iteration_end: 10,
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Default, Debug, Clone)]
 pub struct PredictConfig {
+    /// Kind of prediction to make.
     pub _type: PredictType,
+    /// Whether the prediction is part of training, e.g. to apply dropout of dart boosters.
     pub training: bool,
+    /// First boosting round to use.
     pub iteration_begin: i64,
+    /// Boosting round to stop at (exclusive), or 0 to use all rounds after `iteration_begin`.
     pub iteration_end: i64,
+    /// Whether to always return the full shape of the predictions, including dimensions of size 1 such as the number
+    /// of outputs of single output models.
     pub strict_shape: bool,
 }
 
@@ -49,15 +190,134 @@ impl PredictConfig {
     pub fn as_json(&self) -> String {
         format!(
             "{{\"type\":{},\"training\":{},\"iteration_begin\":{},\"iteration_end\":{},\"strict_shape\":{}}}\0",
-            self._type.clone() as usize,
+            self._type as usize, self.training, self.iteration_begin, self.iteration_end, self.strict_shape
+        )
+    }
+
+    /// Returns json of the config for inplace prediction, which also needs the value treated as missing.
+    fn as_inplace_json(&self, missing: f32) -> String {
+        format!(
+            "{{\"type\":{},\"training\":{},\"iteration_begin\":{},\"iteration_end\":{},\"strict_shape\":{},\"missing\":{},\"cache_id\":0}}",
+            self._type as usize,
             self.training,
             self.iteration_begin,
             self.iteration_end,
-            self.strict_shape
+            self.strict_shape,
+            crate::json_float(missing)
         )
     }
 }
 
+/// Type of feature importance score computed by
+/// [`Booster::feature_importance`](struct.Booster.html#method.feature_importance).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ImportanceType {
+    /// Number of times a feature is used to split the data across all trees, or the coefficients of a linear model.
+    #[default]
+    Weight,
+
+    /// Average gain of the splits which use the feature.
+    Gain,
+
+    /// Average coverage (number of samples affected) of the splits which use the feature.
+    Cover,
+
+    /// Total gain of the splits which use the feature.
+    TotalGain,
+
+    /// Total coverage of the splits which use the feature.
+    TotalCover,
+}
+
+impl fmt::Display for ImportanceType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            ImportanceType::Weight => "weight",
+            ImportanceType::Gain => "gain",
+            ImportanceType::Cover => "cover",
+            ImportanceType::TotalGain => "total_gain",
+            ImportanceType::TotalCover => "total_cover",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Format of model dumps produced by
+/// [`Booster::dump_model_format`](struct.Booster.html#method.dump_model_format).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum DumpFormat {
+    /// Indented text, with one line per node.
+    #[default]
+    Text,
+
+    /// JSON object for the root node, with nested `children`. Can be parsed by
+    /// [`Tree::from_json_dump`](model/struct.Tree.html#method.from_json_dump).
+    Json,
+
+    /// Graphviz dot graph, e.g. for rendering with `dot -Tpng`.
+    Dot,
+}
+
+impl fmt::Display for DumpFormat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            DumpFormat::Text => "text",
+            DumpFormat::Json => "json",
+            DumpFormat::Dot => "dot",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Feature importance scores as a map of feature name -> scores, see
+/// [`Booster::feature_importance`](struct.Booster.html#method.feature_importance).
+pub type FeatureImportance = IndexMap<String, Vec<f32>>;
+
+/// Predictions along with their shape, returned by [`Booster::predict_with`](struct.Booster.html#method.predict_with).
+///
+/// Values are in row-major order, with one row per sample. The shape depends on the kind of prediction and the model,
+/// e.g. (number of samples, number of classes) for transformed predictions of multiclass models, or (number of
+/// samples, number of features + 1) for feature contributions of single output models.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Predictions {
+    data: Vec<f32>,
+    shape: Vec<usize>,
+}
+
+impl Predictions {
+    /// Get the predicted values, in row-major order.
+    pub fn data(&self) -> &[f32] {
+        &self.data
+    }
+
+    /// Get the shape of the predictions, starting with the number of samples.
+    pub fn shape(&self) -> &[usize] {
+        &self.shape
+    }
+
+    /// Get the number of samples predictions were made for.
+    pub fn num_rows(&self) -> usize {
+        self.shape.first().copied().unwrap_or(0)
+    }
+
+    /// Get the predicted values for a single sample, or `None` if it's out of range.
+    pub fn row(&self, index: usize) -> Option<&[f32]> {
+        let row_len: usize = self.shape.iter().skip(1).product();
+        self.data.chunks(row_len.max(1)).nth(index)
+    }
+
+    /// Take the predicted values, in row-major order.
+    pub fn into_vec(self) -> Vec<f32> {
+        self.data
+    }
+
+    /// Convert the predictions into an `ndarray` array of the same shape.
+    #[cfg(feature = "ndarray")]
+    pub fn into_array(self) -> XGBResult<ndarray::ArrayD<f32>> {
+        ndarray::ArrayD::from_shape_vec(self.shape, self.data).map_err(|err| XGBError::new(err.to_string()))
+    }
+}
+
 impl PredictOption {
     /// Convert list of options into a bit mask.
     fn options_as_mask(options: &[PredictOption]) -> i32 {
@@ -86,8 +346,65 @@ impl PredictOption {
 /// [`new_with_cached_dmats`](struct.Booster.html#method.new_with_cached_dmats), then trained by calling
 /// [`update`](struct.Booster.html#method.update) or [`update_custom`](struct.Booster.html#method.update_custom)
 /// in a loop.
+///
+/// # Thread safety
+///
+/// A Booster can be moved to and shared between threads. Methods taking `&self` (predicting, saving, dumping and
+/// reading attributes or feature names) may be called concurrently, so a single model can serve predictions from
+/// many threads at once, e.g. behind an [`Arc`](https://doc.rust-lang.org/std/sync/struct.Arc.html). XGBoost only
+/// guarantees thread safe prediction for tree boosters outside of training mode, so the Booster serializes
+/// predictions of `gblinear` models, and predictions of `dart` models in training mode (which drop trees like
+/// training does, e.g. [`predict_margin`](struct.Booster.html#method.predict_margin)).
+///
+/// Methods which modify the model (training, evaluating, setting parameters, attributes or feature names) take
+/// `&mut self`, so they can't run while the model is being used by other threads. This includes
+/// [`evaluate`](struct.Booster.html#method.evaluate),
+/// [`set_feature_names`](struct.Booster.html#method.set_feature_names) and
+/// [`set_feature_info`](struct.Booster.html#method.set_feature_info), which took `&self` in earlier versions.
 pub struct Booster {
     handle: xgboost_sys::BoosterHandle,
+    gradient_booster: GradientBooster,
+    prediction_lock: Mutex<()>,
+    shape: OnceLock<ModelShape>,
+
+    /// Whether training on matrices with categorical features is allowed by the parameters this model was given.
+    /// Models loaded from files allow it, as the parameters they were created with aren't known.
+    enable_categorical: bool,
+}
+
+// SAFETY: the Booster owns its handle, which XGBoost allows to be used from any thread.
+unsafe impl Send for Booster {}
+
+// SAFETY: XGBoost's prediction, serialization and getter functions are thread safe for tree boosters, with results
+// written to thread local buffers which are copied before returning. The exceptions are predictions of linear
+// boosters, and predictions of dart boosters in training mode which pick the trees to drop in shared state, so both
+// are guarded by `prediction_lock`. Everything else mutating the learner requires `&mut self`.
+unsafe impl Sync for Booster {}
+
+/// Kind of gradient booster of a model, deciding which predictions can run concurrently.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum GradientBooster {
+    Tree,
+    Linear,
+    Dart,
+}
+
+impl GradientBooster {
+    fn from_name(name: &str) -> Self {
+        match name {
+            "gblinear" => GradientBooster::Linear,
+            "dart" => GradientBooster::Dart,
+            _ => GradientBooster::Tree,
+        }
+    }
+}
+
+/// Number of outputs and trees per round of a model, which are fixed once it has been trained.
+#[derive(Clone, Copy, Debug)]
+struct ModelShape {
+    num_outputs: usize,
+    /// Number of trees added by each boosting round, or `None` for linear models.
+    trees_per_round: Option<usize>,
 }
 
 impl Booster {
@@ -114,7 +431,7 @@ impl Booster {
             &mut handle
         ))?;
 
-        let mut booster = Booster { handle };
+        let mut booster = Booster::from_handle(handle);
         booster.set_params(params)?;
         Ok(booster)
     }
@@ -143,6 +460,57 @@ impl Booster {
         Ok(buffer)
     }
 
+    /// Get the full configuration of this model as JSON, including the resolved value of every parameter.
+    ///
+    /// The configuration isn't part of the model saved by [`save`](struct.Booster.html#method.save), use
+    /// [`BoosterParameters::from_config`](parameters/struct.BoosterParameters.html#method.from_config) to read the
+    /// parameters from it.
+    pub fn save_config(&self) -> XGBResult<String> {
+        let mut out_len = 0;
+        let mut out_str = ptr::null();
+        xgb_call!(xgboost_sys::XGBoosterSaveJsonConfig(
+            self.handle,
+            &mut out_len,
+            &mut out_str
+        ))?;
+        let config = unsafe { slice::from_raw_parts(out_str as *const u8, out_len as usize) };
+        Ok(String::from_utf8_lossy(config).into_owned())
+    }
+
+    /// Get the parameters this model was trained with, read from its
+    /// [`save_config`](struct.Booster.html#method.save_config) configuration.
+    ///
+    /// Unlike [`BoosterParameters::from_config`](parameters/struct.BoosterParameters.html#method.from_config), this
+    /// also enables categorical features for tree and dart boosters when the model has features of type `c` or
+    /// categorical splits.
+    pub fn params(&self) -> XGBResult<BoosterParameters> {
+        let mut params = BoosterParameters::from_config(&self.save_config()?)?;
+        let categorical = match params.booster_type() {
+            BoosterType::Linear(_) => false,
+            _ => {
+                self.get_feature_info("feature_type")?.iter().any(|t| t == "c")
+                    || self.tree_model()?.has_categorical_splits()
+            }
+        };
+        if categorical {
+            let mut booster_type = params.booster_type().clone();
+            booster_type.set_enable_categorical(true);
+            params.set_booster_type(booster_type);
+        }
+        Ok(params)
+    }
+
+    /// Set the configuration of this model from JSON returned by
+    /// [`save_config`](struct.Booster.html#method.save_config), e.g. to continue training a loaded model with the
+    /// parameters it was trained with.
+    pub fn load_config(&mut self, config: &str) -> XGBResult<()> {
+        let config = ffi::CString::new(config).map_err(|err| XGBError::new(err.to_string()))?;
+        xgb_call!(xgboost_sys::XGBoosterLoadJsonConfig(self.handle, config.as_ptr()))?;
+        self.gradient_booster = self.read_gradient_booster()?;
+        self.shape = OnceLock::new();
+        Ok(())
+    }
+
     /// Load a Booster from a binary file at given path.
     pub fn load<P: AsRef<Path>>(path: P) -> XGBResult<Self> {
         debug!("Loading Booster from: {}", path.as_ref().display());
@@ -155,8 +523,10 @@ impl Booster {
         let fname = crate::path_to_c_str(path);
         let mut handle = ptr::null_mut();
         xgb_call!(xgboost_sys::XGBoosterCreate(ptr::null(), 0, &mut handle))?;
+        let mut booster = Booster::from_handle(handle);
         xgb_call!(xgboost_sys::XGBoosterLoadModel(handle, fname.as_ptr()))?;
-        Ok(Booster { handle })
+        booster.gradient_booster = booster.read_gradient_booster()?;
+        Ok(booster)
     }
 
     /// Load a Booster directly from a buffer.
@@ -165,28 +535,143 @@ impl Booster {
 
         let mut handle = ptr::null_mut();
         xgb_call!(xgboost_sys::XGBoosterCreate(ptr::null(), 0, &mut handle))?;
+        let mut booster = Booster::from_handle(handle);
         xgb_call!(xgboost_sys::XGBoosterLoadModelFromBuffer(
             handle,
             bytes.as_ptr() as *const _,
             bytes.len() as u64
         ))?;
-        Ok(Booster { handle })
+        booster.gradient_booster = booster.read_gradient_booster()?;
+        Ok(booster)
+    }
+
+    /// Create a new model from a subset of this model's boosting rounds, keeping every `step`th round in the range
+    /// `begin..end`.
+    ///
+    /// An `end` of 0 means up to the last round. The new model keeps this model's parameters, feature names and
+    /// attributes, so e.g. the best model found by early stopping can be exported with:
+    ///
+    /// ```no_run
+    /// # use xgb::Booster;
+    /// # let booster = Booster::load("xgb.model").unwrap();
+    /// let best_iteration = booster.best_iteration().unwrap().unwrap();
+    /// let best = booster.slice(0, best_iteration + 1, 1).unwrap();
+    /// ```
+    ///
+    /// Fails if the range is empty or out of bounds, or for linear models, which don't have separate rounds.
+    pub fn slice(&self, begin: u32, end: u32, step: u32) -> XGBResult<Self> {
+        let num_rounds = self.num_boosted_rounds()?;
+        let slice_end = if end == 0 { num_rounds } else { end };
+        if step == 0 || begin >= slice_end || slice_end > num_rounds {
+            let msg = format!(
+                "Invalid slice {}..{} with step {} of model with {} boosting rounds",
+                begin, end, step, num_rounds
+            );
+            return Err(XGBError::new(msg));
+        }
+
+        let mut handle = ptr::null_mut();
+        let ret_val =
+            unsafe { xgboost_sys::XGBoosterSlice(self.handle, begin as i32, end as i32, step as i32, &mut handle) };
+        // XGBoost returns -2 when the range is out of bounds, which is checked above
+        XGBError::check_return_value(ret_val)?;
+        let mut booster = Booster::from_handle(handle);
+        booster.gradient_booster = self.gradient_booster;
+        booster.enable_categorical = self.enable_categorical;
+        Ok(booster)
+    }
+
+    /// Copy this model, along with its full configuration, through XGBoost's serialization format.
+    fn try_clone(&self) -> XGBResult<Self> {
+        let mut out_len = 0;
+        let mut out_buffer = ptr::null();
+        xgb_call!(xgboost_sys::XGBoosterSerializeToBuffer(
+            self.handle,
+            &mut out_len,
+            &mut out_buffer
+        ))?;
+
+        let mut handle = ptr::null_mut();
+        xgb_call!(xgboost_sys::XGBoosterCreate(ptr::null(), 0, &mut handle))?;
+        let mut booster = Booster::from_handle(handle);
+        xgb_call!(xgboost_sys::XGBoosterUnserializeFromBuffer(
+            handle,
+            out_buffer as *const _,
+            out_len
+        ))?;
+        booster.gradient_booster = self.gradient_booster;
+        booster.enable_categorical = self.enable_categorical;
+        Ok(booster)
+    }
+
+    fn from_handle(handle: xgboost_sys::BoosterHandle) -> Self {
+        Booster {
+            handle,
+            gradient_booster: GradientBooster::Tree,
+            prediction_lock: Mutex::new(()),
+            shape: OnceLock::new(),
+            enable_categorical: true,
+        }
+    }
+
+    /// Read which gradient booster this model uses from its configuration.
+    fn read_gradient_booster(&self) -> XGBResult<GradientBooster> {
+        let config = self.config()?;
+        Ok(GradientBooster::from_name(
+            config["learner"]["gradient_booster"]["name"]
+                .as_str()
+                .unwrap_or_default(),
+        ))
+    }
+
+    /// Get XGBoost's internal configuration of this model, with all parameters resolved.
+    fn config(&self) -> XGBResult<serde_json::Value> {
+        serde_json::from_str(&self.save_config()?)
+            .map_err(|err| XGBError::new(format!("Invalid model configuration: {}", err)))
+    }
+
+    /// Serialize predictions which XGBoost doesn't guarantee to be thread safe: all predictions of linear models,
+    /// and predictions of dart models in training mode.
+    ///
+    /// The returned guard must be held until the predictions have been copied out of XGBoost's buffers.
+    fn prediction_guard(&self, training: bool) -> Option<MutexGuard<'_, ()>> {
+        let serialize = match self.gradient_booster {
+            GradientBooster::Linear => true,
+            GradientBooster::Dart => training,
+            GradientBooster::Tree => false,
+        };
+        // a panic while holding the lock can't leave the unit value in an inconsistent state
+        serialize.then(|| self.prediction_lock.lock().unwrap_or_else(|err| err.into_inner()))
     }
 
     /// Convenience function for creating/training a new Booster.
     ///
     /// This does the following:
     ///
-    /// 1. create a new Booster model with given parameters
-    /// 2. train the model with given DMatrix
-    /// 3. print out evaluation results for each training round
+    /// 1. create a new Booster model with given parameters, storing the feature names and types of `dtrain` in it
+    /// 2. train the model with given DMatrix for one boosting iteration per round, using the custom objective
+    ///    function if one is set, or the learning objective from the booster parameters otherwise
+    /// 3. output evaluation results of the updated model for each training round, as configured by
+    ///    [`TrainingParameters::evaluation_output`](parameters/struct.TrainingParameters.html#method.evaluation_output)
     /// 4. return trained Booster
     ///
     /// * `params` - training parameters
     /// * `dtrain` - matrix to train Booster with
     /// * `num_boost_round` - number of training iterations
     /// * `eval_sets` - list of datasets to evaluate after each boosting round
+    ///
+    /// Use [`train_with_history`](struct.Booster.html#method.train_with_history) to also get the evaluation results
+    /// of each round.
     pub fn train(params: &TrainingParameters) -> XGBResult<Self> {
+        Booster::train_with_history(params).map(|(bst, _)| bst)
+    }
+
+    /// Create and train a new Booster in the same way as [`train`](struct.Booster.html#method.train), returning it
+    /// together with the evaluation results of every training round.
+    ///
+    /// The history maps each evaluation set name to metric names, which map to the metric's score in each round.
+    /// It's empty if no evaluation sets were given.
+    pub fn train_with_history(params: &TrainingParameters) -> XGBResult<(Self, EvaluationHistory)> {
         let cached_dmats = {
             let mut dmats = vec![params.dtrain];
             if let Some(eval_sets) = params.evaluation_sets {
@@ -197,43 +682,75 @@ impl Booster {
             dmats
         };
 
-        let bst = Booster::new_with_cached_dmats(&params.booster_params, &cached_dmats)?;
+        if params.early_stopping.is_some() && params.evaluation_sets.is_none_or(|eval_sets| eval_sets.is_empty()) {
+            return Err(XGBError::new("Early stopping requires at least one evaluation set"));
+        }
+
+        let mut bst = Booster::new_with_cached_dmats(&params.booster_params, &cached_dmats)?;
+        bst.set_feature_info_from(params.dtrain)?;
+        for callback in params.callbacks {
+            callback.before_training(&mut bst)?;
+        }
+
+        let mut early_stopping = params.early_stopping.as_ref().map(EarlyStopping::new);
+        let mut history = EvaluationHistory::new();
         for i in 0..params.boost_rounds as i32 {
+            let mut stop = false;
+            for callback in params.callbacks {
+                stop |= callback.before_iteration(&mut bst, i)? == CallbackAction::Stop;
+            }
+            if stop {
+                debug!("Stopping before iteration {}, requested by callback", i);
+                break;
+            }
+
+            if let Some(objective_fn) = params.custom_objective_fn {
+                bst.update_custom(params.dtrain, objective_fn)?;
+            } else {
+                bst.update(params.dtrain, i)?;
+            }
+
+            let mut dmat_eval_results = EvaluationResults::new();
             if let Some(eval_sets) = params.evaluation_sets {
-                let mut dmat_eval_results = bst.eval_set(eval_sets, i)?;
-
-                if let Some(eval_fn) = params.custom_evaluation_fn {
-                    let eval_name = "custom";
-                    for (dmat, dmat_name) in eval_sets {
-                        let margin = bst.predict_margin(dmat)?;
-                        let eval_result = eval_fn(&margin, dmat);
-                        let eval_results = dmat_eval_results
-                            .entry(eval_name.to_string())
-                            .or_insert_with(IndexMap::new);
-                        eval_results.insert(dmat_name.to_string(), eval_result);
-                    }
+                dmat_eval_results = bst.evaluate_round(eval_sets, i, params.custom_evaluation_fn)?;
+
+                match params.evaluation_output {
+                    EvaluationOutput::Stdout => println!("{}", format_eval_results(i, &dmat_eval_results)),
+                    EvaluationOutput::Log => info!("{}", format_eval_results(i, &dmat_eval_results)),
+                    EvaluationOutput::Silent => {}
                 }
 
-                // convert to map of eval_name -> (dmat_name -> score)
-                let mut eval_dmat_results = BTreeMap::new();
                 for (dmat_name, eval_results) in &dmat_eval_results {
+                    let dmat_history = history.entry(dmat_name.clone()).or_default();
                     for (eval_name, result) in eval_results {
-                        let dmat_results = eval_dmat_results.entry(eval_name).or_insert_with(BTreeMap::new);
-                        dmat_results.insert(dmat_name, result);
+                        dmat_history.entry(eval_name.clone()).or_default().push(*result);
                     }
                 }
 
-                print!("[{}]", i);
-                for (eval_name, dmat_results) in eval_dmat_results {
-                    for (dmat_name, result) in dmat_results {
-                        print!("\t{}-{}:{}", dmat_name, eval_name, result);
-                    }
+                if let Some(early_stopping) = &mut early_stopping {
+                    let default_data_name = eval_sets.last().unwrap().1;
+                    stop |= early_stopping.update(i, default_data_name, &dmat_eval_results)?;
                 }
-                println!();
             }
+
+            for callback in params.callbacks {
+                stop |= callback.after_iteration(&mut bst, i, &dmat_eval_results)? == CallbackAction::Stop;
+            }
+            if stop {
+                break;
+            }
+        }
+
+        if let Some((best_iteration, best_score)) = early_stopping.and_then(|early_stopping| early_stopping.best) {
+            bst.set_attribute("best_iteration", &best_iteration.to_string())?;
+            bst.set_attribute("best_score", &best_score.to_string())?;
         }
 
-        Ok(bst)
+        for callback in params.callbacks {
+            callback.after_training(&mut bst)?;
+        }
+
+        Ok((bst, history))
     }
 
     /// Update this Booster's parameters.
@@ -242,6 +759,7 @@ impl Booster {
             debug!("Setting parameter: {}={}", &key, &value);
             self.set_param(&key, &value)?;
         }
+        self.enable_categorical = p.booster_type().enable_categorical();
         Ok(())
     }
 
@@ -251,7 +769,13 @@ impl Booster {
     ///
     /// * `dtrain` - matrix to train the model with for a single iteration
     /// * `iteration` - current iteration number
+    ///
+    /// Returns an error if the feature names of `dtrain` don't match the ones stored in this model, or if it has
+    /// categorical features and this model's parameters don't enable them.
     pub fn update(&mut self, dtrain: &DMatrix, iteration: i32) -> XGBResult<()> {
+        self.validate_features(dtrain)?;
+        self.validate_categorical(dtrain)?;
+        let _iter_guards = DMatrix::iter_guards(&[dtrain]);
         xgb_call!(xgboost_sys::XGBoosterUpdateOneIter(
             self.handle,
             iteration,
@@ -260,10 +784,38 @@ impl Booster {
     }
 
     /// Update this model by training it for one round with a custom objective function.
-    pub fn update_custom(&mut self, dtrain: &DMatrix, objective_fn: CustomObjective) -> XGBResult<()> {
-        let pred = self.predict(dtrain)?;
-        let (gradient, hessian) = objective_fn(&pred.to_vec(), dtrain);
-        self.boost(dtrain, &gradient, &hessian)
+    ///
+    /// The objective is given this model's untransformed margin predictions for `dtrain`, in row-major order with
+    /// shape (number of rows, number of model outputs). The number of outputs is the number of classes for
+    /// multiclass models, the number of targets for multi-target models, or 1 otherwise. The objective must return
+    /// gradient and hessian arrays of the same shape.
+    pub fn update_custom<O: CustomObjective + ?Sized>(&mut self, dtrain: &DMatrix, objective_fn: &O) -> XGBResult<()> {
+        let config = PredictConfig {
+            _type: PredictType::OutputMargin,
+            training: true,
+            strict_shape: true,
+            ..Default::default()
+        };
+        let (margins, shape) = self.predict_matrix(dtrain, &config.as_json())?;
+        let shape = match shape[..] {
+            [num_rows, num_outputs] => (num_rows as usize, num_outputs as usize),
+            _ => {
+                let msg = format!("Expected margin predictions with 2 dimensions, got shape {:?}", shape);
+                return Err(XGBError::new(msg));
+            }
+        };
+
+        let (gradient, hessian) = objective_fn.gradient_hessian(&margins, shape, dtrain);
+        if gradient.len() != margins.len() {
+            let msg = format!(
+                "Custom objective returned {} gradients, expected one for each of the {}x{} margin predictions",
+                gradient.len(),
+                shape.0,
+                shape.1
+            );
+            return Err(XGBError::new(msg));
+        }
+        self.boost(dtrain, &gradient, &hessian, shape)
     }
 
     /// Update this model by directly specifying the first and second order gradients.
@@ -271,9 +823,19 @@ impl Booster {
     /// This is typically used instead of `update` when using a customised loss function.
     ///
     /// * `dtrain` - matrix to train the model with for a single iteration
-    /// * `gradient` - first order gradient
-    /// * `hessian` - second order gradient
-    fn boost(&mut self, dtrain: &DMatrix, gradient: &[f32], hessian: &[f32]) -> XGBResult<()> {
+    /// * `gradient` - first order gradient, in row-major order
+    /// * `hessian` - second order gradient, in row-major order
+    /// * `shape` - shape of the gradient and hessian arrays, as (number of rows in `dtrain`, number of model outputs)
+    ///
+    /// Returns an error if `shape` doesn't match the rows of `dtrain` and the
+    /// [number of outputs](struct.Booster.html#method.num_outputs) of this model, or the arrays don't match `shape`.
+    pub fn boost(
+        &mut self,
+        dtrain: &DMatrix,
+        gradient: &[f32],
+        hessian: &[f32],
+        shape: (usize, usize),
+    ) -> XGBResult<()> {
         if gradient.len() != hessian.len() {
             let msg = format!(
                 "Mismatch between length of gradient and hessian arrays ({} != {})",
@@ -282,25 +844,212 @@ impl Booster {
             );
             return Err(XGBError::new(msg));
         }
-        assert_eq!(gradient.len(), hessian.len());
+        let (num_rows, num_outputs) = shape;
+        if num_rows != dtrain.num_rows() || num_rows * num_outputs != gradient.len() {
+            let msg = format!(
+                "Gradient and hessian arrays of length {} don't match shape {}x{} for a matrix with {} rows",
+                gradient.len(),
+                num_rows,
+                num_outputs,
+                dtrain.num_rows()
+            );
+            return Err(XGBError::new(msg));
+        }
+        let model_outputs = self.num_outputs()?;
+        if num_outputs != model_outputs {
+            let msg = format!(
+                "Gradient and hessian arrays have {} columns, but the model has {} outputs",
+                num_outputs, model_outputs
+            );
+            return Err(XGBError::new(msg));
+        }
 
-        // TODO: _validate_feature_names
-        let mut grad_vec = gradient.to_vec();
-        let mut hess_vec = hessian.to_vec();
-        xgb_call!(xgboost_sys::XGBoosterBoostOneIter(
+        self.validate_features(dtrain)?;
+        self.validate_categorical(dtrain)?;
+        let iteration = self.num_boosted_rounds()? as i32;
+        let grad = array_interface::array_interface(gradient, &[num_rows, num_outputs]);
+        let hess = array_interface::array_interface(hessian, &[num_rows, num_outputs]);
+        let _iter_guards = DMatrix::iter_guards(&[dtrain]);
+        xgb_call!(xgboost_sys::XGBoosterTrainOneIter(
             self.handle,
             dtrain.handle,
-            grad_vec.as_mut_ptr(),
-            hess_vec.as_mut_ptr(),
-            grad_vec.len() as u64
+            iteration,
+            grad.as_ptr(),
+            hess.as_ptr()
         ))
     }
 
-    fn eval_set(
-        &self,
-        evals: &[(&DMatrix, &str)],
+    /// Get the number of boosting rounds this model has been trained for.
+    pub fn num_boosted_rounds(&self) -> XGBResult<u32> {
+        let mut out = 0;
+        xgb_call!(xgboost_sys::XGBoosterBoostedRounds(self.handle, &mut out))?;
+        Ok(out as u32)
+    }
+
+    /// Get the number of outputs of this model for each sample: the number of classes for multiclass models, the
+    /// number of targets for multi-target models, or 1 otherwise.
+    pub fn num_outputs(&self) -> XGBResult<usize> {
+        Ok(self.shape()?.num_outputs)
+    }
+
+    /// Get the number of trees in this model, across all boosting rounds and outputs.
+    fn num_trees(&self) -> XGBResult<usize> {
+        let trees_per_round = self
+            .shape()?
+            .trees_per_round
+            .ok_or_else(|| XGBError::new("Linear models don't have trees"))?;
+        Ok(self.num_boosted_rounds()? as usize * trees_per_round)
+    }
+
+    /// Get the number of outputs and trees per round of this model.
+    ///
+    /// Reading them from the model's configuration is slow, so they're cached once the model has been trained.
+    /// XGBoost only fixes them in the first round, e.g. the number of targets is taken from the training labels.
+    fn shape(&self) -> XGBResult<ModelShape> {
+        if let Some(shape) = self.shape.get() {
+            return Ok(*shape);
+        }
+
+        let config = self.config()?;
+        let model_param = &config["learner"]["learner_model_param"];
+        let num_class = config_usize(&model_param["num_class"])?;
+        let num_target = config_usize(&model_param["num_target"])?;
+        let num_rounds = self.num_boosted_rounds()? as usize;
+        let booster = &config["learner"]["gradient_booster"];
+        // dart boosters nest the tree model's configuration
+        let gbtree = if booster["name"] == "dart" { &booster["gbtree"] } else { booster };
+        let trees_per_round = match gbtree.get("gbtree_model_param") {
+            Some(param) => Some(config_usize(&param["num_trees"])? / num_rounds.max(1)),
+            None => None,
+        };
+        let shape = ModelShape {
+            num_outputs: num_class.max(num_target).max(1),
+            trees_per_round,
+        };
+        if num_rounds > 0 {
+            // another thread may have cached the same shape in the meantime
+            let _ = self.shape.set(shape);
+        }
+        Ok(shape)
+    }
+
+    /// Get the number of features this model was trained with, or 0 if it hasn't been trained yet.
+    pub fn num_features(&self) -> XGBResult<usize> {
+        let mut out = 0;
+        xgb_call!(xgboost_sys::XGBoosterGetNumFeature(self.handle, &mut out))?;
+        Ok(out as usize)
+    }
+
+    /// Check that given training matrix only has categorical features if this model's parameters enable them.
+    fn validate_categorical(&self, dtrain: &DMatrix) -> XGBResult<()> {
+        if !self.enable_categorical && dtrain.has_categorical_features()? {
+            return Err(XGBError::new(
+                "Training matrix has categorical features, which requires enable_categorical to be set in the tree \
+                 or dart booster parameters",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check that given matrix has the features this model was trained with.
+    ///
+    /// If this model has feature names, the matrix must have the same names in the same order, as its columns can't
+    /// be matched to the model's features otherwise. Matrices used with models without feature names can't have
+    /// more columns than the model has features.
+    fn validate_features(&self, dmat: &DMatrix) -> XGBResult<()> {
+        let feature_names = self.get_feature_names()?;
+        let dmat_feature_names = dmat.feature_names()?;
+        if !feature_names.is_empty() && dmat_feature_names.is_empty() {
+            return Err(XGBError::new(format!(
+                "Matrix has no feature names, but model expects features {:?}, set them with \
+                 DMatrix::set_feature_names",
+                feature_names
+            )));
+        }
+        if !feature_names.is_empty() {
+            if feature_names == dmat_feature_names {
+                return Ok(());
+            }
+
+            let missing: Vec<&String> = feature_names
+                .iter()
+                .filter(|name| !dmat_feature_names.contains(name))
+                .collect();
+            let unexpected: Vec<&String> = dmat_feature_names
+                .iter()
+                .filter(|name| !feature_names.contains(name))
+                .collect();
+            let msg = if missing.is_empty() && unexpected.is_empty() {
+                format!(
+                    "Feature names mismatch, matrix has features {:?} but model expects them in order {:?}",
+                    dmat_feature_names, feature_names
+                )
+            } else {
+                format!(
+                    "Feature names mismatch, matrix is missing features {:?} and has unexpected features {:?}",
+                    missing, unexpected
+                )
+            };
+            return Err(XGBError::new(msg));
+        }
+
+        let num_features = self.num_features()?;
+        if num_features > 0 && dmat.num_cols() > num_features {
+            let msg = format!(
+                "Matrix has {} columns, but model was trained with {} features",
+                dmat.num_cols(),
+                num_features
+            );
+            return Err(XGBError::new(msg));
+        }
+        Ok(())
+    }
+
+    /// Store the feature names and types of given matrix in this model, if it has any.
+    pub(crate) fn set_feature_info_from(&mut self, dmat: &DMatrix) -> XGBResult<()> {
+        let feature_names = dmat.feature_names()?;
+        if !feature_names.is_empty() {
+            let feature_names: Vec<&str> = feature_names.iter().map(|name| name.as_str()).collect();
+            self.set_feature_names(&feature_names)?;
+        }
+
+        let feature_types: Vec<String> = dmat.get_feature_types()?.iter().map(|t| t.to_string()).collect();
+        if !feature_types.is_empty() {
+            let feature_types: Vec<&str> = feature_types.iter().map(|t| t.as_str()).collect();
+            self.set_feature_info("feature_type", &feature_types)?;
+        }
+        Ok(())
+    }
+
+    /// Evaluate this model against each of the given matrices, using the metrics defined in this model's parameters
+    /// and the custom evaluation function if one is given (reported under its
+    /// [`name`](trait.CustomEvaluation.html#method.name)).
+    pub(crate) fn evaluate_round(
+        &mut self,
+        eval_sets: &[(&DMatrix, &str)],
         iteration: i32,
-    ) -> XGBResult<IndexMap<String, IndexMap<String, f32>>> {
+        custom_evaluation_fn: Option<&dyn CustomEvaluation>,
+    ) -> XGBResult<EvaluationResults> {
+        let mut dmat_eval_results = self.eval_set(eval_sets, iteration)?;
+
+        if let Some(eval_fn) = custom_evaluation_fn {
+            let eval_name = eval_fn.name();
+            for (dmat, dmat_name) in eval_sets {
+                let margin = self.predict_margin(dmat)?;
+                let eval_result = eval_fn.evaluate(&margin, dmat);
+                let eval_results = dmat_eval_results.entry(dmat_name.to_string()).or_default();
+                eval_results.insert(eval_name.to_string(), eval_result);
+            }
+        }
+
+        Ok(dmat_eval_results)
+    }
+
+    fn eval_set(&mut self, evals: &[(&DMatrix, &str)], iteration: i32) -> XGBResult<EvaluationResults> {
+        for (dmat, _) in evals {
+            self.validate_features(dmat)?;
+        }
+
         let (dmats, names) = {
             let mut dmats = Vec::with_capacity(evals.len());
             let mut names = Vec::with_capacity(evals.len());
@@ -328,6 +1077,7 @@ impl Booster {
         evptrs.shrink_to_fit();
 
         let mut out_result = ptr::null();
+        let _iter_guards = DMatrix::iter_guards(&evals.iter().map(|(dmat, _)| *dmat).collect::<Vec<_>>());
         xgb_call!(xgboost_sys::XGBoosterEvalOneIter(
             self.handle,
             iteration,
@@ -345,7 +1095,7 @@ impl Booster {
     /// See parameter::learning::EvaluationMetric for a full list.
     ///
     /// Returns a map of evaluation metric name to score.
-    pub fn evaluate(&self, dmat: &DMatrix) -> XGBResult<HashMap<String, f32>> {
+    pub fn evaluate(&mut self, dmat: &DMatrix) -> XGBResult<HashMap<String, f32>> {
         let name = "default";
         let mut eval = self.eval_set(&[(dmat, name)], 0)?;
         let mut result = HashMap::new();
@@ -384,6 +1134,35 @@ impl Booster {
         xgb_call!(xgboost_sys::XGBoosterSetAttr(self.handle, key.as_ptr(), value.as_ptr()))
     }
 
+    /// Get the best iteration found by early stopping during [`train`](struct.Booster.html#method.train), if any.
+    pub fn best_iteration(&self) -> XGBResult<Option<u32>> {
+        Ok(self
+            .get_attribute("best_iteration")?
+            .and_then(|best_iteration| best_iteration.parse().ok()))
+    }
+
+    /// Get the score of the watched metric at the best iteration found by early stopping during
+    /// [`train`](struct.Booster.html#method.train), if any.
+    pub fn best_score(&self) -> XGBResult<Option<f32>> {
+        Ok(self
+            .get_attribute("best_score")?
+            .and_then(|best_score| best_score.parse().ok()))
+    }
+
+    /// Get a prediction config which limits predictions to the trees up to and including the best iteration found
+    /// by early stopping, for use with [`predict_matrix`](struct.Booster.html#method.predict_matrix).
+    ///
+    /// If no best iteration is stored in this model, all trees are used.
+    pub fn best_iteration_config(&self) -> XGBResult<PredictConfig> {
+        let iteration_end = self
+            .best_iteration()?
+            .map_or(0, |best_iteration| best_iteration as i64 + 1);
+        Ok(PredictConfig {
+            iteration_end,
+            ..Default::default()
+        })
+    }
+
     /// Get names of all attributes stored in this model. Values can then be fetched with calls to `get_attribute`.
     pub fn get_attribute_names(&self) -> XGBResult<Vec<String>> {
         let mut out_len = 0;
@@ -430,21 +1209,18 @@ impl Booster {
     }
 
     /// Set names of features stored in this model.
-    pub fn set_feature_names(&self, features: &Vec<&str>) -> XGBResult<()> {
+    pub fn set_feature_names(&mut self, features: &Vec<&str>) -> XGBResult<()> {
         self.set_feature_info("feature_name", features)
     }
 
     /// Set names of features stored in this model.
     #[allow(clippy::unnecessary_cast)]
-    pub fn set_feature_info(&self, field: &str, features: &Vec<&str>) -> XGBResult<()> {
+    pub fn set_feature_info(&mut self, field: &str, features: &Vec<&str>) -> XGBResult<()> {
         let field: ffi::CString = ffi::CString::new(field).unwrap();
 
-        // We want zero terminated strings
+        // We want zero terminated strings, which XGBoost copies so they only need to outlive the call
         let c_temp_features: Vec<ffi::CString> = features.iter().map(|s| ffi::CString::new(*s).unwrap()).collect();
-        let mut c_feature_ptr: Vec<*const raw::c_char> = c_temp_features
-            .into_iter()
-            .map(|s| s.into_raw() as *const raw::c_char)
-            .collect();
+        let mut c_feature_ptr: Vec<*const raw::c_char> = c_temp_features.iter().map(|s| s.as_ptr()).collect();
 
         xgb_call!(xgboost_sys::XGBoosterSetStrFeatureInfo(
             self.handle,
@@ -458,7 +1234,11 @@ impl Booster {
     ///
     /// config_json should be a 0 terminated string, preferred created by PredictConfig::as_json
     /// Returns an array containing one entry per row in the given data and its shape as array.
+    ///
+    /// Like all predictions from a [`DMatrix`](struct.DMatrix.html), fails if the feature names of `dmat` don't match
+    /// the ones stored in this model (see [`DMatrix::set_feature_names`](struct.DMatrix.html#method.set_feature_names)).
     pub fn predict_matrix(&self, dmat: &DMatrix, config_json: &str) -> XGBResult<(Vec<f32>, Vec<u64>)> {
+        self.validate_features(dmat)?;
         let str_buffer: std::ffi::CString;
         let cfg = if !config_json.is_empty() && config_json.ends_with('\u{0}') {
             unsafe { std::ffi::CStr::from_ptr(config_json.as_ptr() as *const raw::c_char) }
@@ -466,9 +1246,13 @@ impl Booster {
             str_buffer = std::ffi::CString::new(config_json).unwrap();
             str_buffer.as_c_str()
         };
+        // only dart predictions in training mode need the config, to decide whether they're serialized
+        let training = self.gradient_booster == GradientBooster::Dart && config_training(cfg);
         let mut out_shape = ptr::null();
         let mut out_shape_dim = 0;
         let mut out_result = ptr::null();
+        let _iter_guards = DMatrix::iter_guards(&[dmat]);
+        let _guard = self.prediction_guard(training);
         xgb_call!(xgboost_sys::XGBoosterPredictFromDMatrix(
             self.handle,
             dmat.handle,
@@ -477,26 +1261,185 @@ impl Booster {
             &mut out_shape_dim,
             &mut out_result
         ))?;
-        assert!(!out_result.is_null());
-        let shape = unsafe { slice::from_raw_parts(out_shape, out_shape_dim as usize).to_vec() };
-        let mut data_size = 1;
-        for dim in &shape {
-            data_size *= dim;
+        Ok(Booster::prediction_output(out_shape, out_shape_dim, out_result))
+    }
+
+    /// Make any kind of prediction for given data, using the boosting rounds and training mode given by `config`.
+    ///
+    /// Unlike [`predict`](struct.Booster.html#method.predict) and the other prediction functions for a single kind
+    /// of prediction, which always use the full model, this can e.g. predict with the best iteration found by early
+    /// stopping (see [`best_iteration_config`](struct.Booster.html#method.best_iteration_config)).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use xgb::{Booster, DMatrix, PredictConfig, PredictType};
+    ///
+    /// let booster = Booster::load("xgb.model").unwrap();
+    /// let dmat = DMatrix::load(r#"{"uri": "test.txt?format=libsvm"}"#).unwrap();
+    /// let config = PredictConfig {
+    ///     _type: PredictType::PredictContribitions,
+    ///     iteration_end: 5,
+    ///     ..Default::default()
+    /// };
+    /// let contributions = booster.predict_with(&dmat, &config).unwrap();
+    /// println!("SHAP values of first sample: {:?}", contributions.row(0));
+    /// ```
+    pub fn predict_with(&self, dmat: &DMatrix, config: &PredictConfig) -> XGBResult<Predictions> {
+        let (data, shape) = self.predict_matrix(dmat, &config.as_json())?;
+        let shape = shape.iter().map(|dim| *dim as usize).collect();
+        Ok(Predictions { data, shape })
+    }
+
+    /// Predict results for a dense matrix in row-major order, without creating a [`DMatrix`](struct.DMatrix.html).
+    ///
+    /// This uses XGBoost's inplace prediction, which avoids copying the data and is much faster for small batches
+    /// (e.g. scoring a single row).
+    ///
+    /// * `data` - feature values, in row-major order
+    /// * `shape` - shape of `data` as (number of rows, number of columns)
+    /// * `missing` - value in `data` treated as missing, typically `f32::NAN`
+    /// * `config` - type of prediction to make, and which trees to use
+    ///
+    /// Returns predictions and their shape, in the same way as
+    /// [`predict_matrix`](struct.Booster.html#method.predict_matrix).
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use xgb::{Booster, PredictConfig};
+    ///
+    /// let booster = Booster::load("xgb.model").unwrap();
+    /// let row = [0.5, 1.0, 0.0];
+    /// let (preds, _shape) = booster
+    ///     .predict_inplace_dense(&row, (1, 3), f32::NAN, &PredictConfig::default())
+    ///     .unwrap();
+    /// ```
+    pub fn predict_inplace_dense(
+        &self,
+        data: &[f32],
+        shape: (usize, usize),
+        missing: f32,
+        config: &PredictConfig,
+    ) -> XGBResult<(Vec<f32>, Vec<u64>)> {
+        let (num_rows, num_cols) = shape;
+        if data.len() != num_rows * num_cols {
+            let msg = format!(
+                "Data of length {} doesn't match shape {}x{}",
+                data.len(),
+                num_rows,
+                num_cols
+            );
+            return Err(XGBError::new(msg));
+        }
+
+        let values = array_interface::array_interface(data, &[num_rows, num_cols]);
+        let training = config.training;
+        let config_json = ffi::CString::new(config.as_inplace_json(missing)).unwrap();
+        let mut out_shape = ptr::null();
+        let mut out_shape_dim = 0;
+        let mut out_result = ptr::null();
+        let _guard = self.prediction_guard(training);
+        xgb_call!(xgboost_sys::XGBoosterPredictFromDense(
+            self.handle,
+            values.as_ptr(),
+            config_json.as_ptr(),
+            ptr::null_mut(),
+            &mut out_shape,
+            &mut out_shape_dim,
+            &mut out_result
+        ))?;
+        Ok(Booster::prediction_output(out_shape, out_shape_dim, out_result))
+    }
+
+    /// Predict results for a sparse
+    /// [CSR](https://en.wikipedia.org/wiki/Sparse_matrix#Compressed_sparse_row_(CSR,_CRS_or_Yale_format)) matrix,
+    /// without creating a [`DMatrix`](struct.DMatrix.html).
+    ///
+    /// Uses the same CSR representation as [`DMatrix::from_csr`](struct.DMatrix.html#method.from_csr), and inplace
+    /// prediction in the same way as [`predict_inplace_dense`](struct.Booster.html#method.predict_inplace_dense).
+    ///
+    /// * `num_cols` - number of columns in the matrix
+    /// * `missing` - value in `data` treated as missing, in addition to values which aren't stored
+    pub fn predict_inplace_csr(
+        &self,
+        indptr: &[usize],
+        indices: &[usize],
+        data: &[f32],
+        num_cols: usize,
+        missing: f32,
+        config: &PredictConfig,
+    ) -> XGBResult<(Vec<f32>, Vec<u64>)> {
+        if indices.len() != data.len() || indptr.last() != Some(&data.len()) {
+            let msg = format!(
+                "Invalid CSR matrix: {} indices and {} values, with {} at the end of indptr",
+                indices.len(),
+                data.len(),
+                indptr.last().map_or("nothing".to_owned(), |last| last.to_string())
+            );
+            return Err(XGBError::new(msg));
         }
-        let data = unsafe { slice::from_raw_parts(out_result, data_size as usize).to_vec() };
 
-        Ok((data, shape))
+        let indptr = array_interface::array_interface(indptr, &[indptr.len()]);
+        let indices = array_interface::array_interface(indices, &[indices.len()]);
+        let values = array_interface::array_interface(data, &[data.len()]);
+        let training = config.training;
+        let config_json = ffi::CString::new(config.as_inplace_json(missing)).unwrap();
+        let mut out_shape = ptr::null();
+        let mut out_shape_dim = 0;
+        let mut out_result = ptr::null();
+        let _guard = self.prediction_guard(training);
+        xgb_call!(xgboost_sys::XGBoosterPredictFromCSR(
+            self.handle,
+            indptr.as_ptr(),
+            indices.as_ptr(),
+            values.as_ptr(),
+            num_cols as xgboost_sys::bst_ulong,
+            config_json.as_ptr(),
+            ptr::null_mut(),
+            &mut out_shape,
+            &mut out_shape_dim,
+            &mut out_result
+        ))?;
+        Ok(Booster::prediction_output(out_shape, out_shape_dim, out_result))
+    }
+
+    /// Copy the predictions and their shape out of the buffers returned by one of XGBoost's `XGBoosterPredictFrom*`
+    /// functions.
+    fn prediction_output(
+        out_shape: *const xgboost_sys::bst_ulong,
+        out_shape_dim: xgboost_sys::bst_ulong,
+        out_result: *const f32,
+    ) -> (Vec<f32>, Vec<u64>) {
+        let shape = unsafe { slice::from_raw_parts(out_shape, out_shape_dim as usize).to_vec() };
+        let data_size: u64 = shape.iter().product();
+        let data = Booster::prediction_vec(out_result, data_size as usize);
+
+        (data, shape)
+    }
+
+    /// Copy predictions out of the buffer returned by XGBoost, which may be null if there aren't any.
+    fn prediction_vec(out_result: *const f32, out_len: usize) -> Vec<f32> {
+        if out_len == 0 {
+            return Vec::new();
+        }
+        assert!(!out_result.is_null());
+        unsafe { slice::from_raw_parts(out_result, out_len).to_vec() }
     }
 
     /// Predict results for given data.
     ///
     /// Returns an array containing one entry per row in the given data.
-    /// Uses old call to XGBoosterPredict
+    /// Uses old call to XGBoosterPredict, with all boosting rounds. Use
+    /// [`predict_with`](struct.Booster.html#method.predict_with) to select the rounds to use.
     pub fn predict(&self, dmat: &DMatrix) -> XGBResult<Vec<f32>> {
+        self.validate_features(dmat)?;
         let option_mask = PredictOption::options_as_mask(&[]);
         let ntree_limit = 0;
         let mut out_len = 0;
         let mut out_result = ptr::null();
+        let _iter_guards = DMatrix::iter_guards(&[dmat]);
+        let _guard = self.prediction_guard(false);
         xgb_call!(xgboost_sys::XGBoosterPredict(
             self.handle,
             dmat.handle,
@@ -507,19 +1450,23 @@ impl Booster {
             &mut out_result
         ))?;
 
-        assert!(!out_result.is_null());
-        let data = unsafe { slice::from_raw_parts(out_result, out_len as usize).to_vec() };
+        let data = Booster::prediction_vec(out_result, out_len as usize);
         Ok(data)
     }
 
     /// Predict margin for given data.
     ///
-    /// Returns an array containing one entry per row in the given data.
+    /// Returns an array containing one entry per row in the given data. Uses all boosting rounds, see
+    /// [`predict_with`](struct.Booster.html#method.predict_with) with
+    /// [`PredictType::OutputMargin`](enum.PredictType.html#variant.OutputMargin) to select the rounds to use.
     pub fn predict_margin(&self, dmat: &DMatrix) -> XGBResult<Vec<f32>> {
+        self.validate_features(dmat)?;
         let option_mask = PredictOption::options_as_mask(&[PredictOption::OutputMargin]);
         let ntree_limit = 0;
         let mut out_len = 0;
         let mut out_result = ptr::null();
+        let _iter_guards = DMatrix::iter_guards(&[dmat]);
+        let _guard = self.prediction_guard(true);
         xgb_call!(xgboost_sys::XGBoosterPredict(
             self.handle,
             dmat.handle,
@@ -529,8 +1476,7 @@ impl Booster {
             &mut out_len,
             &mut out_result
         ))?;
-        assert!(!out_result.is_null());
-        let data = unsafe { slice::from_raw_parts(out_result, out_len as usize).to_vec() };
+        let data = Booster::prediction_vec(out_result, out_len as usize);
         Ok(data)
     }
 
@@ -539,11 +1485,18 @@ impl Booster {
     /// Returns an array of shape (number of samples, number of trees) as tuple of (data, num_rows).
     ///
     /// Note: the leaf index of a tree is unique per tree, so e.g. leaf 1 could be found in both tree 1 and tree 0.
+    ///
+    /// Uses all boosting rounds, see [`predict_with`](struct.Booster.html#method.predict_with) with
+    /// [`PredictType::PredictLeafTraining`](enum.PredictType.html#variant.PredictLeafTraining) to select the rounds to
+    /// use.
     pub fn predict_leaf(&self, dmat: &DMatrix) -> XGBResult<(Vec<f32>, (usize, usize))> {
+        self.validate_features(dmat)?;
         let option_mask = PredictOption::options_as_mask(&[PredictOption::PredictLeaf]);
         let ntree_limit = 0;
         let mut out_len = 0;
         let mut out_result = ptr::null();
+        let _iter_guards = DMatrix::iter_guards(&[dmat]);
+        let _guard = self.prediction_guard(false);
         xgb_call!(xgboost_sys::XGBoosterPredict(
             self.handle,
             dmat.handle,
@@ -553,11 +1506,12 @@ impl Booster {
             &mut out_len,
             &mut out_result
         ))?;
-        assert!(!out_result.is_null());
-
-        let data = unsafe { slice::from_raw_parts(out_result, out_len as usize).to_vec() };
+        let data = Booster::prediction_vec(out_result, out_len as usize);
         let num_rows = dmat.num_rows();
-        let num_cols = data.len() / num_rows;
+        let num_cols = match data.len().checked_div(num_rows) {
+            Some(num_cols) => num_cols,
+            None => self.num_trees()?,
+        };
         Ok((data, (num_rows, num_cols)))
     }
 
@@ -567,12 +1521,21 @@ impl Booster {
     /// prediction.
     ///
     /// Returns an array of shape (number of samples, number of features + 1) as a tuple of
-    /// (data, num_rows). The final column contains the bias term.
+    /// (data, num_rows). The final column contains the bias term. For models with several outputs (e.g. multiclass
+    /// models), each row contains the contributions for each output in turn, so has
+    /// [`num_outputs`](struct.Booster.html#method.num_outputs) * (number of features + 1) columns.
+    ///
+    /// Uses all boosting rounds, see [`predict_with`](struct.Booster.html#method.predict_with) with
+    /// [`PredictType::PredictContribitions`](enum.PredictType.html#variant.PredictContribitions) to select the rounds
+    /// to use, or `PredictType::PredictApproximateContributions` for faster approximate contributions.
     pub fn predict_contributions(&self, dmat: &DMatrix) -> XGBResult<(Vec<f32>, (usize, usize))> {
+        self.validate_features(dmat)?;
         let option_mask = PredictOption::options_as_mask(&[PredictOption::PredictContribitions]);
         let ntree_limit = 0;
         let mut out_len = 0;
         let mut out_result = ptr::null();
+        let _iter_guards = DMatrix::iter_guards(&[dmat]);
+        let _guard = self.prediction_guard(false);
         xgb_call!(xgboost_sys::XGBoosterPredict(
             self.handle,
             dmat.handle,
@@ -582,11 +1545,9 @@ impl Booster {
             &mut out_len,
             &mut out_result
         ))?;
-        assert!(!out_result.is_null());
-
-        let data = unsafe { slice::from_raw_parts(out_result, out_len as usize).to_vec() };
+        let data = Booster::prediction_vec(out_result, out_len as usize);
         let num_rows = dmat.num_rows();
-        let num_cols = data.len() / num_rows;
+        let num_cols = self.num_outputs()? * (self.num_features()? + 1);
         Ok((data, (num_rows, num_cols)))
     }
 
@@ -597,12 +1558,21 @@ impl Booster {
     /// untransformed margin value of the prediction.
     ///
     /// Returns an array of shape (number of samples, number of features + 1, number of features + 1).
-    /// The final row and column contain the bias terms.
+    /// The final row and column contain the bias terms. For models with several outputs, the matrices of each output
+    /// are stacked, giving a shape of (number of samples, [`num_outputs`](struct.Booster.html#method.num_outputs) *
+    /// (number of features + 1), number of features + 1).
+    ///
+    /// Uses all boosting rounds, see [`predict_with`](struct.Booster.html#method.predict_with) with
+    /// [`PredictType::PredictFeatureInteractions`](enum.PredictType.html#variant.PredictFeatureInteractions) to select
+    /// the rounds to use.
     pub fn predict_interactions(&self, dmat: &DMatrix) -> XGBResult<(Vec<f32>, (usize, usize, usize))> {
+        self.validate_features(dmat)?;
         let option_mask = PredictOption::options_as_mask(&[PredictOption::PredictInteractions]);
         let ntree_limit = 0;
         let mut out_len = 0;
         let mut out_result = ptr::null();
+        let _iter_guards = DMatrix::iter_guards(&[dmat]);
+        let _guard = self.prediction_guard(false);
         xgb_call!(xgboost_sys::XGBoosterPredict(
             self.handle,
             dmat.handle,
@@ -612,20 +1582,170 @@ impl Booster {
             &mut out_len,
             &mut out_result
         ))?;
-        assert!(!out_result.is_null());
-
-        let data = unsafe { slice::from_raw_parts(out_result, out_len as usize).to_vec() };
+        let data = Booster::prediction_vec(out_result, out_len as usize);
         let num_rows = dmat.num_rows();
+        let dim = self.num_features()? + 1;
+        Ok((data, (num_rows, self.num_outputs()? * dim, dim)))
+    }
+
+    /// Predict results for given data as an `ndarray` array, with the shape reported by XGBoost.
+    ///
+    /// The shape depends on the prediction type and model, see
+    /// [`PredictConfig::strict_shape`](struct.PredictConfig.html#structfield.strict_shape).
+    #[cfg(feature = "ndarray")]
+    pub fn predict_array(&self, dmat: &DMatrix, config: &PredictConfig) -> XGBResult<ndarray::ArrayD<f32>> {
+        self.predict_with(dmat, config)?.into_array()
+    }
+
+    /// Get predicted leaf index for each sample in given data, as an array of shape (number of samples, number of
+    /// trees).
+    #[cfg(feature = "ndarray")]
+    pub fn predict_leaf_array(&self, dmat: &DMatrix) -> XGBResult<ndarray::Array2<f32>> {
+        let config = PredictConfig {
+            _type: PredictType::PredictLeafTraining,
+            ..Default::default()
+        };
+        self.predict_array_with_dim(dmat, &config)
+    }
+
+    /// Get feature contributions (SHAP values) for each prediction, as an array of shape (number of samples, number
+    /// of features + 1). The final column contains the bias term.
+    ///
+    /// Returns an error for models with more than one output (e.g. multiclass models), use
+    /// [`predict_array`](struct.Booster.html#method.predict_array) for those instead.
+    #[cfg(feature = "ndarray")]
+    pub fn predict_contributions_array(&self, dmat: &DMatrix) -> XGBResult<ndarray::Array2<f32>> {
+        let config = PredictConfig {
+            _type: PredictType::PredictContribitions,
+            ..Default::default()
+        };
+        self.predict_array_with_dim(dmat, &config)
+    }
+
+    /// Get SHAP interaction values for each pair of features for each prediction, as an array of shape (number of
+    /// samples, number of features + 1, number of features + 1). The final row and column contain the bias terms.
+    ///
+    /// Returns an error for models with more than one output (e.g. multiclass models), use
+    /// [`predict_array`](struct.Booster.html#method.predict_array) for those instead.
+    #[cfg(feature = "ndarray")]
+    pub fn predict_interactions_array(&self, dmat: &DMatrix) -> XGBResult<ndarray::Array3<f32>> {
+        let config = PredictConfig {
+            _type: PredictType::PredictFeatureInteractions,
+            ..Default::default()
+        };
+        self.predict_array_with_dim(dmat, &config)
+    }
+
+    /// Predict results as an array with a fixed number of dimensions, returning an error if XGBoost reports a
+    /// different shape.
+    #[cfg(feature = "ndarray")]
+    fn predict_array_with_dim<D: ndarray::Dimension>(
+        &self,
+        dmat: &DMatrix,
+        config: &PredictConfig,
+    ) -> XGBResult<ndarray::Array<f32, D>> {
+        let array = self.predict_array(dmat, config)?;
+        let shape = array.shape().to_vec();
+        array.into_dimensionality::<D>().map_err(|_| {
+            XGBError::new(format!(
+                "Expected predictions with {} dimensions, got shape {:?}",
+                D::NDIM.unwrap_or_default(),
+                shape
+            ))
+        })
+    }
+
+    /// Get the importance of each feature used by this model.
+    ///
+    /// Features are named by the feature names stored in the model (see
+    /// [`get_feature_names`](struct.Booster.html#method.get_feature_names)), or `f0`, `f1`, ... if it has none. Tree
+    /// models only report features used by at least one split, with a single score each. Linear models only support
+    /// [`ImportanceType::Weight`](enum.ImportanceType.html#variant.Weight), and report the coefficients of every
+    /// feature, with one coefficient per class for multiclass models.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use xgb::{Booster, ImportanceType};
+    ///
+    /// let booster = Booster::load("xgb.model").unwrap();
+    /// for (feature, scores) in booster.feature_importance(ImportanceType::Gain).unwrap() {
+    ///     println!("{}: {}", feature, scores[0]);
+    /// }
+    /// ```
+    pub fn feature_importance(&self, importance_type: ImportanceType) -> XGBResult<FeatureImportance> {
+        let config = ffi::CString::new(format!("{{\"importance_type\":\"{}\"}}", importance_type)).unwrap();
+        let mut out_n_features = 0;
+        let mut out_features = ptr::null_mut();
+        let mut out_dim = 0;
+        let mut out_shape = ptr::null();
+        let mut out_scores = ptr::null();
+        xgb_call!(xgboost_sys::XGBoosterFeatureScore(
+            self.handle,
+            config.as_ptr(),
+            &mut out_n_features,
+            &mut out_features,
+            &mut out_dim,
+            &mut out_shape,
+            &mut out_scores
+        ))?;
 
-        let dim = ((data.len() / num_rows) as f64).sqrt() as usize;
-        Ok((data, (num_rows, dim, dim)))
+        let mut importance = FeatureImportance::new();
+        if out_n_features == 0 {
+            return Ok(importance);
+        }
+        let features = unsafe { slice::from_raw_parts(out_features, out_n_features as usize) };
+        let shape = unsafe { slice::from_raw_parts(out_shape, out_dim as usize) };
+        let num_scores: u64 = shape.iter().product();
+        let scores = unsafe { slice::from_raw_parts(out_scores, num_scores as usize) };
+        // scores have shape (number of features, number of outputs) for linear models
+        let scores_per_feature = (scores.len() / features.len()).max(1);
+        for (feature, scores) in features.iter().zip(scores.chunks(scores_per_feature)) {
+            let name = unsafe { ffi::CStr::from_ptr(*feature).to_str().unwrap().to_owned() };
+            importance.insert(name, scores.to_vec());
+        }
+        Ok(importance)
     }
 
     /// Get a dump of this model as a string.
     ///
     /// * `with_statistics` - whether to include statistics in output dump
     /// * `feature_map` - if given, map feature IDs to feature names from given map
+    ///
+    /// Splits on categorical features are dumped as the set of categories that take the `yes` branch, e.g.
+    /// `0:[f2:{1,3}] yes=2,no=1,missing=1`.
     pub fn dump_model(&self, with_statistics: bool, feature_map: Option<&FeatureMap>) -> XGBResult<String> {
+        Ok(self
+            .dump_model_format(DumpFormat::Text, with_statistics, feature_map)?
+            .join("\n"))
+    }
+
+    pub fn dump_model_vec(&self, with_statistics: bool) -> XGBResult<Vec<String>> {
+        self.dump_model_format(DumpFormat::Text, with_statistics, None)
+    }
+
+    /// Get a dump of each tree (or the weights of a linear model) in the given format.
+    ///
+    /// * `format` - format of each dump, see [`DumpFormat`](enum.DumpFormat.html)
+    /// * `with_statistics` - whether to include statistics (gain and cover) in output dump
+    /// * `feature_map` - if given, map feature IDs to feature names from given map
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use xgb::{Booster, DumpFormat};
+    ///
+    /// let booster = Booster::load("xgb.model").unwrap();
+    /// for (i, graph) in booster.dump_model_format(DumpFormat::Dot, false, None).unwrap().iter().enumerate() {
+    ///     std::fs::write(format!("tree-{}.dot", i), graph).unwrap();
+    /// }
+    /// ```
+    pub fn dump_model_format(
+        &self,
+        format: DumpFormat,
+        with_statistics: bool,
+        feature_map: Option<&FeatureMap>,
+    ) -> XGBResult<Vec<String>> {
         if let Some(fmap) = feature_map {
             let tmp_dir = match tempfile::tempdir() {
                 Ok(dir) => dir,
@@ -642,27 +1762,63 @@ impl Booster {
                 writeln!(file, "{}\t{}\t{}", feature_num, feature_name, feature_type).unwrap();
             }
 
-            self.dump_model_fmap(with_statistics, Some(&file_path))
+            self.dump_model_fmap_vec(format, with_statistics, Some(&file_path))
         } else {
-            self.dump_model_fmap(with_statistics, None)
+            self.dump_model_fmap_vec(format, with_statistics, None)
         }
     }
 
-    pub fn dump_model_vec(&self, with_statistics: bool) -> XGBResult<Vec<String>> {
-        self.dump_model_fmap_vec(with_statistics, None)
+    /// Get the trees of this model, parsed from its JSON dump with statistics.
+    ///
+    /// See [`model`](model/index.html) for walking the trees, and
+    /// [`tree_model`](struct.Booster.html#method.tree_model) for the whole ensemble including which output each tree
+    /// contributes to.
+    pub fn trees(&self) -> XGBResult<Vec<Tree>> {
+        let feature_names = self.get_feature_names()?;
+        self.dump_model_format(DumpFormat::Json, true, None)?
+            .iter()
+            .map(|dump| Tree::from_json_dump(dump, &feature_names))
+            .collect()
     }
 
-    fn dump_model_fmap(&self, with_statistics: bool, feature_map_path: Option<&PathBuf>) -> XGBResult<String> {
-        Ok(self.dump_model_fmap_vec(with_statistics, feature_map_path)?.join("\n"))
+    /// Get the tree ensemble of this model, parsed from the model saved as JSON.
+    ///
+    /// Only gbtree and dart models are supported.
+    pub fn tree_model(&self) -> XGBResult<TreeModel> {
+        TreeModel::from_json(&self.save_buffer(false)?)
+    }
+
+    /// Generate standalone Rust source code predicting with this model, see [`codegen`](codegen/index.html).
+    ///
+    /// The code is generated from the model saved as JSON rather than its dump, as the dump doesn't include the base
+    /// score or objective.
+    #[cfg(feature = "codegen")]
+    pub fn generate_rust(&self, style: crate::codegen::CodeStyle) -> XGBResult<String> {
+        let ensemble = crate::inference::Ensemble::from_json(&self.save_buffer(false)?)?;
+        Ok(crate::codegen::generate(&ensemble, style))
     }
 
-    fn dump_model_fmap_vec(&self, with_statistics: bool, feature_map_path: Option<&PathBuf>) -> XGBResult<Vec<String>> {
+    /// Export this model as a serialized ONNX model, see [`onnx`](onnx/index.html).
+    ///
+    /// Only gbtree and dart models without categorical splits are supported.
+    #[cfg(feature = "onnx")]
+    pub fn to_onnx(&self) -> XGBResult<Vec<u8>> {
+        let ensemble = crate::inference::Ensemble::from_json(&self.save_buffer(false)?)?;
+        crate::onnx::export(&ensemble)
+    }
+
+    fn dump_model_fmap_vec(
+        &self,
+        format: DumpFormat,
+        with_statistics: bool,
+        feature_map_path: Option<&PathBuf>,
+    ) -> XGBResult<Vec<String>> {
         let fmap = if let Some(path) = feature_map_path {
             crate::path_to_c_str(path)
         } else {
             ffi::CString::new("").unwrap()
         };
-        let format = ffi::CString::new("text").unwrap();
+        let format = ffi::CString::new(format.to_string()).unwrap();
         let mut out_len = 0;
         let mut out_dump_array = ptr::null_mut();
         xgb_call!(xgboost_sys::XGBoosterDumpModelEx(
@@ -689,13 +1845,20 @@ impl Booster {
     }
 
     pub fn set_param(&mut self, name: &str, value: &str) -> XGBResult<()> {
+        let gradient_booster = (name == "booster").then(|| GradientBooster::from_name(value));
         let name = ffi::CString::new(name).unwrap();
         let value = ffi::CString::new(value).unwrap();
         xgb_call!(xgboost_sys::XGBoosterSetParam(
             self.handle,
             name.as_ptr(),
             value.as_ptr()
-        ))
+        ))?;
+        if let Some(gradient_booster) = gradient_booster {
+            self.gradient_booster = gradient_booster;
+        }
+        // e.g. the number of classes may have changed
+        self.shape = OnceLock::new();
+        Ok(())
     }
 
     fn parse_eval_string(eval: &str, evnames: &[&str]) -> IndexMap<String, IndexMap<String, f32>> {
@@ -723,12 +1886,134 @@ impl Booster {
     }
 }
 
+/// Tracks the best round of training seen so far, to decide when to stop early.
+pub(crate) struct EarlyStopping<'p> {
+    params: &'p EarlyStoppingParameters,
+
+    /// (iteration, score) of the best round seen so far.
+    pub(crate) best: Option<(i32, f32)>,
+}
+
+impl<'p> EarlyStopping<'p> {
+    pub(crate) fn new(params: &'p EarlyStoppingParameters) -> Self {
+        EarlyStopping { params, best: None }
+    }
+
+    /// Record the evaluation results of given round, returning whether training should stop.
+    ///
+    /// The watched metric is looked up in the results of `default_data_name` unless the parameters name a different
+    /// evaluation set.
+    pub(crate) fn update(
+        &mut self,
+        iteration: i32,
+        default_data_name: &str,
+        eval_results: &EvaluationResults,
+    ) -> XGBResult<bool> {
+        let (metric_name, score) = self.score(default_data_name, eval_results)?;
+        let maximize = self
+            .params
+            .maximize()
+            .unwrap_or_else(|| learning::maximize_metric(metric_name));
+        let improved = match self.best {
+            Some((_, best_score)) if maximize => score > best_score,
+            Some((_, best_score)) => score < best_score,
+            None => true,
+        };
+        if improved {
+            self.best = Some((iteration, score));
+        }
+
+        let (best_iteration, _) = self.best.unwrap();
+        if iteration - best_iteration >= self.params.rounds() as i32 {
+            debug!(
+                "Stopping early at iteration {}, best iteration was {}",
+                iteration, best_iteration
+            );
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Find the name and score of the watched metric in the given evaluation results.
+    fn score<'r>(&self, default_data_name: &str, eval_results: &'r EvaluationResults) -> XGBResult<(&'r str, f32)> {
+        let data_name = self.params.data_name().unwrap_or(default_data_name);
+        let metrics = eval_results.get(data_name).ok_or_else(|| {
+            XGBError::new(format!(
+                "Evaluation set '{}' used for early stopping not found",
+                data_name
+            ))
+        })?;
+        let metric = match self.params.metric_name() {
+            Some(metric_name) => metrics.get_key_value(metric_name),
+            None => metrics.last(),
+        };
+        match metric {
+            Some((metric_name, score)) => Ok((metric_name, *score)),
+            None => Err(XGBError::new(format!(
+                "Metric '{}' used for early stopping not found for evaluation set '{}'",
+                self.params.metric_name().unwrap_or_default(),
+                data_name
+            ))),
+        }
+    }
+}
+
+/// Format evaluation results of a training round in the same way as XGBoost's command line output, e.g.
+/// `[0]\ttest-logloss:0.5\ttrain-logloss:0.25`.
+pub(crate) fn format_eval_results(iteration: i32, eval_results: &EvaluationResults) -> String {
+    // convert to map of eval_name -> (dmat_name -> score)
+    let mut eval_dmat_results = BTreeMap::new();
+    for (dmat_name, eval_results) in eval_results {
+        for (eval_name, result) in eval_results {
+            let dmat_results = eval_dmat_results.entry(eval_name).or_insert_with(BTreeMap::new);
+            dmat_results.insert(dmat_name, result);
+        }
+    }
+
+    let mut line = format!("[{}]", iteration);
+    for (eval_name, dmat_results) in eval_dmat_results {
+        for (dmat_name, result) in dmat_results {
+            line.push_str(&format!("\t{}-{}:{}", dmat_name, eval_name, result));
+        }
+    }
+    line
+}
+
+/// Copies the model along with its parameters, attributes and feature names, so the copy can be trained further
+/// independently.
+///
+/// # Panics
+///
+/// Panics if XGBoost fails to copy the model.
+impl Clone for Booster {
+    fn clone(&self) -> Self {
+        self.try_clone().expect("Failed to copy Booster")
+    }
+}
+
 impl Drop for Booster {
     fn drop(&mut self) {
         xgb_call!(xgboost_sys::XGBoosterFree(self.handle)).unwrap();
     }
 }
 
+/// Whether a prediction config given as JSON requests training mode. Configs which can't be parsed are treated as
+/// training mode to be safe, XGBoost rejects them anyway.
+fn config_training(config: &ffi::CStr) -> bool {
+    match serde_json::from_slice::<serde_json::Value>(config.to_bytes()) {
+        Ok(config) => config["training"] != false,
+        Err(_) => true,
+    }
+}
+
+/// Parse a number from XGBoost's model configuration, where they're stored as strings.
+fn config_usize(value: &serde_json::Value) -> XGBResult<usize> {
+    value
+        .as_str()
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| XGBError::new(format!("Invalid number in model configuration: {}", value)))
+}
+
 /// Maps a feature index to a name and type, used when dumping models as text.
 ///
 /// See [dump_model](struct.Booster.html#method.dump_model) for usage.
@@ -746,6 +2031,7 @@ impl FeatureMap {
     /// * `i` - binary feature
     /// * `q` - quantitative feature
     /// * `int` - integer features
+    /// * `c` - categorical features
     ///
     /// E.g.:
     /// ```text
@@ -796,7 +2082,9 @@ impl FeatureMap {
     }
 }
 
-/// Indicates the type of a feature, used when dumping models as text.
+/// Indicates the type of a feature, used when dumping models as text and to mark categorical features of a
+/// [`DMatrix`](struct.DMatrix.html).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum FeatureType {
     /// Binary indicator feature.
     Binary,
@@ -806,6 +2094,11 @@ pub enum FeatureType {
 
     /// Integer feature (when hinted, decision boundary will be integer).
     Integer,
+
+    /// Categorical feature, with values identifying each category. Split on natively by tree boosters with
+    /// [`enable_categorical`](parameters/tree/struct.TreeBoosterParametersBuilder.html#method.enable_categorical)
+    /// set.
+    Categorical,
 }
 
 impl FromStr for FeatureType {
@@ -816,8 +2109,9 @@ impl FromStr for FeatureType {
             "i" => Ok(FeatureType::Binary),
             "q" => Ok(FeatureType::Quantitative),
             "int" => Ok(FeatureType::Integer),
+            "c" => Ok(FeatureType::Categorical),
             _ => Err(format!(
-                "unrecognised feature type '{}', must be one of: 'i', 'q', 'int'",
+                "unrecognised feature type '{}', must be one of: 'i', 'q', 'int', 'c'",
                 s
             )),
         }
@@ -830,6 +2124,7 @@ impl fmt::Display for FeatureType {
             FeatureType::Binary => "i",
             FeatureType::Quantitative => "q",
             FeatureType::Integer => "int",
+            FeatureType::Categorical => "c",
         };
         write!(f, "{}", s)
     }
@@ -895,6 +2190,101 @@ mod tests {
         assert_eq!(attr, Some("bar".to_owned()));
     }
 
+    #[test]
+    fn clone() {
+        let dmat = read_train_matrix().expect("Reading train matrix failed");
+        let mut booster = Booster::new_with_cached_dmats(&BoosterParameters::default(), &[&dmat]).unwrap();
+        for i in 0..3 {
+            booster.update(&dmat, i).expect("update failed");
+        }
+        booster.set_attribute("foo", "bar").unwrap();
+
+        let mut copy = booster.clone();
+        assert_eq!(copy.get_attribute("foo").unwrap(), Some("bar".to_owned()));
+        assert_eq!(copy.predict(&dmat).unwrap(), booster.predict(&dmat).unwrap());
+
+        // training the copy leaves the original untouched
+        copy.update(&dmat, 3).expect("update failed");
+        assert_eq!(copy.num_boosted_rounds().unwrap(), 4);
+        assert_eq!(booster.num_boosted_rounds().unwrap(), 3);
+    }
+
+    #[test]
+    fn slice() {
+        let dmat = read_train_matrix().expect("Reading train matrix failed");
+        let mut booster = Booster::new_with_cached_dmats(&BoosterParameters::default(), &[&dmat]).unwrap();
+        for i in 0..10 {
+            booster.update(&dmat, i).expect("update failed");
+        }
+
+        let sliced = booster.slice(0, 5, 1).unwrap();
+        assert_eq!(sliced.num_boosted_rounds().unwrap(), 5);
+        let config = PredictConfig {
+            iteration_end: 5,
+            ..Default::default()
+        };
+        let (expected, _) = booster.predict_matrix(&dmat, &config.as_json()).unwrap();
+        assert_eq!(sliced.predict(&dmat).unwrap(), expected);
+
+        assert_eq!(booster.slice(2, 0, 2).unwrap().num_boosted_rounds().unwrap(), 4);
+        assert_eq!(booster.slice(0, 0, 1).unwrap().dump_model_vec(false).unwrap().len(), 10);
+
+        assert!(booster.slice(0, 11, 1).is_err());
+        assert!(booster.slice(5, 5, 1).is_err());
+        assert!(booster.slice(0, 5, 0).is_err());
+    }
+
+    #[test]
+    fn save_and_load_config() {
+        let dmat = read_train_matrix().expect("Reading train matrix failed");
+        let tree_params = tree::TreeBoosterParametersBuilder::default()
+            .eta(0.1)
+            .max_depth(3)
+            .build()
+            .unwrap();
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::BinaryLogistic)
+            .eval_metrics(learning::Metrics::Custom(vec![learning::EvaluationMetric::LogLoss]))
+            .seed(7)
+            .build()
+            .unwrap();
+        let params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Tree(tree_params))
+            .learning_params(learning_params)
+            .verbose(false)
+            .build()
+            .unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat]).unwrap();
+        booster.update(&dmat, 0).expect("update failed");
+
+        let config = booster.save_config().unwrap();
+        let config_params = BoosterParameters::from_config(&config).unwrap();
+        assert_eq!(
+            config_params.learning_params().objective().to_string(),
+            "binary:logistic"
+        );
+        assert_eq!(config_params.learning_params().seed(), 7);
+        match config_params.learning_params().eval_metrics() {
+            learning::Metrics::Custom(metrics) => {
+                let names: Vec<String> = metrics.iter().map(|metric| metric.to_string()).collect();
+                assert_eq!(names, vec!["logloss"]);
+            }
+            learning::Metrics::Auto => panic!("expected custom metrics"),
+        }
+        let pairs = config_params.as_string_pairs();
+        assert!(pairs.contains(&("booster".to_owned(), "gbtree".to_owned())));
+        assert!(pairs.contains(&("eta".to_owned(), "0.1".to_owned())));
+        assert!(pairs.contains(&("max_depth".to_owned(), "3".to_owned())));
+
+        let mut other = Booster::new(&BoosterParameters::default()).unwrap();
+        other.load_config(&config).unwrap();
+        let other_params = BoosterParameters::from_config(&other.save_config().unwrap()).unwrap();
+        assert_eq!(other_params.as_string_pairs(), pairs);
+
+        assert!(other.load_config("not json").is_err());
+        assert!(BoosterParameters::from_config("not json").is_err());
+    }
+
     #[test]
     fn get_attribute_names() {
         let mut booster = load_test_booster();
@@ -919,7 +2309,7 @@ mod tests {
 
     #[test]
     fn get_set_feature_names() {
-        let booster = load_test_booster();
+        let mut booster = load_test_booster();
         let attrs = booster.get_feature_names().expect("Getting features failed");
         assert_eq!(attrs, Vec::<String>::new());
         let mut expected = vec!["foo", "another", "4", "an even longer features name?"];
@@ -1016,7 +2406,7 @@ mod tests {
     }
 
     #[test]
-    fn predict_matrix() {
+    fn train() {
         let dmat_train =
             DMatrix::load(r#"{"uri": "xgboost-sys/xgboost/demo/data/agaricus.txt.train?format=libsvm"}"#).unwrap();
         let dmat_test =
@@ -1029,61 +2419,508 @@ mod tests {
             .unwrap();
         let learning_params = learning::LearningTaskParametersBuilder::default()
             .objective(learning::Objective::BinaryLogistic)
-            .eval_metrics(learning::Metrics::Custom(vec![
-                learning::EvaluationMetric::MAPCutNegative(4),
-                learning::EvaluationMetric::LogLoss,
-                learning::EvaluationMetric::BinaryErrorRate(0.5),
-            ]))
+            .eval_metrics(learning::Metrics::Custom(vec![learning::EvaluationMetric::LogLoss]))
             .build()
             .unwrap();
-        let params = parameters::BoosterParametersBuilder::default()
+        let booster_params = parameters::BoosterParametersBuilder::default()
             .booster_type(parameters::BoosterType::Tree(tree_params))
             .learning_params(learning_params)
             .verbose(false)
             .build()
             .unwrap();
-        let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat_train, &dmat_test]).unwrap();
 
-        for i in 0..10 {
-            booster.update(&dmat_train, i).expect("update failed");
-        }
+        let evaluation_sets = &[(&dmat_train, "train"), (&dmat_test, "test")];
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .dtrain(&dmat_train)
+            .booster_params(booster_params)
+            .boost_rounds(10)
+            .evaluation_sets(Some(evaluation_sets))
+            .evaluation_output(parameters::EvaluationOutput::Silent)
+            .build()
+            .unwrap();
+        let (mut booster, history) = Booster::train_with_history(&training_params).unwrap();
 
+        assert_eq!(booster.dump_model_vec(false).unwrap().len(), 10);
         let train_metrics = booster.evaluate(&dmat_train).unwrap();
         assert_eq!(*train_metrics.get("logloss").unwrap(), 0.006634271);
-        assert_eq!(*train_metrics.get("map@4-").unwrap(), 1.0);
 
-        let test_metrics = booster.evaluate(&dmat_test).unwrap();
-        let diff = *test_metrics.get("logloss").unwrap() - 0.0069199526;
-        assert_eq!(diff < 0.000001, diff > -0.000001);
-        assert_eq!(*test_metrics.get("map@4-").unwrap(), 1.0);
+        assert_eq!(history.keys().collect::<Vec<_>>(), vec!["train", "test"]);
+        let train_logloss = &history["train"]["logloss"];
+        assert_eq!(train_logloss.len(), 10);
+        assert_eq!(train_logloss[9], 0.006634271);
+        assert!(train_logloss[0] > train_logloss[9]);
+        assert_eq!(history["test"]["logloss"].len(), 10);
+    }
 
-        let single_matrix = dmat_test.slice(&[0]).unwrap();
-        let (v, shape) = booster
-            .predict_matrix(&single_matrix, &PredictConfig::default().as_json())
-            .unwrap();
-        assert_eq!(shape, vec![1]);
-        assert_eq!(v.len(), 1);
-        assert_eq!(v[0], 0.0050151693);
-        let cfg = PredictConfig::default();
-        let (v, shape) = booster.predict_matrix(&dmat_test, &cfg.as_json()).unwrap();
-        assert_eq!(v.len(), dmat_test.num_rows());
-        assert_eq!(shape, vec![1611]);
+    #[test]
+    fn train_custom_objective() {
+        let dmat_train =
+            DMatrix::load(r#"{"uri": "xgboost-sys/xgboost/demo/data/agaricus.txt.train?format=libsvm"}"#).unwrap();
 
-        // first 10 predictions
-        let expected_start = [
-            0.0050151693,
-            0.9884467,
-            0.0050151693,
-            0.0050151693,
-            0.026636455,
-            0.11789363,
-            0.9884467,
-            0.01231471,
-            0.9884467,
-            0.00013656063,
-        ];
+        fn log_reg_obj(preds: &[f32], dtrain: &DMatrix) -> (Vec<f32>, Vec<f32>) {
+            let labels = dtrain.get_labels().unwrap();
+            let preds: Vec<f32> = preds.iter().map(|x| 1.0 / (1.0 + (-x).exp())).collect();
+            let gradient = preds.iter().zip(labels).map(|(p, l)| p - l).collect();
+            let hessian = preds.iter().map(|p| p * (1.0 - p)).collect();
+            (gradient, hessian)
+        }
 
-        // last 10 predictions
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .dtrain(&dmat_train)
+            .boost_rounds(5)
+            .custom_objective_fn(Some(&log_reg_obj))
+            .build()
+            .unwrap();
+        let booster = Booster::train(&training_params).unwrap();
+        assert_eq!(booster.dump_model_vec(false).unwrap().len(), 5);
+    }
+
+    #[test]
+    fn train_custom_closures() {
+        let dmat_train =
+            DMatrix::load(r#"{"uri": "xgboost-sys/xgboost/demo/data/agaricus.txt.train?format=libsvm"}"#).unwrap();
+
+        // weighted logistic loss, with weights captured by the closure
+        let (neg_weight, pos_weight) = (1.0, 2.0);
+        let weighted_log_reg_obj = move |preds: &[f32], dtrain: &DMatrix| {
+            let labels = dtrain.get_labels().unwrap();
+            let mut gradient = Vec::with_capacity(preds.len());
+            let mut hessian = Vec::with_capacity(preds.len());
+            for (pred, label) in preds.iter().zip(labels) {
+                let pred = 1.0 / (1.0 + (-pred).exp());
+                let weight = if *label > 0.5 { pos_weight } else { neg_weight };
+                gradient.push(weight * (pred - label));
+                hessian.push(weight * pred * (1.0 - pred));
+            }
+            (gradient, hessian)
+        };
+
+        struct ErrorRate {
+            threshold: f32,
+        }
+
+        impl CustomEvaluation for ErrorRate {
+            fn evaluate(&self, preds: &[f32], dmat: &DMatrix) -> f32 {
+                let labels = dmat.get_labels().unwrap();
+                let num_incorrect = preds
+                    .iter()
+                    .zip(labels)
+                    .filter(|(pred, label)| (**pred > self.threshold) != (**label > 0.5))
+                    .count();
+                num_incorrect as f32 / labels.len() as f32
+            }
+
+            fn name(&self) -> &str {
+                "error_rate"
+            }
+        }
+        let error_rate = ErrorRate { threshold: 0.0 };
+
+        let evaluation_sets = &[(&dmat_train, "train")];
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .dtrain(&dmat_train)
+            .boost_rounds(5)
+            .evaluation_sets(Some(evaluation_sets))
+            .evaluation_output(parameters::EvaluationOutput::Silent)
+            .custom_objective_fn(Some(&weighted_log_reg_obj))
+            .custom_evaluation_fn(Some(&error_rate))
+            .build()
+            .unwrap();
+        let (booster, history) = Booster::train_with_history(&training_params).unwrap();
+        assert_eq!(booster.dump_model_vec(false).unwrap().len(), 5);
+
+        let errors = &history["train"]["error_rate"];
+        assert_eq!(errors.len(), 5);
+        assert!(errors[4] < 0.1);
+    }
+
+    #[test]
+    fn train_custom_multi_output() {
+        let num_rows = 30;
+        let num_classes = 3;
+        let data: Vec<f32> = (0..num_rows)
+            .flat_map(|i| [(i % num_classes) as f32, i as f32])
+            .collect();
+        let labels: Vec<f32> = (0..num_rows).map(|i| (i % num_classes) as f32).collect();
+        let mut dmat = DMatrix::from_dense(&data, num_rows).unwrap();
+        dmat.set_labels(&labels).unwrap();
+
+        let softmax_obj = MultiOutputObjective::new(|margins: &[f32], shape: (usize, usize), dtrain: &DMatrix| {
+            let (num_rows, num_classes) = shape;
+            assert_eq!(num_rows, dtrain.num_rows());
+            assert_eq!(margins.len(), num_rows * num_classes);
+
+            let labels = dtrain.get_labels().unwrap();
+            let mut gradient = Vec::with_capacity(margins.len());
+            let mut hessian = Vec::with_capacity(margins.len());
+            for (row, row_margins) in margins.chunks(num_classes).enumerate() {
+                let max = row_margins.iter().cloned().fold(f32::MIN, f32::max);
+                let sum: f32 = row_margins.iter().map(|m| (m - max).exp()).sum();
+                for (class, margin) in row_margins.iter().enumerate() {
+                    let p = (margin - max).exp() / sum;
+                    let target = if labels[row] as usize == class { 1.0 } else { 0.0 };
+                    gradient.push(p - target);
+                    hessian.push((2.0 * p * (1.0 - p)).max(1e-6));
+                }
+            }
+            (gradient, hessian)
+        });
+
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::MultiSoftprob(num_classes as u32))
+            .build()
+            .unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .learning_params(learning_params)
+            .verbose(false)
+            .build()
+            .unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .dtrain(&dmat)
+            .booster_params(booster_params)
+            .boost_rounds(4)
+            .custom_objective_fn(Some(&softmax_obj))
+            .build()
+            .unwrap();
+        let mut booster = Booster::train(&training_params).unwrap();
+
+        // one tree per class per round
+        assert_eq!(booster.dump_model_vec(false).unwrap().len(), 4 * num_classes);
+
+        let preds = booster.predict(&dmat).unwrap();
+        assert_eq!(preds.len(), num_rows * num_classes);
+        for (row, row_preds) in preds.chunks(num_classes).enumerate() {
+            let predicted = (0..num_classes)
+                .max_by(|a, b| row_preds[*a].partial_cmp(&row_preds[*b]).unwrap())
+                .unwrap();
+            assert_eq!(predicted, row % num_classes);
+        }
+
+        // gradients must match the shape of the model's outputs
+        let gradient = vec![0.0; num_rows];
+        let hessian = vec![1.0; num_rows];
+        let err = booster.boost(&dmat, &gradient, &hessian, (num_rows, 1)).unwrap_err();
+        assert!(err.to_string().contains("model has 3 outputs"), "{}", err);
+        assert!(booster
+            .boost(&dmat, &gradient, &hessian, (num_rows, num_classes))
+            .is_err());
+    }
+
+    #[test]
+    fn train_early_stopping() {
+        let dmat_train =
+            DMatrix::load(r#"{"uri": "xgboost-sys/xgboost/demo/data/agaricus.txt.train?format=libsvm"}"#).unwrap();
+        let dmat_test =
+            DMatrix::load(r#"{"uri": "xgboost-sys/xgboost/demo/data/agaricus.txt.test?format=libsvm"}"#).unwrap();
+
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::BinaryLogistic)
+            .eval_metrics(learning::Metrics::Custom(vec![
+                learning::EvaluationMetric::AUC,
+                learning::EvaluationMetric::LogLoss,
+            ]))
+            .build()
+            .unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .learning_params(learning_params)
+            .verbose(false)
+            .build()
+            .unwrap();
+        let early_stopping = parameters::EarlyStoppingParametersBuilder::default()
+            .rounds(3)
+            .metric_name(Some("auc".to_owned()))
+            .build()
+            .unwrap();
+
+        let evaluation_sets = &[(&dmat_train, "train"), (&dmat_test, "test")];
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .dtrain(&dmat_train)
+            .booster_params(booster_params)
+            .boost_rounds(100)
+            .evaluation_sets(Some(evaluation_sets))
+            .early_stopping(Some(early_stopping))
+            .build()
+            .unwrap();
+        let booster = Booster::train(&training_params).unwrap();
+
+        // training stops once AUC on the test set hasn't improved for 3 rounds
+        let best_iteration = booster.best_iteration().unwrap().unwrap();
+        let num_trees = booster.dump_model_vec(false).unwrap().len() as u32;
+        assert_eq!(num_trees, best_iteration + 4);
+        assert!(booster.best_score().unwrap().is_some());
+
+        let (preds, _) = booster
+            .predict_matrix(&dmat_test, &booster.best_iteration_config().unwrap().as_json())
+            .unwrap();
+        assert_eq!(preds.len(), dmat_test.num_rows());
+    }
+
+    #[test]
+    fn train_early_stopping_without_evaluation_sets() {
+        let dmat_train =
+            DMatrix::load(r#"{"uri": "xgboost-sys/xgboost/demo/data/agaricus.txt.train?format=libsvm"}"#).unwrap();
+        let early_stopping = parameters::EarlyStoppingParametersBuilder::default()
+            .rounds(3)
+            .build()
+            .unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .dtrain(&dmat_train)
+            .early_stopping(Some(early_stopping))
+            .build()
+            .unwrap();
+        assert!(Booster::train(&training_params).is_err());
+    }
+
+    #[test]
+    fn train_callbacks() {
+        use crate::callback::{Checkpoint, LearningRateScheduler, TrainingCallback};
+        use std::cell::Cell;
+
+        struct StopAfter {
+            rounds: i32,
+            calls: Cell<u32>,
+        }
+
+        impl TrainingCallback for StopAfter {
+            fn after_iteration(
+                &self,
+                _booster: &mut Booster,
+                iteration: i32,
+                eval_results: &EvaluationResults,
+            ) -> XGBResult<CallbackAction> {
+                assert!(eval_results["train"].contains_key("rmse"));
+                self.calls.set(self.calls.get() + 1);
+                if iteration + 1 >= self.rounds {
+                    Ok(CallbackAction::Stop)
+                } else {
+                    Ok(CallbackAction::Continue)
+                }
+            }
+        }
+
+        let dmat_train =
+            DMatrix::load(r#"{"uri": "xgboost-sys/xgboost/demo/data/agaricus.txt.train?format=libsvm"}"#).unwrap();
+        let tmp_dir = tempfile::tempdir().expect("failed to create temp dir");
+
+        let stop_after = StopAfter {
+            rounds: 3,
+            calls: Cell::new(0),
+        };
+        let checkpoint = Checkpoint::new(tmp_dir.path(), 2);
+        let scheduler = LearningRateScheduler::new(|iteration| 0.3 / (iteration + 1) as f32);
+        let callbacks: &[&dyn TrainingCallback] = &[&scheduler, &checkpoint, &stop_after];
+
+        let evaluation_sets = &[(&dmat_train, "train")];
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .dtrain(&dmat_train)
+            .boost_rounds(10)
+            .evaluation_sets(Some(evaluation_sets))
+            .evaluation_output(parameters::EvaluationOutput::Silent)
+            .callbacks(callbacks)
+            .build()
+            .unwrap();
+        let booster = Booster::train(&training_params).unwrap();
+
+        assert_eq!(stop_after.calls.get(), 3);
+        assert_eq!(booster.dump_model_vec(false).unwrap().len(), 3);
+        assert!(!checkpoint.path(0).exists());
+        assert!(checkpoint.path(1).exists());
+        assert!(!checkpoint.path(2).exists());
+    }
+
+    #[test]
+    fn train_feature_names() {
+        let data = [1.0, 0.0, 2.0, 0.0, 1.0, 3.0, 1.0, 1.0, 4.0, 0.0, 0.0, 5.0];
+        let mut dmat_train = DMatrix::from_dense(&data, 4).unwrap();
+        dmat_train.set_labels(&[1.0, 0.0, 1.0, 0.0]).unwrap();
+        dmat_train.set_feature_names(&["a", "b", "c"]).unwrap();
+
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .dtrain(&dmat_train)
+            .boost_rounds(2)
+            .build()
+            .unwrap();
+        let booster = Booster::train(&training_params).unwrap();
+        assert_eq!(booster.get_feature_names().unwrap(), vec!["a", "b", "c"]);
+        assert_eq!(booster.predict(&dmat_train).unwrap().len(), 4);
+
+        // matrices without feature names can't be matched to the model's features
+        let dmat = DMatrix::from_dense(&data, 4).unwrap();
+        let err = booster.predict(&dmat).unwrap_err();
+        assert!(err.to_string().contains("no feature names"), "{}", err);
+        assert!(booster.predict_margin(&dmat).is_err());
+
+        // models without feature names only check the number of columns
+        let mut unnamed_train = DMatrix::from_dense(&data, 4).unwrap();
+        unnamed_train.set_labels(&[1.0, 0.0, 1.0, 0.0]).unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .dtrain(&unnamed_train)
+            .boost_rounds(2)
+            .build()
+            .unwrap();
+        let unnamed = Booster::train(&training_params).unwrap();
+        assert!(unnamed.get_feature_names().unwrap().is_empty());
+        assert_eq!(unnamed.predict(&dmat).unwrap().len(), 4);
+        let dmat = DMatrix::from_dense(&data, 3).unwrap();
+        assert!(unnamed.predict(&dmat).is_err());
+
+        let mut dmat = DMatrix::from_dense(&data, 4).unwrap();
+        dmat.set_feature_names(&["b", "a", "c"]).unwrap();
+        let err = booster.predict(&dmat).unwrap_err();
+        assert!(err.to_string().contains("in order"), "{}", err);
+        assert!(booster
+            .predict_matrix(&dmat, &PredictConfig::default().as_json())
+            .is_err());
+
+        dmat.set_feature_names(&["a", "b", "d"]).unwrap();
+        let err = booster.predict(&dmat).unwrap_err();
+        assert!(err.to_string().contains("missing features [\"c\"]"), "{}", err);
+    }
+
+    #[test]
+    fn train_categorical() {
+        // label is 1 for categories 1 and 3 of the first feature
+        let mut data = Vec::new();
+        let mut labels = Vec::new();
+        for i in 0..60 {
+            let category = (i % 6) as f32;
+            data.extend_from_slice(&[category, (i % 7) as f32]);
+            labels.push(if category == 1.0 || category == 3.0 { 1.0 } else { 0.0 });
+        }
+        let mut dmat_train = DMatrix::from_dense(&data, labels.len()).unwrap();
+        dmat_train.set_labels(&labels).unwrap();
+        dmat_train
+            .set_feature_types(&[FeatureType::Categorical, FeatureType::Quantitative])
+            .unwrap();
+
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .dtrain(&dmat_train)
+            .boost_rounds(2)
+            .build()
+            .unwrap();
+        assert!(Booster::train(&training_params).is_err());
+
+        let tree_params = tree::TreeBoosterParametersBuilder::default()
+            .tree_method(tree::TreeMethod::Hist)
+            .enable_categorical(true)
+            .max_cat_to_onehot(1)
+            .max_depth(1)
+            .build()
+            .unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Tree(tree_params))
+            .build()
+            .unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .dtrain(&dmat_train)
+            .booster_params(booster_params)
+            .boost_rounds(2)
+            .build()
+            .unwrap();
+        let booster = Booster::train(&training_params).unwrap();
+
+        // categorical splits are dumped as the set of categories going down one branch
+        let dump = booster.dump_model(false, None).unwrap();
+        assert!(dump.starts_with("0:[f0:{"), "{}", dump);
+
+        // parameters read back from the model allow training on categorical features again
+        let params = booster.params().unwrap();
+        assert!(params.booster_type().enable_categorical());
+        let config_params = parameters::BoosterParameters::from_config(&booster.save_config().unwrap()).unwrap();
+        assert!(!config_params.booster_type().enable_categorical());
+        let mut retrained = Booster::new_with_cached_dmats(&params, &[&dmat_train]).unwrap();
+        retrained.update(&dmat_train, 0).unwrap();
+
+        // every training entry point applies the same rule
+        let mut booster = Booster::new(&parameters::BoosterParameters::default()).unwrap();
+        assert!(booster.update(&dmat_train, 0).is_err());
+        let (gradient, hessian) = (vec![0.5; labels.len()], vec![1.0; labels.len()]);
+        assert!(booster
+            .boost(&dmat_train, &gradient, &hessian, (labels.len(), 1))
+            .is_err());
+
+        let dart_params = parameters::dart::DartBoosterParametersBuilder::default()
+            .enable_categorical(true)
+            .build()
+            .unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Dart(dart_params))
+            .build()
+            .unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&booster_params, &[&dmat_train]).unwrap();
+        booster.update(&dmat_train, 0).unwrap();
+        assert_eq!(booster.num_boosted_rounds().unwrap(), 1);
+    }
+
+    #[test]
+    fn predict_matrix() {
+        let dmat_train =
+            DMatrix::load(r#"{"uri": "xgboost-sys/xgboost/demo/data/agaricus.txt.train?format=libsvm"}"#).unwrap();
+        let dmat_test =
+            DMatrix::load(r#"{"uri": "xgboost-sys/xgboost/demo/data/agaricus.txt.test?format=libsvm"}"#).unwrap();
+
+        let tree_params = tree::TreeBoosterParametersBuilder::default()
+            .max_depth(2)
+            .eta(1.0)
+            .build()
+            .unwrap();
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::BinaryLogistic)
+            .eval_metrics(learning::Metrics::Custom(vec![
+                learning::EvaluationMetric::MAPCutNegative(4),
+                learning::EvaluationMetric::LogLoss,
+                learning::EvaluationMetric::BinaryErrorRate(0.5),
+            ]))
+            .build()
+            .unwrap();
+        let params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Tree(tree_params))
+            .learning_params(learning_params)
+            .verbose(false)
+            .build()
+            .unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat_train, &dmat_test]).unwrap();
+
+        for i in 0..10 {
+            booster.update(&dmat_train, i).expect("update failed");
+        }
+
+        let train_metrics = booster.evaluate(&dmat_train).unwrap();
+        assert_eq!(*train_metrics.get("logloss").unwrap(), 0.006634271);
+        assert_eq!(*train_metrics.get("map@4-").unwrap(), 1.0);
+
+        let test_metrics = booster.evaluate(&dmat_test).unwrap();
+        let diff = *test_metrics.get("logloss").unwrap() - 0.0069199526;
+        assert_eq!(diff < 0.000001, diff > -0.000001);
+        assert_eq!(*test_metrics.get("map@4-").unwrap(), 1.0);
+
+        let single_matrix = dmat_test.slice(&[0]).unwrap();
+        let (v, shape) = booster
+            .predict_matrix(&single_matrix, &PredictConfig::default().as_json())
+            .unwrap();
+        assert_eq!(shape, vec![1]);
+        assert_eq!(v.len(), 1);
+        assert_eq!(v[0], 0.0050151693);
+        let cfg = PredictConfig::default();
+        let (v, shape) = booster.predict_matrix(&dmat_test, &cfg.as_json()).unwrap();
+        assert_eq!(v.len(), dmat_test.num_rows());
+        assert_eq!(shape, vec![1611]);
+
+        // first 10 predictions
+        let expected_start = [
+            0.0050151693,
+            0.9884467,
+            0.0050151693,
+            0.0050151693,
+            0.026636455,
+            0.11789363,
+            0.9884467,
+            0.01231471,
+            0.9884467,
+            0.00013656063,
+        ];
+
+        // last 10 predictions
         let expected_end = [
             0.002520344,
             0.00060917926,
@@ -1109,6 +2946,243 @@ mod tests {
         }
     }
 
+    #[test]
+    fn predict_with() {
+        let dmat = read_train_matrix().expect("Reading train matrix failed");
+        let mut booster = Booster::new_with_cached_dmats(&BoosterParameters::default(), &[&dmat]).unwrap();
+        for i in 0..10 {
+            booster.update(&dmat, i).expect("update failed");
+        }
+        let num_rows = dmat.num_rows();
+        let num_features = dmat.num_cols();
+
+        let preds = booster.predict_with(&dmat, &PredictConfig::default()).unwrap();
+        assert_eq!(preds.shape(), &[num_rows]);
+        assert_eq!(preds.num_rows(), num_rows);
+        assert_eq!(preds.data(), booster.predict(&dmat).unwrap().as_slice());
+
+        // first 5 rounds only, for each kind of prediction
+        let sliced = booster.slice(0, 5, 1).unwrap();
+        let config = |_type| PredictConfig {
+            _type,
+            iteration_end: 5,
+            ..Default::default()
+        };
+        let preds = booster.predict_with(&dmat, &config(PredictType::Normal)).unwrap();
+        assert_eq!(preds.into_vec(), sliced.predict(&dmat).unwrap());
+
+        let margins = booster.predict_with(&dmat, &config(PredictType::OutputMargin)).unwrap();
+        assert_eq!(margins.data(), sliced.predict_margin(&dmat).unwrap().as_slice());
+
+        let leaves = booster
+            .predict_with(&dmat, &config(PredictType::PredictLeafTraining))
+            .unwrap();
+        assert_eq!(leaves.shape(), &[num_rows, 5]);
+        assert_eq!(leaves.data(), sliced.predict_leaf(&dmat).unwrap().0.as_slice());
+
+        for _type in [
+            PredictType::PredictContribitions,
+            PredictType::PredictApproximateContributions,
+        ] {
+            let contributions = booster.predict_with(&dmat, &config(_type)).unwrap();
+            assert_eq!(contributions.shape(), &[num_rows, num_features + 1]);
+            for (row, margin) in [0, 1, num_rows - 1].iter().map(|&i| (i, margins.data()[i])) {
+                let sum: f32 = contributions.row(row).unwrap().iter().sum();
+                assert!((sum - margin).abs() < 1e-4, "{} != {}", sum, margin);
+            }
+        }
+        assert_eq!(
+            booster
+                .predict_with(&dmat, &config(PredictType::PredictContribitions))
+                .unwrap()
+                .into_vec(),
+            sliced.predict_contributions(&dmat).unwrap().0
+        );
+
+        let interactions = booster
+            .predict_with(&dmat, &config(PredictType::PredictFeatureInteractions))
+            .unwrap();
+        assert_eq!(interactions.shape(), &[num_rows, num_features + 1, num_features + 1]);
+        assert_eq!(interactions.row(num_rows - 1).unwrap().len(), (num_features + 1).pow(2));
+        assert_eq!(interactions.row(num_rows), None);
+    }
+
+    #[test]
+    fn predict_inplace() {
+        let num_rows = 20;
+        let data: Vec<f32> = (0..num_rows).flat_map(|i| [i as f32, (i % 3) as f32, 1.0]).collect();
+        let labels: Vec<f32> = (0..num_rows).map(|i| i as f32 * 0.5).collect();
+        let mut dmat = DMatrix::from_dense(&data, num_rows).unwrap();
+        dmat.set_labels(&labels).unwrap();
+
+        let params = parameters::BoosterParametersBuilder::default()
+            .verbose(false)
+            .build()
+            .unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat]).unwrap();
+        for i in 0..5 {
+            booster.update(&dmat, i).expect("update failed");
+        }
+
+        let config = PredictConfig::default();
+        let (expected, expected_shape) = booster.predict_matrix(&dmat, &config.as_json()).unwrap();
+
+        let (preds, shape) = booster
+            .predict_inplace_dense(&data, (num_rows, 3), f32::NAN, &config)
+            .unwrap();
+        assert_eq!(shape, expected_shape);
+        assert_eq!(preds, expected);
+
+        // same data in CSR format, with all values stored
+        let indptr: Vec<usize> = (0..=num_rows).map(|i| i * 3).collect();
+        let indices: Vec<usize> = (0..num_rows).flat_map(|_| [0, 1, 2]).collect();
+        let (preds, shape) = booster
+            .predict_inplace_csr(&indptr, &indices, &data, 3, f32::NAN, &config)
+            .unwrap();
+        assert_eq!(shape, expected_shape);
+        assert_eq!(preds, expected);
+
+        // single row
+        let (preds, shape) = booster
+            .predict_inplace_dense(&data[3..6], (1, 3), -999.0, &config)
+            .unwrap();
+        assert_eq!(shape, vec![1]);
+        assert_eq!(preds[0], expected[1]);
+
+        assert!(booster
+            .predict_inplace_dense(&data, (num_rows, 2), f32::NAN, &config)
+            .is_err());
+        assert!(booster
+            .predict_inplace_csr(&indptr, &indices[1..], &data, 3, f32::NAN, &config)
+            .is_err());
+    }
+
+    #[test]
+    fn predict_concurrently() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Booster>();
+        assert_send_sync::<DMatrix>();
+
+        let num_rows = 50;
+        let data: Vec<f32> = (0..num_rows)
+            .flat_map(|i| [i as f32, (i % 7) as f32, (i % 2) as f32])
+            .collect();
+        let labels: Vec<f32> = (0..num_rows).map(|i| (i % 2) as f32).collect();
+        let mut dmat = DMatrix::from_dense(&data, num_rows).unwrap();
+        dmat.set_labels(&labels).unwrap();
+
+        let linear_params = parameters::linear::LinearBoosterParameters::default();
+        for booster_type in [
+            parameters::BoosterType::default(),
+            parameters::BoosterType::Linear(linear_params),
+        ] {
+            let params = parameters::BoosterParametersBuilder::default()
+                .booster_type(booster_type)
+                .verbose(false)
+                .build()
+                .unwrap();
+            let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat]).unwrap();
+            for i in 0..5 {
+                booster.update(&dmat, i).expect("update failed");
+            }
+
+            let config = PredictConfig::default();
+            let expected = booster.predict(&dmat).unwrap();
+            let (expected_inplace, _) = booster
+                .predict_inplace_dense(&data, (num_rows, 3), f32::NAN, &config)
+                .unwrap();
+
+            std::thread::scope(|scope| {
+                for _ in 0..8 {
+                    scope.spawn(|| {
+                        for _ in 0..20 {
+                            assert_eq!(booster.predict(&dmat).unwrap(), expected);
+                            let (preds, _) = booster.predict_matrix(&dmat, &config.as_json()).unwrap();
+                            assert_eq!(preds, expected);
+                            let (preds, _) = booster
+                                .predict_inplace_dense(&data, (num_rows, 3), f32::NAN, &config)
+                                .unwrap();
+                            assert_eq!(preds, expected_inplace);
+                        }
+                    });
+                }
+            });
+
+            // models can also be moved to other threads, e.g. after loading them
+            let bytes = booster.save_buffer(true).unwrap();
+            let loaded = std::thread::spawn(move || Booster::load_buffer(&bytes).unwrap())
+                .join()
+                .unwrap();
+            assert_eq!(loaded.gradient_booster, booster.gradient_booster);
+            assert_eq!(loaded.predict(&dmat).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn predict_dart_margin_concurrently() {
+        let num_rows = 50;
+        let data: Vec<f32> = (0..num_rows)
+            .flat_map(|i| [i as f32, (i % 7) as f32, (i % 2) as f32])
+            .collect();
+        let labels: Vec<f32> = (0..num_rows).map(|i| (i % 2) as f32).collect();
+        let mut dmat = DMatrix::from_dense(&data, num_rows).unwrap();
+        dmat.set_labels(&labels).unwrap();
+
+        let dart_params = parameters::dart::DartBoosterParametersBuilder::default()
+            .rate_drop(0.5)
+            .build()
+            .unwrap();
+        let params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Dart(dart_params))
+            .verbose(false)
+            .build()
+            .unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat]).unwrap();
+        for i in 0..10 {
+            booster.update(&dmat, i).expect("update failed");
+        }
+        assert_eq!(booster.gradient_booster, GradientBooster::Dart);
+
+        // training mode predictions drop trees in state shared by the model, so they're serialized
+        assert!(booster.prediction_guard(true).is_some());
+        assert!(booster.prediction_guard(false).is_none());
+        let training_config = PredictConfig {
+            _type: PredictType::OutputMargin,
+            training: true,
+            ..Default::default()
+        };
+        let json = |config: &PredictConfig| ffi::CString::new(config.as_json().trim_end_matches('\0')).unwrap();
+        assert!(config_training(&json(&training_config)));
+        assert!(!config_training(&json(&PredictConfig::default())));
+
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..20 {
+                        let margins = booster.predict_margin(&dmat).unwrap();
+                        assert_eq!(margins.len(), num_rows);
+                        assert!(margins.iter().all(|margin| margin.is_finite()));
+                        let margins = booster.predict_with(&dmat, &training_config).unwrap();
+                        assert_eq!(margins.data().len(), num_rows);
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn predict_config_inplace_json() {
+        let config = PredictConfig::default();
+        assert_eq!(
+            config.as_inplace_json(f32::NAN),
+            r#"{"type":0,"training":false,"iteration_begin":0,"iteration_end":0,"strict_shape":false,"missing":NaN,"cache_id":0}"#
+        );
+        assert!(config.as_inplace_json(-999.0).contains(r#""missing":-999,"#));
+        assert!(config
+            .as_inplace_json(f32::NEG_INFINITY)
+            .contains(r#""missing":-Infinity,"#));
+    }
+
     #[test]
     fn predict_leaf() {
         let dmat_train =
@@ -1180,6 +3254,216 @@ mod tests {
         assert_eq!(shape, (num_samples, num_features + 1));
     }
 
+    #[test]
+    fn model_dimensions() {
+        let dmat = read_train_matrix().expect("Reading train matrix failed");
+        let mut booster = Booster::new_with_cached_dmats(&BoosterParameters::default(), &[&dmat]).unwrap();
+        assert_eq!(booster.num_boosted_rounds().unwrap(), 0);
+        assert_eq!(booster.num_features().unwrap(), 0);
+
+        for i in 0..3 {
+            booster.update(&dmat, i).expect("update failed");
+        }
+        assert_eq!(booster.num_boosted_rounds().unwrap(), 3);
+        assert_eq!(booster.num_features().unwrap(), dmat.num_cols());
+        assert_eq!(booster.num_outputs().unwrap(), 1);
+        assert_eq!(booster.num_trees().unwrap(), 3);
+
+        // the shape of a trained model is cached until its parameters change
+        assert!(booster.shape.get().is_some());
+        booster.update(&dmat, 3).expect("update failed");
+        assert_eq!(booster.num_trees().unwrap(), 4);
+        booster.set_param("eta", "0.1").unwrap();
+        assert!(booster.shape.get().is_none());
+        assert_eq!(booster.num_outputs().unwrap(), 1);
+
+        // multiclass model, predicting on a matrix without any rows
+        let num_rows = 30;
+        let num_classes = 3;
+        let data: Vec<f32> = (0..num_rows)
+            .flat_map(|i| [(i % num_classes) as f32, i as f32])
+            .collect();
+        let labels: Vec<f32> = (0..num_rows).map(|i| (i % num_classes) as f32).collect();
+        let mut dmat = DMatrix::from_dense(&data, num_rows).unwrap();
+        dmat.set_labels(&labels).unwrap();
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::MultiSoftprob(num_classes as u32))
+            .build()
+            .unwrap();
+        let params = parameters::BoosterParametersBuilder::default()
+            .learning_params(learning_params)
+            .verbose(false)
+            .build()
+            .unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat]).unwrap();
+        for i in 0..2 {
+            booster.update(&dmat, i).expect("update failed");
+        }
+        assert_eq!(booster.num_boosted_rounds().unwrap(), 2);
+        assert_eq!(booster.num_features().unwrap(), 2);
+        assert_eq!(booster.num_outputs().unwrap(), num_classes);
+
+        let (leaves, shape) = booster.predict_leaf(&dmat).unwrap();
+        assert_eq!(shape, (num_rows, 2 * num_classes));
+        assert_eq!(leaves.len(), shape.0 * shape.1);
+        let (contributions, shape) = booster.predict_contributions(&dmat).unwrap();
+        assert_eq!(shape, (num_rows, num_classes * 3));
+        assert_eq!(contributions.len(), shape.0 * shape.1);
+        let (interactions, shape) = booster.predict_interactions(&dmat).unwrap();
+        assert_eq!(shape, (num_rows, num_classes * 3, 3));
+        assert_eq!(interactions.len(), shape.0 * shape.1 * shape.2);
+
+        let empty = dmat.slice(&[]).unwrap();
+        assert_eq!(booster.predict_leaf(&empty).unwrap(), (vec![], (0, 2 * num_classes)));
+        assert_eq!(
+            booster.predict_contributions(&empty).unwrap(),
+            (vec![], (0, num_classes * 3))
+        );
+        assert_eq!(
+            booster.predict_interactions(&empty).unwrap(),
+            (vec![], (0, num_classes * 3, 3))
+        );
+    }
+
+    #[test]
+    fn feature_importance() {
+        let dmat = read_train_matrix().expect("Reading train matrix failed");
+        let mut booster = Booster::new_with_cached_dmats(&BoosterParameters::default(), &[&dmat]).unwrap();
+        for i in 0..5 {
+            booster.update(&dmat, i).expect("update failed");
+        }
+
+        // number of splits on each feature, counted in the text dump
+        let mut expected_weights: HashMap<String, f32> = HashMap::new();
+        for tree in booster.dump_model_vec(false).unwrap() {
+            for split in tree.split("[f").skip(1) {
+                let feature = split.split('<').next().unwrap();
+                *expected_weights.entry(format!("f{}", feature)).or_default() += 1.0;
+            }
+        }
+        let weights = booster.feature_importance(ImportanceType::Weight).unwrap();
+        assert_eq!(weights.len(), expected_weights.len());
+        for (feature, scores) in &weights {
+            assert_eq!(scores, &vec![expected_weights[feature]], "{}", feature);
+        }
+
+        let gains = booster.feature_importance(ImportanceType::Gain).unwrap();
+        let total_gains = booster.feature_importance(ImportanceType::TotalGain).unwrap();
+        let covers = booster.feature_importance(ImportanceType::Cover).unwrap();
+        let total_covers = booster.feature_importance(ImportanceType::TotalCover).unwrap();
+        for (feature, weight) in &weights {
+            let total_gain = gains[feature][0] * weight[0];
+            assert!((total_gains[feature][0] - total_gain).abs() <= total_gain * 1e-4);
+            let total_cover = covers[feature][0] * weight[0];
+            assert!((total_covers[feature][0] - total_cover).abs() <= total_cover * 1e-4);
+        }
+
+        // named features
+        let names: Vec<String> = (0..dmat.num_cols()).map(|i| format!("feature_{}", i)).collect();
+        booster
+            .set_feature_names(&names.iter().map(|name| name.as_str()).collect())
+            .unwrap();
+        let named_weights = booster.feature_importance(ImportanceType::Weight).unwrap();
+        let (feature, scores) = weights.first().unwrap();
+        assert_eq!(named_weights[&feature.replace('f', "feature_")], *scores);
+
+        // linear multiclass model, with one coefficient per class
+        let num_rows = 30;
+        let num_classes = 3;
+        let data: Vec<f32> = (0..num_rows)
+            .flat_map(|i| [(i % num_classes) as f32, i as f32])
+            .collect();
+        let labels: Vec<f32> = (0..num_rows).map(|i| (i % num_classes) as f32).collect();
+        let mut dmat = DMatrix::from_dense(&data, num_rows).unwrap();
+        dmat.set_labels(&labels).unwrap();
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::MultiSoftprob(num_classes as u32))
+            .build()
+            .unwrap();
+        let params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Linear(Default::default()))
+            .learning_params(learning_params)
+            .verbose(false)
+            .build()
+            .unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat]).unwrap();
+        for i in 0..3 {
+            booster.update(&dmat, i).expect("update failed");
+        }
+        let weights = booster.feature_importance(ImportanceType::Weight).unwrap();
+        assert_eq!(weights.keys().collect::<Vec<_>>(), vec!["f0", "f1"]);
+        assert!(weights.values().all(|scores| scores.len() == num_classes));
+        assert!(booster.feature_importance(ImportanceType::Gain).is_err());
+    }
+
+    #[test]
+    fn dump_formats_and_trees() {
+        let dmat = read_train_matrix().expect("Reading train matrix failed");
+        let mut booster = Booster::new_with_cached_dmats(&BoosterParameters::default(), &[&dmat]).unwrap();
+        for i in 0..3 {
+            booster.update(&dmat, i).expect("update failed");
+        }
+
+        let text = booster.dump_model_format(DumpFormat::Text, false, None).unwrap();
+        assert_eq!(text, booster.dump_model_vec(false).unwrap());
+        let dot = booster.dump_model_format(DumpFormat::Dot, false, None).unwrap();
+        assert_eq!(dot.len(), 3);
+        assert!(dot.iter().all(|graph| graph.trim_start().starts_with("digraph")));
+        let json = booster.dump_model_format(DumpFormat::Json, true, None).unwrap();
+        assert_eq!(json.len(), 3);
+
+        let trees = booster.trees().unwrap();
+        assert_eq!(trees.len(), 3);
+        for (tree, dump) in trees.iter().zip(&text) {
+            assert_eq!(tree.num_leaves(), dump.matches("leaf=").count());
+            assert!(tree.root().cover.is_some());
+            let root = tree.root().split().unwrap();
+            assert!(root.gain.is_some());
+            assert!(dump.starts_with(&format!("0:[f{}<", root.feature)));
+        }
+
+        let model = booster.tree_model().unwrap();
+        assert_eq!(model.trees().len(), 3);
+        assert_eq!(model.num_boosted_rounds(), 3);
+        assert_eq!(model.num_features(), booster.num_features().unwrap());
+        assert_eq!(model.objective(), "reg:squarederror");
+        for (saved, dumped) in model.trees().iter().zip(&trees) {
+            assert_eq!(saved.nodes.len(), dumped.nodes.len());
+            for (saved, dumped) in saved.nodes.iter().zip(&dumped.nodes) {
+                assert_eq!(saved.id, dumped.id);
+                match (saved.split(), dumped.split()) {
+                    (Some(saved), Some(dumped)) => {
+                        assert_eq!(saved.feature, dumped.feature);
+                        assert_eq!(
+                            (saved.yes, saved.no, saved.missing),
+                            (dumped.yes, dumped.no, dumped.missing)
+                        );
+                    }
+                    (None, None) => {
+                        let (saved, dumped) = (saved.leaf_value().unwrap(), dumped.leaf_value().unwrap());
+                        assert!((saved - dumped).abs() <= 1e-5 * saved.abs().max(1.0));
+                    }
+                    _ => panic!("node {} differs between saved model and dump", saved.id),
+                }
+            }
+        }
+
+        // named features are mapped back to their index
+        let names: Vec<String> = (0..dmat.num_cols()).map(|i| format!("feature_{}", i)).collect();
+        booster
+            .set_feature_names(&names.iter().map(|name| name.as_str()).collect())
+            .unwrap();
+        assert_eq!(booster.trees().unwrap(), trees);
+        assert_eq!(booster.tree_model().unwrap().feature_names(), names.as_slice());
+
+        let linear_params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Linear(Default::default()))
+            .build()
+            .unwrap();
+        let linear = Booster::new_with_cached_dmats(&linear_params, &[&dmat]).unwrap();
+        assert!(linear.tree_model().is_err());
+    }
+
     #[test]
     fn predict_interactions() {
         let dmat_train =
@@ -1216,6 +3500,51 @@ mod tests {
         assert_eq!(shape, (num_samples, num_features + 1, num_features + 1));
     }
 
+    #[test]
+    #[cfg(feature = "ndarray")]
+    fn predict_arrays() {
+        let dmat_train =
+            DMatrix::load(r#"{"uri": "xgboost-sys/xgboost/demo/data/agaricus.txt.train?format=libsvm"}"#).unwrap();
+        let dmat_test =
+            DMatrix::load(r#"{"uri": "xgboost-sys/xgboost/demo/data/agaricus.txt.test?format=libsvm"}"#).unwrap();
+
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::MultiSoftprob(2))
+            .build()
+            .unwrap();
+        let params = parameters::BoosterParametersBuilder::default()
+            .learning_params(learning_params)
+            .verbose(false)
+            .build()
+            .unwrap();
+        let mut booster = Booster::new_with_cached_dmats(&params, &[&dmat_train, &dmat_test]).unwrap();
+
+        let num_rounds = 5;
+        for i in 0..num_rounds {
+            booster.update(&dmat_train, i).expect("update failed");
+        }
+
+        let num_samples = dmat_test.num_rows();
+        let num_features = dmat_train.num_cols();
+
+        let preds = booster.predict_array(&dmat_test, &PredictConfig::default()).unwrap();
+        assert_eq!(preds.shape(), &[num_samples, 2]);
+
+        let config = PredictConfig {
+            _type: PredictType::PredictContribitions,
+            strict_shape: true,
+            ..Default::default()
+        };
+        let contributions = booster.predict_array(&dmat_test, &config).unwrap();
+        assert_eq!(contributions.shape(), &[num_samples, 2, num_features + 1]);
+
+        // fixed rank arrays are only available for single output models
+        assert!(booster.predict_contributions_array(&dmat_test).is_err());
+
+        let leaves = booster.predict_leaf_array(&dmat_test).unwrap();
+        assert_eq!(leaves.dim(), (num_samples, num_rounds as usize * 2));
+    }
+
     #[test]
     fn parse_eval_string() {
         let s = "[0]\ttrain-map@4-:0.5\ttrain-logloss:1.0\ttest-map@4-:0.25\ttest-logloss:0.75";
@@ -0,0 +1,193 @@
+//! Callbacks for hooking into the training loop of [`Booster::train`](../struct.Booster.html#method.train).
+//!
+//! Callbacks are passed to training using
+//! [`TrainingParametersBuilder::callbacks`](../parameters/struct.TrainingParametersBuilder.html#method.callbacks).
+//!
+//! # Example
+//!
+//! ```
+//! use xgb::callback::{EvaluationMonitor, LearningRateScheduler, TrainingCallback};
+//! use xgb::parameters::TrainingParametersBuilder;
+//! use xgb::DMatrix;
+//!
+//! let dtrain = DMatrix::from_dense(&[1.0, 0.0, 1.0, 1.0], 2).unwrap();
+//!
+//! // log evaluation results every 10 rounds, and decay the learning rate each round
+//! let monitor = EvaluationMonitor::new(10);
+//! let scheduler = LearningRateScheduler::new(|iteration| 0.3 * 0.99f32.powi(iteration));
+//! let callbacks: &[&dyn TrainingCallback] = &[&monitor, &scheduler];
+//!
+//! let training_params = TrainingParametersBuilder::default()
+//!     .dtrain(&dtrain)
+//!     .callbacks(callbacks)
+//!     .build()
+//!     .unwrap();
+//! ```
+use std::path::PathBuf;
+
+use super::booster::{format_eval_results, EvaluationResults};
+use super::{Booster, XGBResult};
+
+/// Whether training should continue after a callback has run.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CallbackAction {
+    /// Continue training.
+    #[default]
+    Continue,
+
+    /// Stop training early.
+    Stop,
+}
+
+/// Hooks into the training loop of [`Booster::train`](../struct.Booster.html#method.train).
+///
+/// All methods have default implementations which do nothing, so implementors only need to override the hooks they
+/// use. Callbacks are passed by shared reference, so any state they keep between calls needs interior mutability
+/// (e.g. [`Cell`](std::cell::Cell) or [`RefCell`](std::cell::RefCell)).
+///
+/// Returning an error from any hook aborts training with that error.
+pub trait TrainingCallback {
+    /// Called once the Booster has been created, before the first boosting round.
+    fn before_training(&self, _booster: &mut Booster) -> XGBResult<()> {
+        Ok(())
+    }
+
+    /// Called before each boosting round. Returning [`CallbackAction::Stop`] stops training before the round is run.
+    fn before_iteration(&self, _booster: &mut Booster, _iteration: i32) -> XGBResult<CallbackAction> {
+        Ok(CallbackAction::Continue)
+    }
+
+    /// Called after each boosting round, with the evaluation results of that round (empty if no evaluation sets
+    /// were given). Returning [`CallbackAction::Stop`] stops training after this round.
+    fn after_iteration(
+        &self,
+        _booster: &mut Booster,
+        _iteration: i32,
+        _eval_results: &EvaluationResults,
+    ) -> XGBResult<CallbackAction> {
+        Ok(CallbackAction::Continue)
+    }
+
+    /// Called once training has finished, either after the last round or after stopping early.
+    fn after_training(&self, _booster: &mut Booster) -> XGBResult<()> {
+        Ok(())
+    }
+}
+
+/// Writes evaluation results through the [`log`](https://docs.rs/log) crate, at info level.
+pub struct EvaluationMonitor {
+    period: u32,
+}
+
+impl EvaluationMonitor {
+    /// Create a new monitor which logs evaluation results every `period` rounds (a period of 0 is treated as 1).
+    pub fn new(period: u32) -> Self {
+        EvaluationMonitor { period: period.max(1) }
+    }
+}
+
+impl TrainingCallback for EvaluationMonitor {
+    fn after_iteration(
+        &self,
+        _booster: &mut Booster,
+        iteration: i32,
+        eval_results: &EvaluationResults,
+    ) -> XGBResult<CallbackAction> {
+        if !eval_results.is_empty() && (iteration as u32).is_multiple_of(self.period) {
+            info!("{}", format_eval_results(iteration, eval_results));
+        }
+        Ok(CallbackAction::Continue)
+    }
+}
+
+/// Periodically saves the Booster being trained to a directory, using
+/// [`Booster::save`](../struct.Booster.html#method.save).
+///
+/// Models are saved as `<name>_<iteration>.json`, or `<name>_<iteration>.ubj` when saving in binary format.
+pub struct Checkpoint {
+    directory: PathBuf,
+    name: String,
+    interval: u32,
+    binary: bool,
+}
+
+impl Checkpoint {
+    /// Create a new callback which saves the model to `directory` every `interval` rounds (an interval of 0 is
+    /// treated as 1), in JSON format and named `model_<iteration>.json`.
+    pub fn new<P: Into<PathBuf>>(directory: P, interval: u32) -> Self {
+        Checkpoint {
+            directory: directory.into(),
+            name: "model".to_owned(),
+            interval: interval.max(1),
+            binary: false,
+        }
+    }
+
+    /// Set the name prefix of saved model files.
+    pub fn with_name<S: Into<String>>(mut self, name: S) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Save models in XGBoost's binary UBJSON format instead of JSON.
+    pub fn with_binary(mut self, binary: bool) -> Self {
+        self.binary = binary;
+        self
+    }
+
+    /// Get the path a model trained for given iteration is saved to.
+    pub fn path(&self, iteration: i32) -> PathBuf {
+        let extension = if self.binary { "ubj" } else { "json" };
+        self.directory
+            .join(format!("{}_{}.{}", self.name, iteration, extension))
+    }
+}
+
+impl TrainingCallback for Checkpoint {
+    fn after_iteration(
+        &self,
+        booster: &mut Booster,
+        iteration: i32,
+        _eval_results: &EvaluationResults,
+    ) -> XGBResult<CallbackAction> {
+        if (iteration as u32 + 1).is_multiple_of(self.interval) {
+            booster.save(self.path(iteration))?;
+        }
+        Ok(CallbackAction::Continue)
+    }
+}
+
+/// Sets the learning rate (`eta`) before each boosting round, using a schedule function which maps the iteration
+/// number to a learning rate.
+pub struct LearningRateScheduler<F: Fn(i32) -> f32> {
+    schedule: F,
+}
+
+impl<F: Fn(i32) -> f32> LearningRateScheduler<F> {
+    /// Create a new scheduler from a function mapping the iteration number to a learning rate.
+    pub fn new(schedule: F) -> Self {
+        LearningRateScheduler { schedule }
+    }
+}
+
+impl<F: Fn(i32) -> f32> TrainingCallback for LearningRateScheduler<F> {
+    fn before_iteration(&self, booster: &mut Booster, iteration: i32) -> XGBResult<CallbackAction> {
+        let eta = (self.schedule)(iteration);
+        booster.set_param("eta", &eta.to_string())?;
+        Ok(CallbackAction::Continue)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checkpoint_path() {
+        let checkpoint = Checkpoint::new("/tmp/checkpoints", 5);
+        assert_eq!(checkpoint.path(9), PathBuf::from("/tmp/checkpoints/model_9.json"));
+
+        let checkpoint = checkpoint.with_name("bst").with_binary(true);
+        assert_eq!(checkpoint.path(4), PathBuf::from("/tmp/checkpoints/bst_4.ubj"));
+    }
+}
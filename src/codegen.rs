@@ -0,0 +1,600 @@
+//! Compile tree models to standalone Rust source code, for predicting without any dependencies.
+//!
+//! The generated code defines:
+//!
+//! * `NUM_FEATURES` and `NUM_OUTPUTS` constants, with the number of features the model was trained on and the number
+//!   of margins predicted per row
+//! * `predict_margin(features: &[f32]) -> [f32; NUM_OUTPUTS]`, predicting the untransformed margins of a row
+//! * `predict(features: &[f32])`, predicting a row with the objective's transformation applied. Returns an `f32` for
+//!   single output models and `multi:softmax` (the predicted class), otherwise an array with one value per output
+//!
+//! Both take feature values in the order the model was trained with, where `NaN` (or features past the end of the
+//! slice) marks missing values. Predictions are identical to
+//! [`Booster::predict`](../struct.Booster.html#method.predict) and
+//! [`Booster::predict_margin`](../struct.Booster.html#method.predict_margin) for the objectives supported by
+//! [`inference`](../inference/index.html).
+//!
+//! # Example
+//!
+//! Embed a saved model at compile time with a build script, using `xgb` as a build dependency without the default
+//! features (so libxgboost isn't needed):
+//!
+//! ```toml
+//! [build-dependencies]
+//! xgb = { version = "3", default-features = false, features = ["codegen"] }
+//! ```
+//!
+//! ```no_run
+//! // build.rs
+//! use xgb::codegen::{self, CodeStyle};
+//!
+//! let out_path = std::path::Path::new(&std::env::var("OUT_DIR").unwrap()).join("model.rs");
+//! codegen::generate_file("model.ubj", out_path, CodeStyle::Branches).unwrap();
+//! println!("cargo:rerun-if-changed=model.ubj");
+//! ```
+//!
+//! Then include the generated code in a module:
+//!
+//! ```ignore
+//! mod model {
+//!     include!(concat!(env!("OUT_DIR"), "/model.rs"));
+//! }
+//!
+//! let prediction = model::predict(&[1.0, f32::NAN, 0.5]);
+//! ```
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+
+use super::inference::{Ensemble, Objective};
+use super::model::{SplitCondition, Tree};
+use super::{XGBError, XGBResult};
+
+/// How trees are laid out in generated code.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CodeStyle {
+    /// One function per tree, made of nested `if`/`else` branches. Usually the fastest, but large models produce a
+    /// lot of code.
+    #[default]
+    Branches,
+
+    /// Static arrays of nodes, walked by a single loop. Compiles faster and produces smaller binaries for large
+    /// models.
+    Arrays,
+}
+
+/// Generate Rust source code predicting with the given ensemble.
+pub fn generate(ensemble: &Ensemble, style: CodeStyle) -> String {
+    let model = ensemble.model();
+    let mut code = String::new();
+    writeln!(
+        code,
+        "// Generated by xgb::codegen from a model with {} trees for objective {}.\n",
+        model.trees().len(),
+        model.objective()
+    )
+    .unwrap();
+    writeln!(code, "/// Number of features the model was trained on.").unwrap();
+    writeln!(code, "#[allow(dead_code)]").unwrap();
+    writeln!(code, "pub const NUM_FEATURES: usize = {};\n", model.num_features()).unwrap();
+    writeln!(code, "/// Number of margins predicted for each row.").unwrap();
+    writeln!(code, "#[allow(dead_code)]").unwrap();
+    writeln!(code, "pub const NUM_OUTPUTS: usize = {};\n", model.num_outputs()).unwrap();
+
+    let base_margin: Vec<String> = ensemble.base_margin().iter().map(|&margin| float(margin)).collect();
+    writeln!(
+        code,
+        "/// Predict the untransformed margins of a row, using `NaN` for missing values."
+    )
+    .unwrap();
+    writeln!(code, "#[allow(dead_code)]").unwrap();
+    writeln!(code, "pub fn predict_margin(features: &[f32]) -> [f32; NUM_OUTPUTS] {{").unwrap();
+    writeln!(code, "    let mut margin = [{}];", base_margin.join(", ")).unwrap();
+    match style {
+        CodeStyle::Branches => {
+            let trees = model.tree_outputs().iter().zip(model.tree_weights()).enumerate();
+            for (i, (output, &weight)) in trees {
+                if weight == 1.0 {
+                    writeln!(code, "    margin[{}] += tree_{}(features);", output, i).unwrap();
+                } else {
+                    writeln!(
+                        code,
+                        "    margin[{}] += {} * tree_{}(features);",
+                        output,
+                        float(weight),
+                        i
+                    )
+                    .unwrap();
+                }
+            }
+        }
+        CodeStyle::Arrays => {
+            writeln!(code, "    for (tree, output, weight) in TREES.iter() {{").unwrap();
+            writeln!(code, "        margin[*output] += weight * leaf_value(tree, features);").unwrap();
+            writeln!(code, "    }}").unwrap();
+        }
+    }
+    writeln!(code, "    margin\n}}\n").unwrap();
+
+    write_predict(&mut code, ensemble.objective(), model.num_outputs());
+
+    match style {
+        CodeStyle::Branches => write_branches(&mut code, model.trees()),
+        CodeStyle::Arrays => write_arrays(&mut code, model.trees(), model.tree_outputs(), model.tree_weights()),
+    }
+    code
+}
+
+/// Generate Rust source code predicting with a model saved as JSON or UBJ, and write it to `out_path`.
+///
+/// Intended for build scripts, see the [module documentation](index.html) for an example.
+pub fn generate_file<P: AsRef<Path>, Q: AsRef<Path>>(model_path: P, out_path: Q, style: CodeStyle) -> XGBResult<()> {
+    let ensemble = Ensemble::load(model_path)?;
+    fs::write(out_path.as_ref(), generate(&ensemble, style))
+        .map_err(|err| XGBError::new(format!("Failed to write {}: {}", out_path.as_ref().display(), err)))
+}
+
+/// Write the `predict` function, applying the objective's transformation to the margins.
+fn write_predict(code: &mut String, objective: Objective, num_outputs: usize) {
+    writeln!(code, "/// Predict a row, using `NaN` for missing values.").unwrap();
+    let element = match objective {
+        Objective::Identity if num_outputs == 1 => {
+            writeln!(code, "pub fn predict(features: &[f32]) -> f32 {{").unwrap();
+            writeln!(code, "    predict_margin(features)[0]\n}}\n").unwrap();
+            return;
+        }
+        Objective::Identity => {
+            writeln!(code, "pub fn predict(features: &[f32]) -> [f32; NUM_OUTPUTS] {{").unwrap();
+            writeln!(code, "    predict_margin(features)\n}}\n").unwrap();
+            return;
+        }
+        Objective::Logistic => "1.0 / ((-x).min(88.7).exp() + 1.0)",
+        Objective::Hinge => "if x > 0.0 { 1.0 } else { 0.0 }",
+        Objective::Exp => "x.exp()",
+        Objective::SoftProb => {
+            code.push_str(
+                r#"pub fn predict(features: &[f32]) -> [f32; NUM_OUTPUTS] {
+    let mut margin = predict_margin(features);
+    let max = margin.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let mut sum = 0.0f64;
+    for value in margin.iter_mut() {
+        *value = (*value - max).exp();
+        sum += *value as f64;
+    }
+    for value in margin.iter_mut() {
+        *value /= sum as f32;
+    }
+    margin
+}
+
+"#,
+            );
+            return;
+        }
+        Objective::SoftMax => {
+            code.push_str(
+                r#"pub fn predict(features: &[f32]) -> f32 {
+    let margin = predict_margin(features);
+    let mut class = 0;
+    for (i, &value) in margin.iter().enumerate() {
+        if value > margin[class] {
+            class = i;
+        }
+    }
+    class as f32
+}
+
+"#,
+            );
+            return;
+        }
+    };
+    if num_outputs == 1 {
+        writeln!(code, "pub fn predict(features: &[f32]) -> f32 {{").unwrap();
+        writeln!(code, "    let x = predict_margin(features)[0];").unwrap();
+        writeln!(code, "    {}\n}}\n", element).unwrap();
+    } else {
+        writeln!(code, "pub fn predict(features: &[f32]) -> [f32; NUM_OUTPUTS] {{").unwrap();
+        writeln!(code, "    predict_margin(features).map(|x| {})\n}}\n", element).unwrap();
+    }
+}
+
+/// Write one function per tree, made of nested branches.
+fn write_branches(code: &mut String, trees: &[Tree]) {
+    code.push_str(
+        r#"#[allow(dead_code)]
+#[inline(always)]
+fn less(features: &[f32], index: usize, threshold: f32, missing: bool) -> bool {
+    match features.get(index) {
+        Some(value) if !value.is_nan() => *value < threshold,
+        _ => missing,
+    }
+}
+
+#[allow(dead_code)]
+#[inline(always)]
+fn in_categories(features: &[f32], index: usize, categories: &[u32], missing: bool) -> bool {
+    match features.get(index) {
+        Some(value) if !value.is_nan() => {
+            (0.0..16777216.0).contains(value) && categories.contains(&(*value as u32))
+        }
+        _ => missing,
+    }
+}
+"#,
+    );
+    for (i, tree) in trees.iter().enumerate() {
+        // trees which are a single leaf don't use the features
+        let features = if tree.root().is_leaf() { "_features" } else { "features" };
+        writeln!(code, "\nfn tree_{}({}: &[f32]) -> f32 {{", i, features).unwrap();
+        write_node(code, tree, tree.root().id, 1);
+        writeln!(code, "}}").unwrap();
+    }
+}
+
+fn write_node(code: &mut String, tree: &Tree, id: usize, depth: usize) {
+    let indent = "    ".repeat(depth);
+    let node = tree.node(id).unwrap();
+    let split = match node.split() {
+        Some(split) => split,
+        None => {
+            writeln!(code, "{}{}", indent, float(node.leaf_value().unwrap())).unwrap();
+            return;
+        }
+    };
+    let missing = split.default_yes();
+    match &split.condition {
+        SplitCondition::LessThan(threshold) => writeln!(
+            code,
+            "{}if less(features, {}, {}, {}) {{",
+            indent,
+            split.feature,
+            float(*threshold),
+            missing
+        ),
+        SplitCondition::Categories(categories) => writeln!(
+            code,
+            "{}if in_categories(features, {}, &{:?}, {}) {{",
+            indent, split.feature, categories, missing
+        ),
+    }
+    .unwrap();
+    write_node(code, tree, split.yes, depth + 1);
+    writeln!(code, "{}}} else {{", indent).unwrap();
+    write_node(code, tree, split.no, depth + 1);
+    writeln!(code, "{}}}", indent).unwrap();
+}
+
+/// Write static arrays of nodes for each tree, and a function walking them.
+fn write_arrays(code: &mut String, trees: &[Tree], outputs: &[usize], weights: &[f32]) {
+    code.push_str(
+        r#"/// Node of a tree, splitting on `feature` unless it's `LEAF`, in which case `value` is the leaf value.
+struct Node {
+    feature: usize,
+    value: f32,
+    categorical: bool,
+    categories: &'static [u32],
+    yes: usize,
+    no: usize,
+    missing: usize,
+}
+
+const LEAF: usize = usize::MAX;
+
+fn leaf_value(tree: &[Node], features: &[f32]) -> f32 {
+    let mut node = &tree[0];
+    while node.feature != LEAF {
+        let next = match features.get(node.feature) {
+            Some(value) if !value.is_nan() => {
+                let yes = if node.categorical {
+                    (0.0..16777216.0).contains(value) && node.categories.contains(&(*value as u32))
+                } else {
+                    *value < node.value
+                };
+                if yes {
+                    node.yes
+                } else {
+                    node.no
+                }
+            }
+            _ => node.missing,
+        };
+        node = &tree[next];
+    }
+    node.value
+}
+"#,
+    );
+
+    for (i, tree) in trees.iter().enumerate() {
+        // nodes are stored by position, starting with the root
+        let positions: HashMap<usize, usize> = tree
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(position, node)| (node.id, position))
+            .collect();
+        writeln!(code, "\nstatic TREE_{}: [Node; {}] = [", i, tree.nodes.len()).unwrap();
+        for node in &tree.nodes {
+            let (feature, value, categories, children) = match node.split() {
+                None => ("LEAF".to_owned(), node.leaf_value().unwrap(), None, (0, 0, 0)),
+                Some(split) => {
+                    let (value, categories) = match &split.condition {
+                        SplitCondition::LessThan(threshold) => (*threshold, None),
+                        SplitCondition::Categories(categories) => (0.0, Some(categories)),
+                    };
+                    let children = (positions[&split.yes], positions[&split.no], positions[&split.missing]);
+                    (split.feature.to_string(), value, categories, children)
+                }
+            };
+            write!(
+                code,
+                "    Node {{ feature: {}, value: {}, categorical: {}, categories: &{:?}, ",
+                feature,
+                float(value),
+                categories.is_some(),
+                categories.map(Vec::as_slice).unwrap_or_default(),
+            )
+            .unwrap();
+            writeln!(
+                code,
+                "yes: {}, no: {}, missing: {} }},",
+                children.0, children.1, children.2
+            )
+            .unwrap();
+        }
+        writeln!(code, "];").unwrap();
+    }
+
+    writeln!(
+        code,
+        "\n/// Each tree, with the output it contributes to and its weight."
+    )
+    .unwrap();
+    writeln!(code, "static TREES: [(&[Node], usize, f32); {}] = [", trees.len()).unwrap();
+    for (i, (output, &weight)) in outputs.iter().zip(weights).enumerate() {
+        writeln!(code, "    (&TREE_{}, {}, {}),", i, output, float(weight)).unwrap();
+    }
+    writeln!(code, "];").unwrap();
+}
+
+/// Format a float as a Rust literal which parses back to exactly the same value.
+fn float(value: f32) -> String {
+    if value.is_nan() {
+        "f32::NAN".to_owned()
+    } else if value.is_infinite() {
+        if value > 0.0 { "f32::INFINITY" } else { "f32::NEG_INFINITY" }.to_owned()
+    } else {
+        format!("{:?}_f32", value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::process::Command;
+
+    /// Compile the generated code into a program, and run it on the given rows to get predictions and margins.
+    fn run_generated(code: &str, data: &[f32]) -> (Vec<f32>, Vec<f32>) {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("model.rs"), code).unwrap();
+        fs::write(
+            dir.path().join("main.rs"),
+            r#"
+mod model {
+    include!("model.rs");
+}
+
+trait Values {
+    fn values(self) -> Vec<f32>;
+}
+
+impl Values for f32 {
+    fn values(self) -> Vec<f32> {
+        vec![self]
+    }
+}
+
+impl<const N: usize> Values for [f32; N] {
+    fn values(self) -> Vec<f32> {
+        self.to_vec()
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let bytes = std::fs::read(&args[1]).unwrap();
+    let data: Vec<f32> = bytes.chunks(4).map(|b| f32::from_le_bytes(b.try_into().unwrap())).collect();
+    let (mut predictions, mut margins) = (Vec::new(), Vec::new());
+    for row in data.chunks(model::NUM_FEATURES) {
+        predictions.extend(model::predict(row).values());
+        margins.extend(model::predict_margin(row).values());
+    }
+    let bytes = |values: Vec<f32>| values.iter().flat_map(|value| value.to_le_bytes()).collect::<Vec<u8>>();
+    std::fs::write(&args[2], bytes(predictions)).unwrap();
+    std::fs::write(&args[3], bytes(margins)).unwrap();
+}
+"#,
+        )
+        .unwrap();
+
+        let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_owned());
+        let program = dir.path().join("predict");
+        let output = Command::new(rustc)
+            .args(["--edition", "2021", "-O", "-D", "warnings", "-o"])
+            .arg(&program)
+            .arg(dir.path().join("main.rs"))
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+
+        let bytes: Vec<u8> = data.iter().flat_map(|value| value.to_le_bytes()).collect();
+        fs::write(dir.path().join("data"), bytes).unwrap();
+        let status = Command::new(&program)
+            .arg(dir.path().join("data"))
+            .arg(dir.path().join("predictions"))
+            .arg(dir.path().join("margins"))
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let read = |name: &str| -> Vec<f32> {
+            fs::read(dir.path().join(name))
+                .unwrap()
+                .chunks(4)
+                .map(|b| f32::from_le_bytes(b.try_into().unwrap()))
+                .collect()
+        };
+        (read("predictions"), read("margins"))
+    }
+
+    #[test]
+    fn generate_matches_inference() {
+        let mut model = serde_json::json!({
+            "learner": {
+                "gradient_booster": {
+                    "model": {
+                        "gbtree_model_param": {"num_parallel_tree": "1", "num_trees": "2"},
+                        "iteration_indptr": [0, 1, 2],
+                        "tree_info": [0, 0],
+                        "trees": [
+                            {
+                                "default_left": [1, 0, 0, 0, 0],
+                                "left_children": [1, 3, -1, -1, -1],
+                                "right_children": [2, 4, -1, -1, -1],
+                                "split_indices": [2, 0, 0, 0, 0],
+                                "split_conditions": [0.5, -1.25, 0.75, 0.1, -0.3],
+                                "split_type": [0, 0, 0, 0, 0],
+                                "loss_changes": [10.0, 3.0, 0.0, 0.0, 0.0],
+                                "sum_hessian": [8.0, 3.0, 5.0, 1.0, 2.0],
+                                "tree_param": {"num_nodes": "5", "size_leaf_vector": "1"}
+                            },
+                            {
+                                "default_left": [0, 0, 0],
+                                "left_children": [1, -1, -1],
+                                "right_children": [2, -1, -1],
+                                "split_indices": [1, 0, 0],
+                                "split_conditions": [1.0, 0.5, -0.5],
+                                "split_type": [1, 0, 0],
+                                "categories": [2, 4],
+                                "categories_nodes": [0],
+                                "categories_segments": [0],
+                                "categories_sizes": [2],
+                                "loss_changes": [4.0, 0.0, 0.0],
+                                "sum_hessian": [8.0, 6.0, 2.0],
+                                "tree_param": {"num_nodes": "3", "size_leaf_vector": "1"}
+                            }
+                        ]
+                    },
+                    "name": "gbtree"
+                },
+                "learner_model_param": {
+                    "base_score": "[3E-1]",
+                    "num_class": "0",
+                    "num_feature": "3",
+                    "num_target": "1"
+                },
+                "objective": {"name": "binary:logistic"}
+            },
+            "version": [3, 0, 0]
+        });
+        let nan = f32::NAN;
+        #[rustfmt::skip]
+        let data = [
+            0.0, 0.0, 0.0,
+            nan, nan, nan,
+            -2.0, 2.0, 0.25,
+            1.0, 4.0, 1.0,
+            0.0, 4.5, nan,
+            5.0, -4.0, 0.5,
+        ];
+
+        // the same trees as a single round of a 3 class model, where the second class has no trees
+        for (objective, num_class, tree_info, iteration_indptr, signature) in [
+            ("binary:logistic", "0", [0, 0], vec![0, 1, 2], "-> f32 {"),
+            ("multi:softprob", "3", [0, 2], vec![0, 2], "-> [f32; NUM_OUTPUTS] {"),
+        ] {
+            let learner = &mut model["learner"];
+            learner["objective"]["name"] = objective.into();
+            learner["learner_model_param"]["num_class"] = num_class.into();
+            learner["gradient_booster"]["model"]["tree_info"] = tree_info.into();
+            learner["gradient_booster"]["model"]["iteration_indptr"] = iteration_indptr.into();
+            let ensemble = Ensemble::from_json(model.to_string().as_bytes()).unwrap();
+
+            let expected: Vec<f32> = data.chunks(3).flat_map(|row| ensemble.predict_row(row)).collect();
+            let expected_margin: Vec<f32> = data
+                .chunks(3)
+                .flat_map(|row| ensemble.predict_row_margin(row))
+                .collect();
+            for style in [CodeStyle::Branches, CodeStyle::Arrays] {
+                let code = generate(&ensemble, style);
+                assert!(code.contains(&format!("pub fn predict(features: &[f32]) {}", signature)));
+                let (predictions, margins) = run_generated(&code, &data);
+                assert_eq!(predictions, expected, "{} {:?}", objective, style);
+                assert_eq!(margins, expected_margin, "{} {:?}", objective, style);
+            }
+        }
+    }
+
+    #[test]
+    fn float_literals() {
+        assert_eq!(float(1.0), "1.0_f32");
+        assert_eq!(float(-0.1), "-0.1_f32");
+        assert_eq!(float(1e-20), "1e-20_f32");
+        assert_eq!(float(f32::NEG_INFINITY), "f32::NEG_INFINITY");
+        for value in [0.1f32, 1.859_649_2, -2.000_01, 1e-20, f32::MAX, f32::MIN_POSITIVE] {
+            assert_eq!(float(value).trim_end_matches("_f32").parse::<f32>().unwrap(), value);
+        }
+    }
+
+    #[cfg(feature = "libxgboost")]
+    #[test]
+    fn generate_matches_booster() {
+        use crate::parameters::{self, learning};
+        use crate::{Booster, DMatrix};
+
+        let path = "xgboost-sys/xgboost/demo/data/agaricus.txt";
+        let dtrain = DMatrix::load(format!(r#"{{"uri": "{}.train?format=libsvm"}}"#, path)).unwrap();
+        let dtest = DMatrix::load(format!(r#"{{"uri": "{}.test?format=libsvm"}}"#, path)).unwrap();
+        for objective in [
+            learning::Objective::BinaryLogistic,
+            learning::Objective::MultiSoftprob(2),
+            learning::Objective::MultiSoftprob(3),
+        ] {
+            let learning_params = learning::LearningTaskParametersBuilder::default()
+                .objective(objective)
+                .build()
+                .unwrap();
+            let booster_params = parameters::BoosterParametersBuilder::default()
+                .learning_params(learning_params)
+                .verbose(false)
+                .build()
+                .unwrap();
+            let training_params = parameters::TrainingParametersBuilder::default()
+                .dtrain(&dtrain)
+                .booster_params(booster_params)
+                .boost_rounds(5)
+                .build()
+                .unwrap();
+            let booster = Booster::train(&training_params).unwrap();
+
+            // dense test data, with NaN for missing values
+            let num_features = booster.num_features().unwrap();
+            let mut data = Vec::new();
+            for line in fs::read_to_string(format!("{}.test", path)).unwrap().lines() {
+                let mut row = vec![f32::NAN; num_features];
+                for entry in line.split_whitespace().skip(1) {
+                    let (index, value) = entry.split_once(':').unwrap();
+                    row[index.parse::<usize>().unwrap()] = value.parse().unwrap();
+                }
+                data.extend(row);
+            }
+
+            for style in [CodeStyle::Branches, CodeStyle::Arrays] {
+                let (predictions, margins) = run_generated(&booster.generate_rust(style).unwrap(), &data);
+                assert_eq!(predictions, booster.predict(&dtest).unwrap());
+                assert_eq!(margins, booster.predict_margin(&dtest).unwrap());
+            }
+        }
+    }
+}
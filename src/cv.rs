@@ -0,0 +1,510 @@
+//! K-fold cross-validation, training one model per fold in lockstep.
+use super::booster::{EarlyStopping, EvaluationHistory, EvaluationResults};
+use super::parameters::{CrossValidationParameters, EvaluationOutput, FoldType};
+use super::{Booster, DMatrix, XGBError, XGBResult};
+
+/// Name of the evaluation results for each fold's training matrix.
+const TRAIN_NAME: &str = "train";
+
+/// Name of the evaluation results for each fold's held out matrix.
+const TEST_NAME: &str = "test";
+
+/// Results of cross-validation, with the mean and standard deviation of each metric across folds for every round.
+#[derive(Clone, Debug)]
+pub struct CrossValidationResults {
+    means: EvaluationHistory,
+    stds: EvaluationHistory,
+    best_iteration: Option<u32>,
+}
+
+impl CrossValidationResults {
+    /// Get the mean score of each metric across folds in each round.
+    ///
+    /// Maps `"train"` and `"test"` to metric names, which map to the metric's mean score in each round.
+    pub fn means(&self) -> &EvaluationHistory {
+        &self.means
+    }
+
+    /// Get the (population) standard deviation of each metric across folds in each round, in the same layout as
+    /// [`means`](struct.CrossValidationResults.html#method.means).
+    pub fn stds(&self) -> &EvaluationHistory {
+        &self.stds
+    }
+
+    /// Get the round with the best mean score, if early stopping was enabled.
+    ///
+    /// Results are truncated to this round when stopping early.
+    pub fn best_iteration(&self) -> Option<u32> {
+        self.best_iteration
+    }
+
+    /// Get the number of rounds results are available for.
+    pub fn num_rounds(&self) -> usize {
+        self.means
+            .values()
+            .flat_map(|metrics| metrics.values())
+            .map(|scores| scores.len())
+            .next()
+            .unwrap_or(0)
+    }
+}
+
+/// Run k-fold cross-validation.
+///
+/// The matrix is split into [`nfold`](parameters/struct.CrossValidationParameters.html#method.nfold) folds. For each
+/// fold a model is trained on the remaining folds and evaluated on both its training rows (`"train"`) and the held out
+/// fold (`"test"`). All models are trained in lockstep, one boosting round at a time, so the mean and standard
+/// deviation of each metric across folds are known after every round and can be used for early stopping.
+///
+/// # Example
+///
+/// ```no_run
+/// use xgb::parameters::{self, learning, CrossValidationParametersBuilder, EarlyStoppingParametersBuilder, FoldType};
+/// use xgb::DMatrix;
+///
+/// let dtrain = DMatrix::load(r#"{"uri": "agaricus.txt.train?format=libsvm"}"#).unwrap();
+///
+/// let learning_params = learning::LearningTaskParametersBuilder::default()
+///     .objective(learning::Objective::BinaryLogistic)
+///     .build()
+///     .unwrap();
+/// let booster_params = parameters::BoosterParametersBuilder::default()
+///     .learning_params(learning_params)
+///     .build()
+///     .unwrap();
+/// let early_stopping = EarlyStoppingParametersBuilder::default().rounds(5).build().unwrap();
+///
+/// let cv_params = CrossValidationParametersBuilder::default()
+///     .dtrain(&dtrain)
+///     .nfold(5)
+///     .fold_type(FoldType::Stratified)
+///     .boost_rounds(100)
+///     .booster_params(booster_params)
+///     .early_stopping(Some(early_stopping))
+///     .build()
+///     .unwrap();
+///
+/// let results = xgb::cv(&cv_params).unwrap();
+/// let test_logloss = &results.means()["test"]["logloss"];
+/// println!("best round: {:?}, logloss: {}", results.best_iteration(), test_logloss.last().unwrap());
+/// ```
+pub fn cv(params: &CrossValidationParameters) -> XGBResult<CrossValidationResults> {
+    let units = fold_units(params)?;
+    let nfold = params.nfold as usize;
+    if units.len() < nfold {
+        let msg = format!(
+            "Can't split {} {} into {} folds",
+            units.len(),
+            if params.fold_type == FoldType::Grouped { "groups" } else { "rows" },
+            nfold
+        );
+        return Err(XGBError::new(msg));
+    }
+
+    let labels = match params.fold_type {
+        FoldType::Stratified => {
+            let labels = params.dtrain.get_labels()?;
+            if labels.len() != params.dtrain.num_rows() {
+                return Err(XGBError::new("Stratified folds require labels for every row"));
+            }
+            Some(labels)
+        }
+        _ => None,
+    };
+    let assignments = assign_folds(units.len(), nfold, labels, params.shuffle, params.seed);
+
+    let mut folds = Vec::with_capacity(nfold);
+    for fold in 0..nfold {
+        folds.push(Fold::new(params, &units, &assignments, fold)?);
+    }
+
+    let mut early_stopping = params.early_stopping.as_ref().map(EarlyStopping::new);
+    let mut means = EvaluationHistory::new();
+    let mut stds = EvaluationHistory::new();
+    for i in 0..params.boost_rounds as i32 {
+        let mut fold_results = Vec::with_capacity(nfold);
+        for fold in &mut folds {
+            fold_results.push(fold.update(params, i)?);
+        }
+
+        let (round_means, round_stds) = aggregate_results(&fold_results);
+        match params.evaluation_output {
+            EvaluationOutput::Stdout => println!("{}", format_cv_results(i, &round_means, &round_stds)),
+            EvaluationOutput::Log => info!("{}", format_cv_results(i, &round_means, &round_stds)),
+            EvaluationOutput::Silent => {}
+        }
+
+        for (history, round_results) in [(&mut means, &round_means), (&mut stds, &round_stds)] {
+            for (dmat_name, eval_results) in round_results {
+                let dmat_history = history.entry(dmat_name.clone()).or_default();
+                for (eval_name, result) in eval_results {
+                    dmat_history.entry(eval_name.clone()).or_default().push(*result);
+                }
+            }
+        }
+
+        if let Some(early_stopping) = &mut early_stopping {
+            if early_stopping.update(i, TEST_NAME, &round_means)? {
+                break;
+            }
+        }
+    }
+
+    let best_iteration = early_stopping
+        .and_then(|early_stopping| early_stopping.best)
+        .map(|(best_iteration, _)| best_iteration as u32);
+    if let Some(best_iteration) = best_iteration {
+        for history in [&mut means, &mut stds] {
+            for scores in history.values_mut().flat_map(|metrics| metrics.values_mut()) {
+                scores.truncate(best_iteration as usize + 1);
+            }
+        }
+    }
+
+    Ok(CrossValidationResults {
+        means,
+        stds,
+        best_iteration,
+    })
+}
+
+/// Train and test matrices of a single fold, along with the model trained on them.
+struct Fold {
+    dtrain: DMatrix,
+    dtest: DMatrix,
+    booster: Booster,
+}
+
+impl Fold {
+    /// Create the fold holding out the units assigned to `fold`.
+    fn new(
+        params: &CrossValidationParameters,
+        units: &[Vec<usize>],
+        assignments: &[usize],
+        fold: usize,
+    ) -> XGBResult<Self> {
+        let (mut train_rows, mut test_rows) = (Vec::new(), Vec::new());
+        let (mut train_groups, mut test_groups) = (Vec::new(), Vec::new());
+        for (unit, assignment) in units.iter().zip(assignments) {
+            let (rows, groups) = if *assignment == fold {
+                (&mut test_rows, &mut test_groups)
+            } else {
+                (&mut train_rows, &mut train_groups)
+            };
+            rows.extend_from_slice(unit);
+            groups.push(unit.len() as u32);
+        }
+
+        let (dtrain, dtest) = if params.fold_type == FoldType::Grouped {
+            // slices don't keep query groups, so set the groups of the rows in each slice
+            let mut dtrain = params.dtrain.slice_ungrouped(&train_rows)?;
+            let mut dtest = params.dtrain.slice_ungrouped(&test_rows)?;
+            dtrain.set_group(&train_groups)?;
+            dtest.set_group(&test_groups)?;
+            (dtrain, dtest)
+        } else {
+            (params.dtrain.slice(&train_rows)?, params.dtrain.slice(&test_rows)?)
+        };
+
+        let mut booster = Booster::new_with_cached_dmats(&params.booster_params, &[&dtrain, &dtest])?;
+        booster.set_feature_info_from(params.dtrain)?;
+        Ok(Fold { dtrain, dtest, booster })
+    }
+
+    /// Train this fold's model for one round, and evaluate it on the fold's train and test matrices.
+    fn update(&mut self, params: &CrossValidationParameters, iteration: i32) -> XGBResult<EvaluationResults> {
+        if let Some(objective_fn) = params.custom_objective_fn {
+            self.booster.update_custom(&self.dtrain, objective_fn)?;
+        } else {
+            self.booster.update(&self.dtrain, iteration)?;
+        }
+
+        let eval_sets = [(&self.dtrain, TRAIN_NAME), (&self.dtest, TEST_NAME)];
+        self.booster
+            .evaluate_round(&eval_sets, iteration, params.custom_evaluation_fn)
+    }
+}
+
+/// Split the rows of the matrix into the units assigned to folds: query groups when using grouped folds, or single
+/// rows otherwise.
+fn fold_units(params: &CrossValidationParameters) -> XGBResult<Vec<Vec<usize>>> {
+    let group_ptr = params.dtrain.get_group()?;
+    match params.fold_type {
+        FoldType::Grouped => {
+            if group_ptr.len() < 2 {
+                return Err(XGBError::new(
+                    "Grouped folds require query groups to be set on the matrix",
+                ));
+            }
+            Ok(group_ptr
+                .windows(2)
+                .map(|bounds| (bounds[0] as usize..bounds[1] as usize).collect())
+                .collect())
+        }
+        _ if group_ptr.len() > 1 => Err(XGBError::new(
+            "Matrix has query groups, use FoldType::Grouped to keep them in the same fold",
+        )),
+        _ => Ok((0..params.dtrain.num_rows()).map(|row| vec![row]).collect()),
+    }
+}
+
+/// Assign each of `num_units` units to one of `nfold` folds, returning the fold of each unit.
+///
+/// Random folds are contiguous runs of (optionally shuffled) units of near-equal size. Stratified folds are built by
+/// dealing out the units of each label value in turn, so every fold has roughly the same label distribution.
+fn assign_folds(num_units: usize, nfold: usize, labels: Option<&[f32]>, shuffle: bool, seed: u64) -> Vec<usize> {
+    let mut rng = SplitMix64(seed);
+    let mut order: Vec<usize> = (0..num_units).collect();
+    if shuffle {
+        rng.shuffle(&mut order);
+    }
+
+    let mut assignments = vec![0; num_units];
+    match labels {
+        Some(labels) => {
+            // stable sort keeps the shuffled order within each label value
+            order.sort_by(|a, b| labels[*a].total_cmp(&labels[*b]));
+            for (i, unit) in order.into_iter().enumerate() {
+                assignments[unit] = i % nfold;
+            }
+        }
+        None => {
+            for (i, unit) in order.into_iter().enumerate() {
+                assignments[unit] = i * nfold / num_units;
+            }
+        }
+    }
+    assignments
+}
+
+/// Compute the mean and (population) standard deviation of each metric across the results of each fold.
+fn aggregate_results(fold_results: &[EvaluationResults]) -> (EvaluationResults, EvaluationResults) {
+    let mut means = EvaluationResults::new();
+    let mut stds = EvaluationResults::new();
+    for (dmat_name, eval_results) in &fold_results[0] {
+        for eval_name in eval_results.keys() {
+            let scores: Vec<f32> = fold_results
+                .iter()
+                .filter_map(|results| results.get(dmat_name).and_then(|results| results.get(eval_name)))
+                .copied()
+                .collect();
+            let mean = scores.iter().sum::<f32>() / scores.len() as f32;
+            let variance = scores.iter().map(|score| (score - mean).powi(2)).sum::<f32>() / scores.len() as f32;
+
+            means
+                .entry(dmat_name.clone())
+                .or_default()
+                .insert(eval_name.clone(), mean);
+            stds.entry(dmat_name.clone())
+                .or_default()
+                .insert(eval_name.clone(), variance.sqrt());
+        }
+    }
+    (means, stds)
+}
+
+/// Format the cross-validation results of a round in the same way as XGBoost's Python package, e.g.
+/// `[0]\ttrain-logloss:0.25+0.01\ttest-logloss:0.5+0.02`.
+fn format_cv_results(iteration: i32, means: &EvaluationResults, stds: &EvaluationResults) -> String {
+    let mut line = format!("[{}]", iteration);
+    for (dmat_name, eval_results) in means {
+        for (eval_name, mean) in eval_results {
+            let std = stds[dmat_name][eval_name];
+            line.push_str(&format!("\t{}-{}:{}+{}", dmat_name, eval_name, mean, std));
+        }
+    }
+    line
+}
+
+/// Minimal [SplitMix64](https://prng.di.unimi.it/splitmix64.c) generator, so folds are reproducible from a seed.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Shuffle `items` in place using the Fisher-Yates algorithm.
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = (self.next_u64() % (i as u64 + 1)) as usize;
+            items.swap(i, j);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parameters::{self, learning, CrossValidationParametersBuilder, EarlyStoppingParametersBuilder};
+
+    fn fold_sizes(assignments: &[usize], nfold: usize) -> Vec<usize> {
+        let mut sizes = vec![0; nfold];
+        for fold in assignments {
+            sizes[*fold] += 1;
+        }
+        sizes
+    }
+
+    #[test]
+    fn assign_random_folds() {
+        let assignments = assign_folds(10, 3, None, false, 0);
+        assert_eq!(assignments, vec![0, 0, 0, 0, 1, 1, 1, 2, 2, 2]);
+
+        let shuffled = assign_folds(10, 3, None, true, 42);
+        assert_ne!(shuffled, assignments);
+        assert_eq!(fold_sizes(&shuffled, 3), vec![4, 3, 3]);
+        assert_eq!(shuffled, assign_folds(10, 3, None, true, 42));
+    }
+
+    #[test]
+    fn assign_stratified_folds() {
+        let labels: Vec<f32> = (0..12).map(|i| if i < 9 { 0.0 } else { 1.0 }).collect();
+        let assignments = assign_folds(labels.len(), 3, Some(&labels), true, 7);
+        assert_eq!(fold_sizes(&assignments, 3), vec![4, 4, 4]);
+
+        // each fold gets 3 negative and 1 positive rows
+        for fold in 0..3 {
+            let positives = (0..labels.len())
+                .filter(|row| assignments[*row] == fold && labels[*row] > 0.5)
+                .count();
+            assert_eq!(positives, 1);
+        }
+    }
+
+    #[test]
+    fn aggregate() {
+        let mut fold_results = Vec::new();
+        for score in [1.0, 3.0] {
+            let mut results = EvaluationResults::new();
+            results
+                .entry("test".to_owned())
+                .or_default()
+                .insert("rmse".to_owned(), score);
+            fold_results.push(results);
+        }
+
+        let (means, stds) = aggregate_results(&fold_results);
+        assert_eq!(means["test"]["rmse"], 2.0);
+        assert_eq!(stds["test"]["rmse"], 1.0);
+        assert_eq!(format_cv_results(0, &means, &stds), "[0]\ttest-rmse:2+1");
+    }
+
+    #[test]
+    fn cross_validate() {
+        let dtrain =
+            DMatrix::load(r#"{"uri": "xgboost-sys/xgboost/demo/data/agaricus.txt.train?format=libsvm"}"#).unwrap();
+
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::BinaryLogistic)
+            .eval_metrics(learning::Metrics::Custom(vec![learning::EvaluationMetric::LogLoss]))
+            .build()
+            .unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .learning_params(learning_params)
+            .verbose(false)
+            .build()
+            .unwrap();
+        let cv_params = CrossValidationParametersBuilder::default()
+            .dtrain(&dtrain)
+            .nfold(4)
+            .fold_type(FoldType::Stratified)
+            .boost_rounds(5)
+            .booster_params(booster_params.clone())
+            .evaluation_output(parameters::EvaluationOutput::Silent)
+            .build()
+            .unwrap();
+
+        let results = cv(&cv_params).unwrap();
+        assert_eq!(results.num_rounds(), 5);
+        assert_eq!(results.best_iteration(), None);
+        let test_logloss = &results.means()["test"]["logloss"];
+        assert!(test_logloss[4] < test_logloss[0]);
+        assert!(results.stds()["train"]["logloss"].iter().all(|std| *std >= 0.0));
+
+        let early_stopping = EarlyStoppingParametersBuilder::default().rounds(2).build().unwrap();
+        let cv_params = CrossValidationParametersBuilder::default()
+            .dtrain(&dtrain)
+            .boost_rounds(200)
+            .booster_params(booster_params)
+            .evaluation_output(parameters::EvaluationOutput::Silent)
+            .early_stopping(Some(early_stopping))
+            .build()
+            .unwrap();
+
+        let results = cv(&cv_params).unwrap();
+        let best_iteration = results.best_iteration().unwrap();
+        assert!(best_iteration < 199);
+        assert_eq!(results.num_rounds(), best_iteration as usize + 1);
+    }
+
+    #[test]
+    fn cross_validate_grouped() {
+        // 12 queries of 5 documents, where relevance increases with the first feature
+        let num_groups = 12;
+        let mut data = Vec::new();
+        let mut labels = Vec::new();
+        for group in 0..num_groups {
+            for doc in 0..5 {
+                data.extend([doc as f32, ((group * 7 + doc * 3) % 5) as f32]);
+                labels.push(if doc >= 3 { 1.0 } else { 0.0 });
+            }
+        }
+        let mut dtrain = DMatrix::from_dense(&data, labels.len()).unwrap();
+        dtrain.set_labels(&labels).unwrap();
+        dtrain.set_group(&vec![5; num_groups]).unwrap();
+
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::RankPairwise)
+            .eval_metrics(learning::Metrics::Custom(vec![learning::EvaluationMetric::NDCG]))
+            .build()
+            .unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .learning_params(learning_params)
+            .verbose(false)
+            .build()
+            .unwrap();
+        let cv_params = CrossValidationParametersBuilder::default()
+            .dtrain(&dtrain)
+            .nfold(3)
+            .fold_type(FoldType::Grouped)
+            .boost_rounds(3)
+            .booster_params(booster_params)
+            .evaluation_output(parameters::EvaluationOutput::Silent)
+            .build()
+            .unwrap();
+
+        let results = cv(&cv_params).unwrap();
+        assert_eq!(results.num_rounds(), 3);
+        let test_ndcg = &results.means()["test"]["ndcg"];
+        assert!(test_ndcg.iter().all(|ndcg| (0.0..=1.0).contains(ndcg)));
+        assert!(test_ndcg[2] > 0.9);
+    }
+
+    #[test]
+    fn cross_validate_grouped_without_groups() {
+        let mut dtrain = DMatrix::from_dense(&[1.0, 0.0, 1.0, 1.0, 0.0, 0.0], 3).unwrap();
+        dtrain.set_labels(&[1.0, 0.0, 1.0]).unwrap();
+
+        let cv_params = CrossValidationParametersBuilder::default()
+            .dtrain(&dtrain)
+            .fold_type(FoldType::Grouped)
+            .build()
+            .unwrap();
+        assert!(cv(&cv_params).is_err());
+    }
+
+    #[test]
+    fn invalid_nfold() {
+        let dtrain = DMatrix::from_dense(&[1.0, 0.0], 2).unwrap();
+        let result = CrossValidationParametersBuilder::default()
+            .dtrain(&dtrain)
+            .nfold(1)
+            .build();
+        assert!(result.is_err());
+    }
+}
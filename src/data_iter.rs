@@ -0,0 +1,393 @@
+//! Streaming construction of matrices from batches of data, for datasets which are too large to hold in memory at
+//! once.
+//!
+//! Implement [`DataIter`](trait.DataIter.html) to yield the data in batches, then create a matrix from it with
+//! [`DMatrix::from_data_iter`](struct.DMatrix.html#method.from_data_iter) (external memory, cached on disk) or
+//! [`QuantileDMatrix::from_data_iter`](struct.QuantileDMatrix.html#method.from_data_iter) (quantised in memory).
+use std::error::Error;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::{fmt, os::raw};
+
+use super::{DMatrix, XGBError, XGBResult};
+use crate::array_interface;
+
+/// Source of data for matrices created batch by batch, see
+/// [`DMatrix::from_data_iter`](struct.DMatrix.html#method.from_data_iter).
+///
+/// XGBoost may go over the data several times while creating a matrix, calling
+/// [`reset`](trait.DataIter.html#tymethod.reset) before each pass after the first. Every pass must yield the same
+/// batches in the same order.
+///
+/// # Example
+///
+/// Yield a matrix in batches of 2 rows:
+///
+/// ```
+/// use std::error::Error;
+/// use xgb::{Batch, DataIter};
+///
+/// struct Chunks {
+///     data: Vec<f32>,
+///     labels: Vec<f32>,
+///     num_cols: usize,
+///     row: usize,
+/// }
+///
+/// impl DataIter for Chunks {
+///     fn next(&mut self) -> Result<Option<Batch>, Box<dyn Error>> {
+///         if self.row >= self.labels.len() {
+///             return Ok(None);
+///         }
+///         let end = (self.row + 2).min(self.labels.len());
+///         let data = self.data[self.row * self.num_cols..end * self.num_cols].to_vec();
+///         let batch = Batch::from_dense(data, end - self.row).with_labels(self.labels[self.row..end].to_vec());
+///         self.row = end;
+///         Ok(Some(batch))
+///     }
+///
+///     fn reset(&mut self) {
+///         self.row = 0;
+///     }
+/// }
+/// ```
+pub trait DataIter {
+    /// Get the next batch of data, or `None` once all batches of this pass have been returned.
+    ///
+    /// Returning an error stops creating the matrix, and is returned by the constructor.
+    fn next(&mut self) -> Result<Option<Batch>, Box<dyn Error>>;
+
+    /// Go back to the first batch, to start another pass over the data.
+    fn reset(&mut self);
+}
+
+/// Batch of rows yielded by a [`DataIter`](trait.DataIter.html), with optional labels and weights for each row.
+pub struct Batch {
+    data: BatchData,
+    labels: Option<Vec<f32>>,
+    weights: Option<Vec<f32>>,
+}
+
+enum BatchData {
+    Dense {
+        data: Vec<f32>,
+        num_rows: usize,
+    },
+    Csr {
+        indptr: Vec<usize>,
+        indices: Vec<usize>,
+        data: Vec<f32>,
+        num_cols: usize,
+    },
+    #[cfg(feature = "arrow")]
+    RecordBatch(arrow_array::RecordBatch),
+}
+
+impl Batch {
+    /// Create a batch from a dense array in row-major order, in the same way as
+    /// [`DMatrix::from_dense`](struct.DMatrix.html#method.from_dense).
+    pub fn from_dense(data: Vec<f32>, num_rows: usize) -> Self {
+        Batch::new(BatchData::Dense { data, num_rows })
+    }
+
+    /// Create a batch from a sparse CSR matrix, in the same way as
+    /// [`DMatrix::from_csr`](struct.DMatrix.html#method.from_csr). All batches must have the same number of columns.
+    pub fn from_csr(indptr: Vec<usize>, indices: Vec<usize>, data: Vec<f32>, num_cols: usize) -> Self {
+        Batch::new(BatchData::Csr {
+            indptr,
+            indices,
+            data,
+            num_cols,
+        })
+    }
+
+    /// Create a batch from an Arrow record batch, in the same way as
+    /// [`DMatrix::from_record_batch`](struct.DMatrix.html#method.from_record_batch).
+    #[cfg(feature = "arrow")]
+    pub fn from_record_batch(batch: arrow_array::RecordBatch) -> Self {
+        Batch::new(BatchData::RecordBatch(batch))
+    }
+
+    fn new(data: BatchData) -> Self {
+        Batch {
+            data,
+            labels: None,
+            weights: None,
+        }
+    }
+
+    /// Set ground truth labels for each row of this batch.
+    pub fn with_labels(mut self, labels: Vec<f32>) -> Self {
+        self.labels = Some(labels);
+        self
+    }
+
+    /// Set weights of each row of this batch.
+    pub fn with_weights(mut self, weights: Vec<f32>) -> Self {
+        self.weights = Some(weights);
+        self
+    }
+}
+
+/// Borrowed data of a single batch, used to create quantised matrices from in-memory data without copying it.
+pub(crate) enum SliceBatch<'a> {
+    Dense {
+        data: &'a [f32],
+        num_rows: usize,
+    },
+    Csr {
+        indptr: &'a [usize],
+        indices: &'a [usize],
+        data: &'a [f32],
+        num_cols: usize,
+    },
+}
+
+/// Source of batches which are passed to XGBoost through a proxy matrix.
+trait BatchSource {
+    /// Pass the next batch to `proxy`, returning whether there was one.
+    fn next(&mut self, proxy: &mut DMatrix) -> XGBResult<bool>;
+
+    /// Go back to the first batch.
+    fn reset(&mut self);
+}
+
+/// Batches yielded by a user's [`DataIter`](trait.DataIter.html).
+struct IterSource<I> {
+    iter: I,
+
+    /// Batch the proxy currently refers to, along with any validity masks of its Arrow columns.
+    batch: Option<Batch>,
+    #[cfg(feature = "arrow")]
+    arrow_columns: Vec<array_interface::ArrowColumn>,
+}
+
+impl<I: DataIter> BatchSource for IterSource<I> {
+    fn next(&mut self, proxy: &mut DMatrix) -> XGBResult<bool> {
+        let batch = match self.iter.next() {
+            Ok(Some(batch)) => batch,
+            Ok(None) => return Ok(false),
+            Err(err) => return Err(XGBError::new(format!("Data iterator failed: {}", err))),
+        };
+
+        // values are referred to by address, which stays the same when the batch is moved into self below
+        match &batch.data {
+            BatchData::Dense { data, num_rows } => set_dense(proxy, data, *num_rows)?,
+            BatchData::Csr {
+                indptr,
+                indices,
+                data,
+                num_cols,
+            } => set_csr(proxy, indptr, indices, data, *num_cols)?,
+            #[cfg(feature = "arrow")]
+            BatchData::RecordBatch(record_batch) => self.arrow_columns = set_record_batch(proxy, record_batch)?,
+        }
+
+        if let Some(labels) = &batch.labels {
+            proxy.set_labels(labels)?;
+        }
+        if let Some(weights) = &batch.weights {
+            proxy.set_weights(weights)?;
+        }
+        self.batch = Some(batch);
+        Ok(true)
+    }
+
+    fn reset(&mut self) {
+        self.iter.reset();
+    }
+}
+
+/// A single batch of borrowed data.
+struct SliceSource<'a> {
+    batch: SliceBatch<'a>,
+    done: bool,
+}
+
+impl BatchSource for SliceSource<'_> {
+    fn next(&mut self, proxy: &mut DMatrix) -> XGBResult<bool> {
+        if self.done {
+            return Ok(false);
+        }
+        match self.batch {
+            SliceBatch::Dense { data, num_rows } => set_dense(proxy, data, num_rows)?,
+            SliceBatch::Csr {
+                indptr,
+                indices,
+                data,
+                num_cols,
+            } => set_csr(proxy, indptr, indices, data, num_cols)?,
+        }
+        self.done = true;
+        Ok(true)
+    }
+
+    fn reset(&mut self) {
+        self.done = false;
+    }
+}
+
+fn set_dense(proxy: &DMatrix, data: &[f32], num_rows: usize) -> XGBResult<()> {
+    if num_rows == 0 || !data.len().is_multiple_of(num_rows) {
+        let msg = format!(
+            "Dense batch of length {} can't be split into {} rows",
+            data.len(),
+            num_rows
+        );
+        return Err(XGBError::new(msg));
+    }
+    let values = array_interface::array_interface(data, &[num_rows, data.len() / num_rows]);
+    xgb_call!(xgboost_sys::XGProxyDMatrixSetDataDense(proxy.handle, values.as_ptr()))
+}
+
+fn set_csr(proxy: &DMatrix, indptr: &[usize], indices: &[usize], data: &[f32], num_cols: usize) -> XGBResult<()> {
+    DMatrix::check_sparse(indptr, indices, data)?;
+    let indptr = array_interface::array_interface(indptr, &[indptr.len()]);
+    let indices = array_interface::array_interface(indices, &[indices.len()]);
+    let values = array_interface::array_interface(data, &[data.len()]);
+    xgb_call!(xgboost_sys::XGProxyDMatrixSetDataCSR(
+        proxy.handle,
+        indptr.as_ptr(),
+        indices.as_ptr(),
+        values.as_ptr(),
+        num_cols as xgboost_sys::bst_ulong
+    ))
+}
+
+/// Pass the columns of a record batch to the proxy, returning their descriptions which must be kept alive as long
+/// as the proxy refers to them.
+#[cfg(feature = "arrow")]
+fn set_record_batch(
+    proxy: &DMatrix,
+    record_batch: &arrow_array::RecordBatch,
+) -> XGBResult<Vec<array_interface::ArrowColumn>> {
+    let mut arrow_columns = Vec::with_capacity(record_batch.num_columns());
+    for column in record_batch.columns() {
+        arrow_columns.push(array_interface::arrow_column(column.as_ref()).map_err(XGBError::new)?);
+    }
+    let json: Vec<&str> = arrow_columns.iter().map(|column| column.json.as_str()).collect();
+    let data = std::ffi::CString::new(format!("[{}]", json.join(","))).unwrap();
+    xgb_call!(xgboost_sys::XGProxyDMatrixSetDataColumnar(proxy.handle, data.as_ptr()))?;
+    Ok(arrow_columns)
+}
+
+/// Passes batches to XGBoost through a proxy matrix, from the callbacks XGBoost calls while creating a matrix.
+///
+/// Kept alive alongside external memory matrices, as XGBoost may keep using the callbacks afterwards. The callbacks
+/// only get shared access to the proxy, with its mutable state behind a lock.
+pub(crate) struct DataIterProxy<'a> {
+    state: Mutex<ProxyState<'a>>,
+
+    /// Handle of the proxy matrix, passed to XGBoost along with the callbacks.
+    proxy_handle: xgboost_sys::DMatrixHandle,
+
+    /// Held for the whole of every XGBoost call using a matrix created from this proxy, so passes over the batches
+    /// never overlap, see `DMatrix::iter_guards`.
+    usage: Mutex<()>,
+}
+
+struct ProxyState<'a> {
+    source: Box<dyn BatchSource + 'a>,
+    proxy: DMatrix,
+
+    /// First error raised while passing batches, reported once XGBoost returns.
+    error: Option<XGBError>,
+}
+
+impl<'a> DataIterProxy<'a> {
+    /// Create a proxy passing the batches yielded by given iterator.
+    pub(crate) fn from_iter<I: DataIter + 'a>(iter: I) -> XGBResult<Box<Self>> {
+        let source = IterSource {
+            iter,
+            batch: None,
+            #[cfg(feature = "arrow")]
+            arrow_columns: Vec::new(),
+        };
+        DataIterProxy::new(Box::new(source))
+    }
+
+    /// Create a proxy passing a single batch of borrowed data.
+    pub(crate) fn from_slices(batch: SliceBatch<'a>) -> XGBResult<Box<Self>> {
+        DataIterProxy::new(Box::new(SliceSource { batch, done: false }))
+    }
+
+    fn new(source: Box<dyn BatchSource + 'a>) -> XGBResult<Box<Self>> {
+        let proxy = DMatrix::proxy()?;
+        Ok(Box::new(DataIterProxy {
+            proxy_handle: proxy.handle,
+            state: Mutex::new(ProxyState {
+                source,
+                proxy,
+                error: None,
+            }),
+            usage: Mutex::new(()),
+        }))
+    }
+
+    /// Handle passed to XGBoost's callbacks, pointing to this proxy.
+    pub(crate) fn as_handle(&self) -> xgboost_sys::DataIterHandle {
+        self as *const DataIterProxy as xgboost_sys::DataIterHandle
+    }
+
+    /// Handle of the proxy matrix batches are passed through.
+    pub(crate) fn proxy_handle(&self) -> xgboost_sys::DMatrixHandle {
+        self.proxy_handle
+    }
+
+    /// Take the error raised while XGBoost was creating a matrix, if any.
+    pub(crate) fn take_error(&self) -> Option<XGBError> {
+        self.lock_state().error.take()
+    }
+
+    /// Lock this proxy for an XGBoost call using a matrix created from it.
+    pub(crate) fn lock_usage(&self) -> MutexGuard<'_, ()> {
+        // a panic while holding the lock can't leave the unit value in an inconsistent state
+        self.usage.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn lock_state(&self) -> MutexGuard<'_, ProxyState<'a>> {
+        // panics of the iterator are caught while holding the lock, so the state is never left half updated
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl fmt::Debug for DataIterProxy<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DataIterProxy")
+            .field("proxy_handle", &self.proxy_handle)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Callback XGBoost calls to get the next batch, returning 0 once there are no more batches.
+///
+/// Errors and panics from the iterator can't cross into XGBoost, so are stored and treated as the end of the data.
+pub(crate) unsafe extern "C" fn next_callback(handle: xgboost_sys::DataIterHandle) -> raw::c_int {
+    let data_iter = &*(handle as *const DataIterProxy);
+    let mut state = data_iter.lock_state();
+    let ProxyState { source, proxy, error } = &mut *state;
+    if error.is_some() {
+        return 0;
+    }
+    match panic::catch_unwind(AssertUnwindSafe(|| source.next(proxy))) {
+        Ok(Ok(has_batch)) => has_batch as raw::c_int,
+        Ok(Err(err)) => {
+            *error = Some(err);
+            0
+        }
+        Err(_) => {
+            *error = Some(XGBError::new("Data iterator panicked"));
+            0
+        }
+    }
+}
+
+/// Callback XGBoost calls to start another pass over the data.
+pub(crate) unsafe extern "C" fn reset_callback(handle: xgboost_sys::DataIterHandle) {
+    let data_iter = &*(handle as *const DataIterProxy);
+    let mut state = data_iter.lock_state();
+    let ProxyState { source, error, .. } = &mut *state;
+    if panic::catch_unwind(AssertUnwindSafe(|| source.reset())).is_err() && error.is_none() {
+        *error = Some(XGBError::new("Data iterator panicked"));
+    }
+}
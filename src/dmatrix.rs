@@ -1,13 +1,105 @@
 use libc::{c_float, c_uint};
+use std::str::FromStr;
+use std::sync::MutexGuard;
 use std::{ffi, path::Path, ptr, slice};
 
-use super::{XGBError, XGBResult};
+use super::{DataIter, FeatureType, QuantileDMatrix, QuantileDMatrixParameters, XGBError, XGBResult};
+use crate::array_interface;
+use crate::data_iter::{self, DataIterProxy};
 
 static KEY_GROUP_PTR: &str = "group_ptr";
 static KEY_GROUP: &str = "group";
 static KEY_LABEL: &str = "label";
 static KEY_WEIGHT: &str = "weight";
 static KEY_BASE_MARGIN: &str = "base_margin";
+static KEY_FEATURE_NAME: &str = "feature_name";
+static KEY_FEATURE_TYPE: &str = "feature_type";
+
+/// Parameters used when creating a [`DMatrix`](struct.DMatrix.html) from in-memory data, e.g. with
+/// [`DMatrix::from_dense_with_params`](struct.DMatrix.html#method.from_dense_with_params).
+/// Created using [`DMatrixParametersBuilder`](struct.DMatrixParametersBuilder.html).
+///
+/// # Example
+///
+/// ```
+/// use xgb::{DMatrix, DMatrixParametersBuilder};
+///
+/// // -999.0 marks missing values
+/// let params = DMatrixParametersBuilder::default().missing(-999.0).nthread(Some(4)).build().unwrap();
+/// let dmat = DMatrix::from_dense_with_params(&[1.0, -999.0, 3.0, 4.0], 2, &params).unwrap();
+/// assert_eq!(dmat.num_missing().unwrap(), 1);
+/// ```
+#[derive(Builder, Clone, Debug)]
+#[builder(build_fn(validate = "Self::validate"))]
+pub struct DMatrixParameters {
+    /// Value in the input data treated as missing. Values which aren't stored in sparse matrices are always
+    /// treated as missing.
+    ///
+    /// *default*: `f32::NAN`
+    #[builder(default = "f32::NAN")]
+    missing: f32,
+
+    /// Number of threads used to create the matrix.
+    ///
+    /// *default*: `None` (use all available threads)
+    #[builder(default = "None")]
+    nthread: Option<u32>,
+}
+
+impl Default for DMatrixParameters {
+    fn default() -> Self {
+        DMatrixParameters {
+            missing: f32::NAN,
+            nthread: None,
+        }
+    }
+}
+
+impl DMatrixParameters {
+    pub fn missing(&self) -> f32 {
+        self.missing
+    }
+
+    pub fn set_missing(&mut self, missing: f32) {
+        self.missing = missing;
+    }
+
+    pub fn nthread(&self) -> Option<u32> {
+        self.nthread
+    }
+
+    pub fn set_nthread(&mut self, nthread: Option<u32>) {
+        self.nthread = nthread;
+    }
+
+    /// Returns 0 terminated json of the parameters, as expected by XGBoost's `XGDMatrixCreateFrom*` functions.
+    fn as_json(&self) -> ffi::CString {
+        self.as_json_with(&[])
+    }
+
+    /// Returns 0 terminated json of the parameters, along with additional (key, JSON value) fields.
+    pub(crate) fn as_json_with(&self, fields: &[(&str, String)]) -> ffi::CString {
+        let mut json = format!(
+            "{{\"missing\":{},\"nthread\":{}",
+            crate::json_float(self.missing),
+            self.nthread.unwrap_or(0)
+        );
+        for (key, value) in fields {
+            json.push_str(&format!(",\"{}\":{}", key, value));
+        }
+        json.push('}');
+        ffi::CString::new(json).unwrap()
+    }
+}
+
+impl DMatrixParametersBuilder {
+    fn validate(&self) -> Result<(), String> {
+        if self.nthread == Some(Some(0)) {
+            return Err("Invalid value for 'nthread' parameter, must be at least 1.".to_owned());
+        }
+        Ok(())
+    }
+}
 
 /// Data matrix used throughout XGBoost for training/predicting [`Booster`](struct.Booster.html) models.
 ///
@@ -64,13 +156,35 @@ static KEY_BASE_MARGIN: &str = "base_margin";
 /// let dmat = DMatrix::from_csc(indptr, indices, data, None).unwrap();
 /// assert_eq!(dmat.shape(), (3, 3));
 /// ```
+///
+/// # Thread safety
+///
+/// A DMatrix can be moved to and shared between threads, e.g. to predict on the same matrix with a
+/// [`Booster`](struct.Booster.html) from several threads at once. Methods taking `&self` only read the matrix, anything
+/// modifying it (labels, weights, feature names, ...) takes `&mut self`.
+///
+/// Matrices created by [`from_data_iter`](struct.DMatrix.html#method.from_data_iter) are the exception: XGBoost may
+/// go over their batches again whenever they're used, so calls into XGBoost using the same such matrix (training,
+/// evaluating or predicting) are serialized, and never run concurrently.
 #[derive(Debug)]
 pub struct DMatrix {
     pub(super) handle: xgboost_sys::DMatrixHandle,
     num_rows: usize,
     num_cols: usize,
+
+    /// Iterator this matrix was created from, if any, which XGBoost may still call back into.
+    data_iter: Option<Box<DataIterProxy<'static>>>,
 }
 
+// SAFETY: the DMatrix owns its handle, which XGBoost allows to be used from any thread, and iterators kept alive for
+// external memory matrices are required to be `Send`.
+unsafe impl Send for DMatrix {}
+
+// SAFETY: methods taking `&self` only read the matrix. Every XGBoost call which may iterate over the batches of a
+// matrix created from an iterator holds the lock from `iter_guards`, so passes over the batches never overlap, and the
+// callbacks only reach the iterator through the proxy's own lock.
+unsafe impl Sync for DMatrix {}
+
 impl DMatrix {
     /// Construct a new instance from a DMatrixHandle created by the XGBoost C API.
     fn new(handle: xgboost_sys::DMatrixHandle) -> XGBResult<Self> {
@@ -89,6 +203,20 @@ impl DMatrix {
             handle,
             num_rows,
             num_cols,
+            data_iter: None,
+        })
+    }
+
+    /// Create an empty proxy matrix, which refers to batches of data passed to XGBoost by a
+    /// [`DataIter`](trait.DataIter.html).
+    pub(crate) fn proxy() -> XGBResult<Self> {
+        let mut handle = ptr::null_mut();
+        xgb_call!(xgboost_sys::XGProxyDMatrixCreate(&mut handle))?;
+        Ok(DMatrix {
+            handle,
+            num_rows: 0,
+            num_cols: 0,
+            data_iter: None,
         })
     }
 
@@ -170,6 +298,290 @@ impl DMatrix {
         DMatrix::new(handle)
     }
 
+    /// Create a new `DMatrix` from dense array in row-major order, in the same way as
+    /// [`from_dense`](struct.DMatrix.html#method.from_dense), using given value as missing and thread count.
+    pub fn from_dense_with_params(data: &[f32], num_rows: usize, params: &DMatrixParameters) -> XGBResult<Self> {
+        let num_cols = data.len().checked_div(num_rows).unwrap_or(0);
+        if num_rows * num_cols != data.len() {
+            let msg = format!("Data of length {} can't be split into {} rows", data.len(), num_rows);
+            return Err(XGBError::new(msg));
+        }
+
+        let data = array_interface::array_interface(data, &[num_rows, num_cols]);
+        let mut handle = ptr::null_mut();
+        xgb_call!(xgboost_sys::XGDMatrixCreateFromDense(
+            data.as_ptr(),
+            params.as_json().as_ptr(),
+            &mut handle
+        ))?;
+        DMatrix::new(handle)
+    }
+
+    /// Create a new `DMatrix` from a sparse CSR matrix, in the same way as
+    /// [`from_csr`](struct.DMatrix.html#method.from_csr), using given value as missing and thread count.
+    pub fn from_csr_with_params(
+        indptr: &[usize],
+        indices: &[usize],
+        data: &[f32],
+        num_cols: Option<usize>,
+        params: &DMatrixParameters,
+    ) -> XGBResult<Self> {
+        DMatrix::check_sparse(indptr, indices, data)?;
+        let indptr = array_interface::array_interface(indptr, &[indptr.len()]);
+        let indices = array_interface::array_interface(indices, &[indices.len()]);
+        let data = array_interface::array_interface(data, &[data.len()]);
+        let num_cols = num_cols.unwrap_or(0); // infer from data if 0
+        let mut handle = ptr::null_mut();
+        xgb_call!(xgboost_sys::XGDMatrixCreateFromCSR(
+            indptr.as_ptr(),
+            indices.as_ptr(),
+            data.as_ptr(),
+            num_cols as xgboost_sys::bst_ulong,
+            params.as_json().as_ptr(),
+            &mut handle
+        ))?;
+        DMatrix::new(handle)
+    }
+
+    /// Create a new `DMatrix` from a sparse CSC matrix, in the same way as
+    /// [`from_csc`](struct.DMatrix.html#method.from_csc), using given value as missing and thread count.
+    pub fn from_csc_with_params(
+        indptr: &[usize],
+        indices: &[usize],
+        data: &[f32],
+        num_rows: Option<usize>,
+        params: &DMatrixParameters,
+    ) -> XGBResult<Self> {
+        DMatrix::check_sparse(indptr, indices, data)?;
+        let indptr = array_interface::array_interface(indptr, &[indptr.len()]);
+        let indices = array_interface::array_interface(indices, &[indices.len()]);
+        let data = array_interface::array_interface(data, &[data.len()]);
+        let num_rows = num_rows.unwrap_or(0); // infer from data if 0
+        let mut handle = ptr::null_mut();
+        xgb_call!(xgboost_sys::XGDMatrixCreateFromCSC(
+            indptr.as_ptr(),
+            indices.as_ptr(),
+            data.as_ptr(),
+            num_rows as xgboost_sys::bst_ulong,
+            params.as_json().as_ptr(),
+            &mut handle
+        ))?;
+        DMatrix::new(handle)
+    }
+
+    /// Create a new `DMatrix` from columns of numeric Arrow arrays, one per feature, using given parameters.
+    ///
+    /// Supports `Float32`, `Float64`, `Int32`, `Int64`, `UInt32` and `UInt64` arrays, which are read by XGBoost
+    /// directly from Arrow's memory. Null entries are treated as missing, in addition to the missing value set in
+    /// `params`.
+    ///
+    /// ```
+    /// use arrow_array::{Array, Float32Array, Int64Array};
+    /// use xgb::{DMatrix, DMatrixParameters};
+    ///
+    /// let x = Float32Array::from(vec![Some(1.0), None, Some(3.0)]);
+    /// let y = Int64Array::from(vec![4, 5, 6]);
+    /// let columns: &[&dyn Array] = &[&x, &y];
+    /// let dmat = DMatrix::from_arrow_columns(columns, &DMatrixParameters::default()).unwrap();
+    /// assert_eq!(dmat.shape(), (3, 2));
+    /// ```
+    #[cfg(feature = "arrow")]
+    pub fn from_arrow_columns(columns: &[&dyn arrow_array::Array], params: &DMatrixParameters) -> XGBResult<Self> {
+        let num_rows = match columns.first() {
+            Some(column) => column.len(),
+            None => return Err(XGBError::new("Can't create a DMatrix from no columns")),
+        };
+        let mut arrow_columns = Vec::with_capacity(columns.len());
+        for (i, column) in columns.iter().enumerate() {
+            if column.len() != num_rows {
+                let msg = format!(
+                    "Column {} has {} rows, but the first column has {} rows",
+                    i,
+                    column.len(),
+                    num_rows
+                );
+                return Err(XGBError::new(msg));
+            }
+            arrow_columns.push(array_interface::arrow_column(*column).map_err(XGBError::new)?);
+        }
+
+        let json: Vec<&str> = arrow_columns.iter().map(|column| column.json.as_str()).collect();
+        let data = ffi::CString::new(format!("[{}]", json.join(","))).unwrap();
+        let mut handle = ptr::null_mut();
+        xgb_call!(xgboost_sys::XGDMatrixCreateFromColumnar(
+            data.as_ptr(),
+            params.as_json().as_ptr(),
+            &mut handle
+        ))?;
+        DMatrix::new(handle)
+    }
+
+    /// Create a new `DMatrix` from an Arrow record batch, in the same way as
+    /// [`from_arrow_columns`](struct.DMatrix.html#method.from_arrow_columns), using the names of the batch's fields as
+    /// feature names.
+    #[cfg(feature = "arrow")]
+    pub fn from_record_batch(batch: &arrow_array::RecordBatch, params: &DMatrixParameters) -> XGBResult<Self> {
+        let columns: Vec<&dyn arrow_array::Array> = batch.columns().iter().map(|column| column.as_ref()).collect();
+        let mut dmat = DMatrix::from_arrow_columns(&columns, params)?;
+
+        let schema = batch.schema();
+        let feature_names: Vec<&str> = schema.fields().iter().map(|field| field.name().as_str()).collect();
+        dmat.set_str_feature_info(KEY_FEATURE_NAME, &feature_names)?;
+        Ok(dmat)
+    }
+
+    /// Create a new external memory `DMatrix` from batches of data yielded by given iterator.
+    ///
+    /// Batches are written to cache files starting with `cache_prefix` (e.g. `/tmp/xgb-cache`) as they're read, so the
+    /// full dataset is never held in memory. Use
+    /// [`QuantileDMatrix::from_data_iter`](struct.QuantileDMatrix.html#method.from_data_iter) instead if the
+    /// quantised data fits in memory, which is faster to train with.
+    ///
+    /// Returns the first error raised by the iterator, if any. XGBoost may go over the batches again whenever the matrix
+    /// is used, so the iterator is kept alive by the matrix and must be `Send`. The iterator is only ever called from
+    /// one thread at a time: when the matrix is shared between threads, training, evaluating and predicting with it
+    /// are serialized (see [Thread safety](struct.DMatrix.html#thread-safety)).
+    ///
+    /// ```no_run
+    /// use xgb::{Batch, DMatrix, DMatrixParameters, DataIter};
+    /// # use std::error::Error;
+    /// # struct Files;
+    /// # impl DataIter for Files {
+    /// #     fn next(&mut self) -> Result<Option<Batch>, Box<dyn Error>> { Ok(None) }
+    /// #     fn reset(&mut self) {}
+    /// # }
+    ///
+    /// // reads one batch per file, see `DataIter` for how to implement it
+    /// let files = Files;
+    /// let dmat = DMatrix::from_data_iter(files, "/tmp/xgb-cache", &DMatrixParameters::default()).unwrap();
+    /// ```
+    pub fn from_data_iter<I: DataIter + Send + 'static>(
+        iter: I,
+        cache_prefix: &str,
+        params: &DMatrixParameters,
+    ) -> XGBResult<Self> {
+        let config = params.as_json_with(&[("cache_prefix", crate::json_string(cache_prefix))]);
+        let data_iter = DataIterProxy::from_iter(iter)?;
+        let mut handle = ptr::null_mut();
+        let result = xgb_call!(xgboost_sys::XGDMatrixCreateFromCallback(
+            data_iter.as_handle(),
+            data_iter.proxy_handle(),
+            Some(data_iter::reset_callback),
+            Some(data_iter::next_callback),
+            config.as_ptr(),
+            &mut handle
+        ));
+        let mut dmat = DMatrix::from_proxy_result(result, handle, &data_iter)?;
+        dmat.data_iter = Some(data_iter);
+        Ok(dmat)
+    }
+
+    /// Create a new `DMatrix` from batches of data yielded by given iterator, which only stores the data quantised
+    /// into at most `max_bin` bins per feature.
+    #[deprecated(note = "use `QuantileDMatrix::from_data_iter` instead")]
+    pub fn from_data_iter_quantile<I: DataIter + 'static>(
+        iter: I,
+        max_bin: u32,
+        params: &DMatrixParameters,
+    ) -> XGBResult<Self> {
+        let mut quantile_params = QuantileDMatrixParameters::default();
+        quantile_params.set_max_bin(max_bin);
+        quantile_params.set_missing(params.missing());
+        quantile_params.set_nthread(params.nthread());
+        Ok(QuantileDMatrix::from_data_iter(iter, &quantile_params)?.into_dmatrix())
+    }
+
+    /// Wrap the handle of a matrix created from batches passed through given proxy. Errors raised while passing
+    /// batches take precedence over XGBoost's, as they're usually the cause of them.
+    pub(crate) fn from_proxy_result(
+        result: XGBResult<()>,
+        handle: xgboost_sys::DMatrixHandle,
+        data_iter: &DataIterProxy,
+    ) -> XGBResult<Self> {
+        match (result, data_iter.take_error()) {
+            (Ok(()), Some(err)) => {
+                xgb_call!(xgboost_sys::XGDMatrixFree(handle))?;
+                Err(err)
+            }
+            (Err(_), Some(err)) | (Err(err), None) => Err(err),
+            (Ok(()), None) => DMatrix::new(handle),
+        }
+    }
+
+    /// Lock the iterators of given matrices created by [`from_data_iter`](struct.DMatrix.html#method.from_data_iter),
+    /// for the duration of an XGBoost call using them.
+    ///
+    /// Each iterator is locked once, in a consistent order, so calls using several matrices can't deadlock.
+    pub(crate) fn iter_guards<'a>(dmats: &[&'a DMatrix]) -> Vec<MutexGuard<'a, ()>> {
+        let mut data_iters: Vec<&DataIterProxy> = dmats.iter().filter_map(|dmat| dmat.data_iter.as_deref()).collect();
+        data_iters.sort_by_key(|data_iter| *data_iter as *const DataIterProxy);
+        data_iters.dedup_by_key(|data_iter| *data_iter as *const DataIterProxy);
+        data_iters.iter().map(|data_iter| data_iter.lock_usage()).collect()
+    }
+
+    /// Check the arrays of a sparse matrix are consistent with each other.
+    pub(crate) fn check_sparse(indptr: &[usize], indices: &[usize], data: &[f32]) -> XGBResult<()> {
+        if indices.len() != data.len() || indptr.last() != Some(&data.len()) {
+            let msg = format!(
+                "Invalid sparse matrix: {} indices and {} values, with {} at the end of indptr",
+                indices.len(),
+                data.len(),
+                indptr.last().map_or("nothing".to_owned(), |last| last.to_string())
+            );
+            return Err(XGBError::new(msg));
+        }
+        Ok(())
+    }
+
+    /// Create a new `DMatrix` from a dense 2-D `ndarray` view, with one row per instance.
+    ///
+    /// Views which aren't in standard (row-major, contiguous) layout are copied before being passed to XGBoost.
+    ///
+    /// ```
+    /// use ndarray::array;
+    /// use xgb::DMatrix;
+    ///
+    /// let data = array![[1.0, 2.0], [3.0, 4.0], [5.0, 6.0]];
+    /// let dmat = DMatrix::from_array(data.view()).unwrap();
+    /// assert_eq!(dmat.shape(), (3, 2));
+    /// ```
+    #[cfg(feature = "ndarray")]
+    pub fn from_array(data: ndarray::ArrayView2<f32>) -> XGBResult<Self> {
+        let (num_rows, num_cols) = data.dim();
+        let data = data.as_standard_layout();
+        let mut handle = ptr::null_mut();
+        xgb_call!(xgboost_sys::XGDMatrixCreateFromMat(
+            data.as_slice().unwrap().as_ptr(),
+            num_rows as xgboost_sys::bst_ulong,
+            num_cols as xgboost_sys::bst_ulong,
+            f32::NAN,
+            &mut handle
+        ))?;
+        DMatrix::new(handle)
+    }
+
+    /// Create a new `DMatrix` from a sparse [`sprs`](https://docs.rs/sprs) matrix view, in either CSR or CSC
+    /// storage, with one row per instance.
+    ///
+    /// ```
+    /// use sprs::CsMat;
+    /// use xgb::DMatrix;
+    ///
+    /// let data = CsMat::new((3, 3), vec![0, 2, 3, 6], vec![0, 2, 2, 0, 1, 2], vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+    /// let dmat = DMatrix::from_sparse(data.view()).unwrap();
+    /// assert_eq!(dmat.shape(), (3, 3));
+    /// ```
+    #[cfg(feature = "ndarray")]
+    pub fn from_sparse(data: sprs::CsMatView<f32>) -> XGBResult<Self> {
+        let indptr = data.indptr();
+        let indptr = indptr.to_proper();
+        if data.is_csr() {
+            DMatrix::from_csr(&indptr, data.indices(), data.data(), Some(data.cols()))
+        } else {
+            DMatrix::from_csc(&indptr, data.indices(), data.data(), Some(data.rows()))
+        }
+    }
+
     /// Create a new `DMatrix` from given file.
     ///
     /// Supports text files in [LIBSVM](https://www.csie.ntu.edu.tw/~cjlin/libsvm/) format, CSV,
@@ -235,16 +647,46 @@ impl DMatrix {
         (self.num_rows(), self.num_cols())
     }
 
+    /// Get the number of entries in this matrix which aren't missing.
+    pub fn num_non_missing(&self) -> XGBResult<usize> {
+        let mut out = 0;
+        xgb_call!(xgboost_sys::XGDMatrixNumNonMissing(self.handle, &mut out))?;
+        Ok(out as usize)
+    }
+
+    /// Get the number of entries in this matrix which are missing, i.e. which were equal to the missing value when
+    /// the matrix was created, or weren't stored in a sparse matrix.
+    ///
+    /// Useful for validating that missing values were encoded as expected.
+    pub fn num_missing(&self) -> XGBResult<usize> {
+        Ok(self.num_rows * self.num_cols - self.num_non_missing()?)
+    }
+
     /// Get a new DMatrix as a containing only given indices.
+    ///
+    /// Fails if this matrix has query groups, as they can't be sliced by row.
     pub fn slice(&self, indices: &[usize]) -> XGBResult<DMatrix> {
+        self.slice_rows(indices, false)
+    }
+
+    /// Get a new DMatrix containing only given indices of a matrix which may have query groups.
+    ///
+    /// The groups of the slice aren't meaningful, so callers must set them with
+    /// [`set_group`](struct.DMatrix.html#method.set_group).
+    pub(crate) fn slice_ungrouped(&self, indices: &[usize]) -> XGBResult<DMatrix> {
+        self.slice_rows(indices, true)
+    }
+
+    fn slice_rows(&self, indices: &[usize], allow_groups: bool) -> XGBResult<DMatrix> {
         debug!("Slicing {} rows from DMatrix", indices.len());
         let mut out_handle = ptr::null_mut();
         let indices: Vec<i32> = indices.iter().map(|x| *x as i32).collect();
-        xgb_call!(xgboost_sys::XGDMatrixSliceDMatrix(
+        xgb_call!(xgboost_sys::XGDMatrixSliceDMatrixEx(
             self.handle,
             indices.as_ptr(),
             indices.len() as xgboost_sys::bst_ulong,
-            &mut out_handle
+            &mut out_handle,
+            allow_groups as i32
         ))?;
         DMatrix::new(out_handle)
     }
@@ -300,6 +742,114 @@ impl DMatrix {
         self.get_uint_info(KEY_GROUP_PTR)
     }
 
+    fn get_str_feature_info(&self, field: &str) -> XGBResult<Vec<String>> {
+        let field = ffi::CString::new(field).unwrap();
+        let mut out_len = 0;
+        let mut out_dptr = ptr::null_mut();
+        xgb_call!(xgboost_sys::XGDMatrixGetStrFeatureInfo(
+            self.handle,
+            field.as_ptr(),
+            &mut out_len,
+            &mut out_dptr
+        ))?;
+
+        if out_len > 0 {
+            let out_ptrs = unsafe { slice::from_raw_parts(out_dptr, out_len as usize) };
+            Ok(out_ptrs
+                .iter()
+                .map(|str_ptr| unsafe { ffi::CStr::from_ptr(*str_ptr).to_string_lossy().into_owned() })
+                .collect())
+        } else {
+            Ok(Vec::new())
+        }
+    }
+
+    fn set_str_feature_info(&mut self, field: &str, values: &[&str]) -> XGBResult<()> {
+        let field = ffi::CString::new(field).unwrap();
+        let values: Vec<ffi::CString> = values
+            .iter()
+            .map(|value| ffi::CString::new(*value).map_err(|err| XGBError::new(err.to_string())))
+            .collect::<XGBResult<_>>()?;
+        let mut value_ptrs: Vec<*const libc::c_char> = values.iter().map(|value| value.as_ptr()).collect();
+        xgb_call!(xgboost_sys::XGDMatrixSetStrFeatureInfo(
+            self.handle,
+            field.as_ptr(),
+            value_ptrs.as_mut_ptr(),
+            value_ptrs.len() as xgboost_sys::bst_ulong
+        ))
+    }
+
+    /// Set the name of each feature (column) of this matrix.
+    ///
+    /// Models trained with [`Booster::train`](struct.Booster.html#method.train) store the feature names of their
+    /// training matrix, and refuse to make predictions for matrices with different or no feature names, which catches
+    /// columns being passed in a different order.
+    ///
+    /// ```
+    /// use xgb::DMatrix;
+    ///
+    /// let mut dmat = DMatrix::from_dense(&[0.5, 2.0, 1.5, 0.0], 2).unwrap();
+    /// dmat.set_feature_names(&["age", "income"]).unwrap();
+    /// assert_eq!(dmat.feature_names().unwrap(), vec!["age", "income"]);
+    /// ```
+    pub fn set_feature_names(&mut self, feature_names: &[&str]) -> XGBResult<()> {
+        if feature_names.len() != self.num_cols {
+            let msg = format!(
+                "Got {} feature names for a matrix with {} columns",
+                feature_names.len(),
+                self.num_cols
+            );
+            return Err(XGBError::new(msg));
+        }
+        self.set_str_feature_info(KEY_FEATURE_NAME, feature_names)
+    }
+
+    /// Get the name of each feature (column) of this matrix, or an empty vector if they haven't been set.
+    pub fn feature_names(&self) -> XGBResult<Vec<String>> {
+        self.get_str_feature_info(KEY_FEATURE_NAME)
+    }
+
+    /// Set the type of each feature (column) of this matrix.
+    ///
+    /// Features marked as [`Categorical`](enum.FeatureType.html#variant.Categorical) are split on natively by tree
+    /// and dart boosters with
+    /// [`enable_categorical`](parameters/tree/struct.TreeBoosterParametersBuilder.html#method.enable_categorical)
+    /// set, instead of being treated as ordered numbers. Their values must be non-negative integers identifying each
+    /// category.
+    ///
+    /// ```
+    /// use xgb::{DMatrix, FeatureType};
+    ///
+    /// let mut dmat = DMatrix::from_dense(&[0.5, 2.0, 1.5, 0.0], 2).unwrap();
+    /// dmat.set_feature_types(&[FeatureType::Quantitative, FeatureType::Categorical]).unwrap();
+    /// ```
+    pub fn set_feature_types(&mut self, feature_types: &[FeatureType]) -> XGBResult<()> {
+        if feature_types.len() != self.num_cols {
+            let msg = format!(
+                "Got {} feature types for a matrix with {} columns",
+                feature_types.len(),
+                self.num_cols
+            );
+            return Err(XGBError::new(msg));
+        }
+        let feature_types: Vec<String> = feature_types.iter().map(|t| t.to_string()).collect();
+        let feature_types: Vec<&str> = feature_types.iter().map(|t| t.as_str()).collect();
+        self.set_str_feature_info(KEY_FEATURE_TYPE, &feature_types)
+    }
+
+    /// Get the type of each feature (column) of this matrix, or an empty vector if they haven't been set.
+    pub fn get_feature_types(&self) -> XGBResult<Vec<FeatureType>> {
+        self.get_str_feature_info(KEY_FEATURE_TYPE)?
+            .iter()
+            .map(|t| FeatureType::from_str(t).map_err(XGBError::new))
+            .collect()
+    }
+
+    /// Check whether any feature of this matrix is marked as categorical.
+    pub(crate) fn has_categorical_features(&self) -> XGBResult<bool> {
+        Ok(self.get_feature_types()?.contains(&FeatureType::Categorical))
+    }
+
     fn get_float_info(&self, field: &str) -> XGBResult<&[f32]> {
         let field = ffi::CString::new(field).unwrap();
         let mut out_len = 0;
@@ -366,6 +916,8 @@ impl Drop for DMatrix {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Batch;
+
     fn read_train_matrix() -> XGBResult<DMatrix> {
         DMatrix::load(r#"{"uri": "xgboost-sys/xgboost/demo/data/agaricus.txt.train?format=libsvm"}"#)
     }
@@ -446,6 +998,36 @@ mod tests {
         assert_eq!(dmat.get_group().unwrap(), &[0, 1]);
     }
 
+    #[test]
+    fn get_set_feature_names() {
+        let mut dmat = DMatrix::from_dense(&[0.5, 2.0, 1.0, 1.5, 0.0, 1.0], 2).unwrap();
+        assert_eq!(dmat.feature_names().unwrap(), Vec::<String>::new());
+
+        dmat.set_feature_names(&["a", "b", "c"]).unwrap();
+        assert_eq!(dmat.feature_names().unwrap(), vec!["a", "b", "c"]);
+        assert_eq!(dmat.slice(&[1]).unwrap().feature_names().unwrap(), vec!["a", "b", "c"]);
+
+        assert!(dmat.set_feature_names(&["a", "b"]).is_err());
+    }
+
+    #[test]
+    fn get_set_feature_types() {
+        let mut dmat = DMatrix::from_dense(&[0.5, 2.0, 1.0, 1.5, 0.0, 1.0], 2).unwrap();
+        assert_eq!(dmat.get_feature_types().unwrap(), Vec::new());
+        assert!(!dmat.has_categorical_features().unwrap());
+
+        let feature_types = [
+            FeatureType::Quantitative,
+            FeatureType::Categorical,
+            FeatureType::Integer,
+        ];
+        dmat.set_feature_types(&feature_types).unwrap();
+        assert_eq!(dmat.get_feature_types().unwrap(), feature_types);
+        assert!(dmat.has_categorical_features().unwrap());
+
+        assert!(dmat.set_feature_types(&feature_types[1..]).is_err());
+    }
+
     #[test]
     fn from_csr() {
         let indptr = [0, 2, 3, 6, 8];
@@ -493,6 +1075,232 @@ mod tests {
         assert_eq!(dmat.num_cols(), 1);
     }
 
+    #[test]
+    fn from_dense_with_params() {
+        let data = vec![1.0, -999.0, 3.0, 0.0, -999.0, f32::NAN];
+        let params = DMatrixParametersBuilder::default().missing(-999.0).build().unwrap();
+        let dmat = DMatrix::from_dense_with_params(&data, 2, &params).unwrap();
+        assert_eq!(dmat.shape(), (2, 3));
+        assert_eq!(dmat.num_non_missing().unwrap(), 3);
+        assert_eq!(dmat.num_missing().unwrap(), 3);
+
+        let params = DMatrixParametersBuilder::default()
+            .missing(0.0)
+            .nthread(Some(1))
+            .build()
+            .unwrap();
+        let dmat = DMatrix::from_dense_with_params(&data, 2, &params).unwrap();
+        assert_eq!(dmat.num_missing().unwrap(), 2);
+
+        assert!(DMatrix::from_dense_with_params(&data, 4, &params).is_err());
+        assert!(DMatrixParametersBuilder::default().nthread(Some(0)).build().is_err());
+    }
+
+    #[test]
+    fn from_sparse_with_params() {
+        let indptr = [0, 2, 3, 6];
+        let indices = [0, 2, 2, 0, 1, 2];
+        let data = [1.0, -1.0, 3.0, 4.0, -1.0, 6.0];
+        let params = DMatrixParametersBuilder::default().missing(-1.0).build().unwrap();
+
+        let dmat = DMatrix::from_csr_with_params(&indptr, &indices, &data, Some(3), &params).unwrap();
+        assert_eq!(dmat.shape(), (3, 3));
+        assert_eq!(dmat.num_non_missing().unwrap(), 4);
+        assert_eq!(dmat.num_missing().unwrap(), 5);
+
+        let dmat = DMatrix::from_csc_with_params(&indptr, &indices, &data, None, &params).unwrap();
+        assert_eq!(dmat.shape(), (3, 3));
+        assert_eq!(dmat.num_non_missing().unwrap(), 4);
+
+        assert!(DMatrix::from_csr_with_params(&indptr, &indices[1..], &data[1..], None, &params).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "ndarray")]
+    fn from_array() {
+        let data = ndarray::array![[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]];
+        let dmat = DMatrix::from_array(data.view()).unwrap();
+        assert_eq!(dmat.shape(), (2, 3));
+
+        // transposed views aren't in standard layout
+        let dmat = DMatrix::from_array(data.t()).unwrap();
+        assert_eq!(dmat.shape(), (3, 2));
+    }
+
+    #[test]
+    #[cfg(feature = "ndarray")]
+    fn from_sparse() {
+        let indptr = vec![0, 2, 3, 6];
+        let indices = vec![0, 2, 2, 0, 1, 2];
+        let data = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let csr = sprs::CsMat::new((3, 4), indptr, indices, data);
+        let dmat = DMatrix::from_sparse(csr.view()).unwrap();
+        assert_eq!(dmat.shape(), (3, 4));
+
+        let csc = csr.to_csc();
+        let dmat = DMatrix::from_sparse(csc.view()).unwrap();
+        assert_eq!(dmat.shape(), (3, 4));
+
+        // views of a subset of rows have an offset indptr
+        let dmat = DMatrix::from_sparse(csr.slice_outer(1..3)).unwrap();
+        assert_eq!(dmat.shape(), (2, 4));
+    }
+
+    #[test]
+    #[cfg(feature = "arrow")]
+    fn from_record_batch() {
+        use arrow_array::{ArrayRef, Float32Array, Int64Array, RecordBatch};
+        use std::sync::Arc;
+
+        let x: ArrayRef = Arc::new(Float32Array::from(vec![Some(1.0), None, Some(-1.0)]));
+        let y: ArrayRef = Arc::new(Int64Array::from(vec![Some(4), Some(5), None]));
+        let batch = RecordBatch::try_from_iter(vec![("x", x), ("y", y)]).unwrap();
+
+        let params = DMatrixParametersBuilder::default().missing(-1.0).build().unwrap();
+        let dmat = DMatrix::from_record_batch(&batch, &params).unwrap();
+        assert_eq!(dmat.shape(), (3, 2));
+        assert_eq!(dmat.num_missing().unwrap(), 3);
+
+        let short = Float32Array::from(vec![1.0]);
+        let columns: &[&dyn arrow_array::Array] = &[batch.column(0).as_ref(), &short];
+        assert!(DMatrix::from_arrow_columns(columns, &params).is_err());
+    }
+
+    /// Yields rows of a dense matrix in batches of given size, either as dense or CSR batches.
+    struct Chunks {
+        data: Vec<f32>,
+        labels: Vec<f32>,
+        num_cols: usize,
+        batch_size: usize,
+        csr: bool,
+        row: usize,
+    }
+
+    impl Chunks {
+        fn new(num_rows: usize, num_cols: usize, batch_size: usize, csr: bool) -> Self {
+            Chunks {
+                data: (0..num_rows * num_cols).map(|i| (i % 7) as f32).collect(),
+                labels: (0..num_rows).map(|i| (i % 2) as f32).collect(),
+                num_cols,
+                batch_size,
+                csr,
+                row: 0,
+            }
+        }
+    }
+
+    impl DataIter for Chunks {
+        fn next(&mut self) -> Result<Option<Batch>, Box<dyn std::error::Error>> {
+            if self.row >= self.labels.len() {
+                return Ok(None);
+            }
+            let end = (self.row + self.batch_size).min(self.labels.len());
+            let data = self.data[self.row * self.num_cols..end * self.num_cols].to_vec();
+            let batch = if self.csr {
+                let indptr = (0..=end - self.row).map(|row| row * self.num_cols).collect();
+                let indices = (0..data.len()).map(|i| i % self.num_cols).collect();
+                Batch::from_csr(indptr, indices, data, self.num_cols)
+            } else {
+                Batch::from_dense(data, end - self.row)
+            };
+            let labels = self.labels[self.row..end].to_vec();
+            self.row = end;
+            Ok(Some(batch.with_labels(labels)))
+        }
+
+        fn reset(&mut self) {
+            self.row = 0;
+        }
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn from_data_iter_quantile() {
+        let params = DMatrixParameters::default();
+        let dmat = DMatrix::from_data_iter_quantile(Chunks::new(10, 3, 4, false), 16, &params).unwrap();
+        assert_eq!(dmat.shape(), (10, 3));
+        assert_eq!(dmat.get_labels().unwrap().len(), 10);
+    }
+
+    #[test]
+    fn from_data_iter() {
+        let tmp_dir = tempfile::tempdir().expect("failed to create temp dir");
+        let cache_prefix = tmp_dir.path().join("cache");
+        let params = DMatrixParameters::default();
+
+        for csr in [false, true] {
+            let dmat =
+                DMatrix::from_data_iter(Chunks::new(10, 3, 4, csr), cache_prefix.to_str().unwrap(), &params).unwrap();
+            assert_eq!(dmat.shape(), (10, 3));
+            assert_eq!(dmat.get_labels().unwrap(), Chunks::new(10, 3, 4, csr).labels.as_slice());
+        }
+
+        struct Failing;
+        impl DataIter for Failing {
+            fn next(&mut self) -> Result<Option<Batch>, Box<dyn std::error::Error>> {
+                Err("disk on fire".into())
+            }
+
+            fn reset(&mut self) {}
+        }
+        let err = DMatrix::from_data_iter(Failing, cache_prefix.to_str().unwrap(), &params).unwrap_err();
+        assert!(err.to_string().contains("disk on fire"), "{}", err);
+    }
+
+    #[test]
+    fn from_data_iter_shared_between_threads() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+
+        // fails the iteration if it's ever entered while another thread is still in it
+        struct Exclusive {
+            chunks: Chunks,
+            busy: Arc<AtomicBool>,
+        }
+        impl DataIter for Exclusive {
+            fn next(&mut self) -> Result<Option<Batch>, Box<dyn std::error::Error>> {
+                assert!(!self.busy.swap(true, Ordering::SeqCst), "iterator called concurrently");
+                std::thread::yield_now();
+                let batch = self.chunks.next();
+                self.busy.store(false, Ordering::SeqCst);
+                batch
+            }
+
+            fn reset(&mut self) {
+                self.chunks.reset();
+            }
+        }
+
+        let tmp_dir = tempfile::tempdir().expect("failed to create temp dir");
+        let cache_prefix = tmp_dir.path().join("cache");
+        let iter = Exclusive {
+            chunks: Chunks::new(40, 3, 8, false),
+            busy: Arc::new(AtomicBool::new(false)),
+        };
+        let dmat =
+            DMatrix::from_data_iter(iter, cache_prefix.to_str().unwrap(), &DMatrixParameters::default()).unwrap();
+
+        let params = crate::parameters::BoosterParametersBuilder::default()
+            .verbose(false)
+            .build()
+            .unwrap();
+        let mut booster = crate::Booster::new_with_cached_dmats(&params, &[&dmat]).unwrap();
+        for i in 0..3 {
+            booster.update(&dmat, i).unwrap();
+        }
+        let expected = booster.predict(&dmat).unwrap();
+
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..5 {
+                        assert_eq!(booster.predict(&dmat).unwrap(), expected);
+                    }
+                });
+            }
+        });
+    }
+
     #[test]
     fn slice_from_indices() {
         let data = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
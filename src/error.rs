@@ -1,6 +1,7 @@
 //! Functionality related to errors and error handling.
 
 use std::error::Error;
+#[cfg(feature = "libxgboost")]
 use std::ffi::CStr;
 use std::fmt::{self, Display};
 
@@ -24,6 +25,7 @@ impl XGBError {
     /// Return values of 0 are treated as success, returns values of -1 are treated as errors.
     ///
     /// Meaning of any other return values are undefined, and will cause a panic.
+    #[cfg(feature = "libxgboost")]
     pub(crate) fn check_return_value(ret_val: i32) -> XGBResult<()> {
         match ret_val {
             0 => Ok(()),
@@ -33,6 +35,7 @@ impl XGBError {
     }
 
     /// Get the last error message from XGBoost.
+    #[cfg(feature = "libxgboost")]
     fn from_xgboost() -> Self {
         let c_str = unsafe { CStr::from_ptr(xgboost_sys::XGBGetLastError()) };
         let str_slice = c_str.to_str().unwrap();
@@ -50,7 +53,7 @@ impl Display for XGBError {
     }
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "libxgboost"))]
 mod tests {
     use super::*;
 
@@ -0,0 +1,449 @@
+//! Pure Rust prediction for tree models, without libxgboost.
+//!
+//! Loads gbtree and dart models saved by [`Booster::save`](../struct.Booster.html#method.save) or
+//! [`Booster::save_buffer`](../struct.Booster.html#method.save_buffer), as JSON or UBJ, and computes the same
+//! predictions as [`Booster::predict`](../struct.Booster.html#method.predict) for these objectives:
+//!
+//! * `reg:squarederror`, `reg:squaredlogerror`, `reg:pseudohubererror`, `reg:absoluteerror`, `reg:quantileerror`
+//! * `reg:logistic`, `binary:logistic`, `binary:logitraw`, `binary:hinge`
+//! * `count:poisson`, `reg:gamma`, `reg:tweedie`, `survival:cox`, `survival:aft`
+//! * `multi:softmax`, `multi:softprob`
+//! * `rank:pairwise`, `rank:ndcg`, `rank:map`
+//!
+//! To deploy models without building or shipping libxgboost at all, disable the default features:
+//!
+//! ```toml
+//! xgb = { version = "3", default-features = false, features = ["inference"] }
+//! ```
+//!
+//! # Example
+//!
+//! ```no_run
+//! use xgb::inference::Ensemble;
+//!
+//! let model = Ensemble::load("xgb.ubj").unwrap();
+//!
+//! // 2 rows with 3 features each, using NaN for missing values
+//! let data = &[1.0, f32::NAN, 0.5, 0.0, 1.0, 2.0];
+//! println!("{:?}", model.predict(data, 2).unwrap());
+//! ```
+use std::fs;
+use std::path::Path;
+
+use super::model::TreeModel;
+use super::{XGBError, XGBResult};
+
+mod ubj;
+
+/// A tree ensemble loaded from a saved model, for predicting without libxgboost.
+///
+/// See the [module documentation](index.html) for the supported models.
+#[derive(Clone, Debug)]
+pub struct Ensemble {
+    model: TreeModel,
+    objective: Objective,
+    base_margin: Vec<f32>,
+}
+
+impl Ensemble {
+    /// Load a model from a file, saved as JSON or UBJ.
+    pub fn load<P: AsRef<Path>>(path: P) -> XGBResult<Self> {
+        let bytes = fs::read(path.as_ref())
+            .map_err(|err| XGBError::new(format!("Failed to read {}: {}", path.as_ref().display(), err)))?;
+        Ensemble::load_buffer(&bytes)
+    }
+
+    /// Load a model from a buffer, saved as JSON or UBJ.
+    pub fn load_buffer(bytes: &[u8]) -> XGBResult<Self> {
+        // both formats start with '{', but JSON then has whitespace or a quoted key while UBJ has a type marker
+        let json = bytes
+            .iter()
+            .skip_while(|b| b.is_ascii_whitespace())
+            .skip(1)
+            .find(|b| !b.is_ascii_whitespace())
+            .is_some_and(|&b| b == b'"' || b == b'}');
+        if json {
+            Ensemble::from_json(bytes)
+        } else {
+            Ensemble::from_ubj(bytes)
+        }
+    }
+
+    /// Load a model saved as JSON, e.g. by [`Booster::save_buffer(false)`](../struct.Booster.html#method.save_buffer).
+    pub fn from_json(json: &[u8]) -> XGBResult<Self> {
+        Ensemble::from_model(TreeModel::from_json(json)?)
+    }
+
+    /// Load a model saved as UBJ, e.g. by [`Booster::save_buffer(true)`](../struct.Booster.html#method.save_buffer).
+    pub fn from_ubj(ubj: &[u8]) -> XGBResult<Self> {
+        Ensemble::from_model(TreeModel::from_value(&ubj::to_json(ubj)?)?)
+    }
+
+    /// Create an ensemble from a parsed model, failing if its objective isn't supported.
+    pub fn from_model(model: TreeModel) -> XGBResult<Self> {
+        let objective = Objective::from_name(model.objective()).ok_or_else(|| {
+            XGBError::new(format!(
+                "Objective {} is not supported for inference",
+                model.objective()
+            ))
+        })?;
+        let base_margin = model
+            .base_score()
+            .iter()
+            .map(|&score| objective.base_margin(score))
+            .collect();
+        Ok(Ensemble {
+            model,
+            objective,
+            base_margin,
+        })
+    }
+
+    /// Get the underlying tree model.
+    pub fn model(&self) -> &TreeModel {
+        &self.model
+    }
+
+    /// Get how the objective relates margins to predictions.
+    pub(crate) fn objective(&self) -> Objective {
+        self.objective
+    }
+
+    /// Get the margin of each output before adding any trees.
+    pub(crate) fn base_margin(&self) -> &[f32] {
+        &self.base_margin
+    }
+
+    /// Get the number of features the model was trained on.
+    pub fn num_features(&self) -> usize {
+        self.model.num_features()
+    }
+
+    /// Get the number of values predicted for each row by
+    /// [`predict_margin`](struct.Ensemble.html#method.predict_margin), e.g. the number of classes for multiclass
+    /// models.
+    pub fn num_outputs(&self) -> usize {
+        self.model.num_outputs()
+    }
+
+    /// Predict the untransformed margins of a single row, with one value per output.
+    ///
+    /// `NaN` marks missing values, and features past the end of `features` are also treated as missing.
+    pub fn predict_row_margin(&self, features: &[f32]) -> Vec<f32> {
+        let mut margin = self.base_margin.clone();
+        let trees = self.model.trees().iter();
+        for ((tree, &output), &weight) in trees.zip(self.model.tree_outputs()).zip(self.model.tree_weights()) {
+            let leaf = tree.leaf(features).leaf_value().unwrap();
+            margin[output] += weight * leaf;
+        }
+        margin
+    }
+
+    /// Predict a single row, with the objective's transformation applied (e.g. probabilities for
+    /// `binary:logistic`).
+    ///
+    /// Returns one value per output, except for `multi:softmax` which returns the predicted class.
+    pub fn predict_row(&self, features: &[f32]) -> Vec<f32> {
+        let mut margin = self.predict_row_margin(features);
+        self.objective.transform(&mut margin)
+    }
+
+    /// Predict the untransformed margins of rows of dense data in row-major order, like
+    /// [`Booster::predict_margin`](../struct.Booster.html#method.predict_margin).
+    ///
+    /// `NaN` marks missing values. Returns `num_rows * num_outputs()` values in row-major order.
+    pub fn predict_margin(&self, data: &[f32], num_rows: usize) -> XGBResult<Vec<f32>> {
+        Ok(rows(data, num_rows)?
+            .flat_map(|row| self.predict_row_margin(row))
+            .collect())
+    }
+
+    /// Predict rows of dense data in row-major order, like
+    /// [`Booster::predict`](../struct.Booster.html#method.predict).
+    ///
+    /// `NaN` marks missing values. Returns `num_rows * num_outputs()` values in row-major order, or `num_rows` values
+    /// for `multi:softmax`.
+    pub fn predict(&self, data: &[f32], num_rows: usize) -> XGBResult<Vec<f32>> {
+        Ok(rows(data, num_rows)?.flat_map(|row| self.predict_row(row)).collect())
+    }
+}
+
+/// Split dense row-major data into rows.
+fn rows(data: &[f32], num_rows: usize) -> XGBResult<impl Iterator<Item = &[f32]>> {
+    if !data.len().is_multiple_of(num_rows) {
+        return Err(XGBError::new(format!(
+            "Data of length {} can't be split into {} rows",
+            data.len(),
+            num_rows
+        )));
+    }
+    let num_cols = data.len().checked_div(num_rows).unwrap_or(0);
+    Ok((0..num_rows).map(move |row| &data[row * num_cols..(row + 1) * num_cols]))
+}
+
+/// How an objective relates margins to predictions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Objective {
+    /// Predictions are the margins.
+    Identity,
+
+    /// Predictions are probabilities from the sigmoid of the margin.
+    Logistic,
+
+    /// Predictions are 1 for positive margins, otherwise 0.
+    Hinge,
+
+    /// Predictions are the exponential of the margin.
+    Exp,
+
+    /// Predictions are class probabilities from the softmax of the margins.
+    SoftProb,
+
+    /// Predictions are the class with the largest margin.
+    SoftMax,
+}
+
+impl Objective {
+    fn from_name(name: &str) -> Option<Self> {
+        let objective = match name {
+            "reg:squarederror"
+            | "reg:linear"
+            | "reg:squaredlogerror"
+            | "reg:pseudohubererror"
+            | "reg:absoluteerror"
+            | "reg:quantileerror"
+            | "binary:logitraw"
+            | "rank:pairwise"
+            | "rank:ndcg"
+            | "rank:map" => Objective::Identity,
+            "reg:logistic" | "binary:logistic" => Objective::Logistic,
+            "binary:hinge" => Objective::Hinge,
+            "count:poisson" | "reg:gamma" | "reg:tweedie" | "survival:cox" | "survival:aft" => Objective::Exp,
+            "multi:softprob" => Objective::SoftProb,
+            "multi:softmax" => Objective::SoftMax,
+            _ => return None,
+        };
+        Some(objective)
+    }
+
+    /// Convert a base score (saved in the same space as predictions) to a margin.
+    fn base_margin(self, base_score: f32) -> f32 {
+        match self {
+            Objective::Logistic => -(1.0 / base_score - 1.0).ln(),
+            Objective::Exp => base_score.ln(),
+            _ => base_score,
+        }
+    }
+
+    /// Transform the margins of a row into predictions.
+    fn transform(self, margin: &mut [f32]) -> Vec<f32> {
+        match self {
+            Objective::Identity => margin.to_vec(),
+            Objective::Logistic => margin.iter().map(|&x| sigmoid(x)).collect(),
+            Objective::Hinge => margin.iter().map(|&x| if x > 0.0 { 1.0 } else { 0.0 }).collect(),
+            Objective::Exp => margin.iter().map(|&x| x.exp()).collect(),
+            Objective::SoftProb => {
+                softmax(margin);
+                margin.to_vec()
+            }
+            Objective::SoftMax => {
+                // ties go to the first class, as in XGBoost
+                let class =
+                    margin.iter().enumerate().fold(
+                        (0, f32::NEG_INFINITY),
+                        |best, (i, &x)| if x > best.1 { (i, x) } else { best },
+                    );
+                vec![class.0 as f32]
+            }
+        }
+    }
+}
+
+/// Sigmoid computed the same way as XGBoost, clamping large negative margins to avoid overflow.
+fn sigmoid(x: f32) -> f32 {
+    1.0 / ((-x).min(88.7).exp() + 1.0)
+}
+
+/// In-place softmax, computed the same way as XGBoost: exponentials are summed in double precision, and divided by
+/// the sum rounded to single precision.
+fn softmax(values: &mut [f32]) {
+    let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let mut sum = 0.0f64;
+    for value in values.iter_mut() {
+        *value = (*value - max).exp();
+        sum += *value as f64;
+    }
+    for value in values.iter_mut() {
+        *value /= sum as f32;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transforms() {
+        assert_eq!(Objective::from_name("binary:logistic"), Some(Objective::Logistic));
+        assert_eq!(Objective::from_name("reg:linear"), Some(Objective::Identity));
+        assert_eq!(Objective::from_name("multi:softmax"), Some(Objective::SoftMax));
+        assert_eq!(Objective::from_name("rank:unknown"), None);
+
+        assert_eq!(Objective::Logistic.base_margin(0.5), 0.0);
+        assert_eq!(Objective::Exp.base_margin(1.0), 0.0);
+        assert_eq!(Objective::SoftProb.base_margin(0.5), 0.5);
+
+        let probabilities = Objective::Logistic.transform(&mut [0.0, -1000.0]);
+        assert_eq!(probabilities[0], 0.5);
+        // large negative margins are clamped rather than overflowing
+        assert!(probabilities[1] > 0.0 && probabilities[1] < 1e-38);
+        assert_eq!(Objective::Hinge.transform(&mut [0.5, 0.0, -0.5]), vec![1.0, 0.0, 0.0]);
+        assert_eq!(Objective::Exp.transform(&mut [0.0]), vec![1.0]);
+        assert_eq!(Objective::SoftMax.transform(&mut [0.5, 2.0, 2.0, -1.0]), vec![1.0]);
+        let probabilities = Objective::SoftProb.transform(&mut [1.0, 1.0, 1.0, 1.0]);
+        assert_eq!(probabilities, vec![0.25; 4]);
+    }
+
+    #[test]
+    fn dense_rows() {
+        assert_eq!(
+            rows(&[1.0, 2.0, 3.0, 4.0], 2).unwrap().collect::<Vec<_>>(),
+            vec![&[1.0, 2.0], &[3.0, 4.0]]
+        );
+        assert_eq!(rows(&[], 0).unwrap().count(), 0);
+        assert_eq!(rows(&[], 2).unwrap().collect::<Vec<_>>(), vec![&[] as &[f32]; 2]);
+        assert!(rows(&[1.0, 2.0, 3.0], 2).is_err());
+        assert!(rows(&[1.0], 0).is_err());
+    }
+
+    #[cfg(feature = "libxgboost")]
+    mod parity {
+        use super::super::*;
+        use crate::parameters::{self, dart, learning, tree, BoosterType};
+        use crate::{Booster, DMatrix};
+
+        const TRAIN_PATH: &str = "xgboost-sys/xgboost/demo/data/agaricus.txt.train";
+        const TEST_PATH: &str = "xgboost-sys/xgboost/demo/data/agaricus.txt.test";
+
+        /// Read a libsvm file as dense rows, with NaN for missing values.
+        fn read_dense(path: &str, num_features: usize) -> (Vec<f32>, usize) {
+            let mut data = Vec::new();
+            let mut num_rows = 0;
+            for line in fs::read_to_string(path).unwrap().lines() {
+                let mut row = vec![f32::NAN; num_features];
+                for entry in line.split_whitespace().skip(1) {
+                    let (index, value) = entry.split_once(':').unwrap();
+                    row[index.parse::<usize>().unwrap()] = value.parse().unwrap();
+                }
+                data.extend(row);
+                num_rows += 1;
+            }
+            (data, num_rows)
+        }
+
+        fn train(booster_type: BoosterType, objective: learning::Objective) -> Booster {
+            let dtrain = DMatrix::load(format!(r#"{{"uri": "{}?format=libsvm"}}"#, TRAIN_PATH)).unwrap();
+            let learning_params = learning::LearningTaskParametersBuilder::default()
+                .objective(objective)
+                .build()
+                .unwrap();
+            let booster_params = parameters::BoosterParametersBuilder::default()
+                .booster_type(booster_type)
+                .learning_params(learning_params)
+                .verbose(false)
+                .build()
+                .unwrap();
+            let training_params = parameters::TrainingParametersBuilder::default()
+                .dtrain(&dtrain)
+                .booster_params(booster_params)
+                .boost_rounds(5)
+                .build()
+                .unwrap();
+            Booster::train(&training_params).unwrap()
+        }
+
+        fn assert_close(actual: &[f32], expected: &[f32]) {
+            assert_eq!(actual.len(), expected.len());
+            for (i, (actual, expected)) in actual.iter().zip(expected).enumerate() {
+                assert!(
+                    (actual - expected).abs() <= 1e-5 * expected.abs().max(1.0),
+                    "prediction {}: {} != {}",
+                    i,
+                    actual,
+                    expected
+                );
+            }
+        }
+
+        fn assert_parity(booster: &Booster) {
+            let dtest = DMatrix::load(format!(r#"{{"uri": "{}?format=libsvm"}}"#, TEST_PATH)).unwrap();
+            let num_features = booster.num_features().unwrap();
+            let (data, num_rows) = read_dense(TEST_PATH, num_features);
+            let expected = booster.predict(&dtest).unwrap();
+            let expected_margin = booster.predict_margin(&dtest).unwrap();
+
+            for binary in [false, true] {
+                let ensemble = Ensemble::load_buffer(&booster.save_buffer(binary).unwrap()).unwrap();
+                assert_eq!(ensemble.num_features(), num_features);
+                assert_eq!(ensemble.num_outputs(), booster.num_outputs().unwrap());
+                assert_close(&ensemble.predict(&data, num_rows).unwrap(), &expected);
+                assert_close(&ensemble.predict_margin(&data, num_rows).unwrap(), &expected_margin);
+            }
+        }
+
+        #[test]
+        fn tree_objectives() {
+            for objective in [
+                learning::Objective::BinaryLogistic,
+                learning::Objective::BinaryLogisticRaw,
+                learning::Objective::RegLinear,
+                learning::Objective::CountPoisson,
+                learning::Objective::MultiSoftprob(2),
+                learning::Objective::MultiSoftmax(2),
+                learning::Objective::MultiSoftprob(3),
+                learning::Objective::MultiSoftmax(3),
+            ] {
+                let booster = train(BoosterType::Tree(Default::default()), objective);
+                assert_parity(&booster);
+            }
+        }
+
+        #[test]
+        fn dart() {
+            let dart_params = dart::DartBoosterParametersBuilder::default()
+                .rate_drop(0.5)
+                .build()
+                .unwrap();
+            let booster = train(BoosterType::Dart(dart_params), learning::Objective::BinaryLogistic);
+            assert_parity(&booster);
+        }
+
+        #[test]
+        fn saved_file() {
+            let tree_params = tree::TreeBoosterParametersBuilder::default()
+                .num_parallel_tree(2)
+                .build()
+                .unwrap();
+            let booster = train(BoosterType::Tree(tree_params), learning::Objective::BinaryLogistic);
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("model.ubj");
+            booster.save(&path).unwrap();
+            let ensemble = Ensemble::load(&path).unwrap();
+            assert_eq!(ensemble.model().trees().len(), 10);
+            assert_eq!(ensemble.model().num_boosted_rounds(), 5);
+
+            let dtest = DMatrix::load(format!(r#"{{"uri": "{}?format=libsvm"}}"#, TEST_PATH)).unwrap();
+            let (data, num_rows) = read_dense(TEST_PATH, ensemble.num_features());
+            let expected = booster.predict(&dtest).unwrap();
+            assert_close(&ensemble.predict(&data, num_rows).unwrap(), &expected);
+            let row = &data[..ensemble.num_features()];
+            assert_close(&ensemble.predict_row(row), &expected[..1]);
+
+            let linear = train(
+                BoosterType::Linear(Default::default()),
+                learning::Objective::BinaryLogistic,
+            );
+            assert!(Ensemble::load_buffer(&linear.save_buffer(true).unwrap()).is_err());
+        }
+    }
+}
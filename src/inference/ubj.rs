@@ -0,0 +1,231 @@
+//! Decoding of [Universal Binary JSON](https://ubjson.org), the format XGBoost saves models in unless they're saved
+//! with a `.json` extension.
+use serde_json::{Map, Number, Value};
+
+use crate::{XGBError, XGBResult};
+
+/// Maximum nesting of arrays and objects, same as the recursion limit of `serde_json`.
+const MAX_DEPTH: usize = 128;
+
+/// Decode a UBJ document into the equivalent JSON value.
+pub(crate) fn to_json(bytes: &[u8]) -> XGBResult<Value> {
+    let mut reader = Reader {
+        bytes,
+        pos: 0,
+        depth: 0,
+    };
+    reader.value()
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn error(&self, msg: &str) -> XGBError {
+        XGBError::new(format!("Invalid UBJ at byte {}: {}", self.pos, msg))
+    }
+
+    fn take(&mut self, len: usize) -> XGBResult<&'a [u8]> {
+        let bytes = self
+            .bytes
+            .get(self.pos..self.pos.saturating_add(len))
+            .ok_or_else(|| self.error("unexpected end of data"))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> XGBResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Peek at the next type marker, skipping no-ops.
+    fn peek_marker(&mut self) -> Option<u8> {
+        while self.bytes.get(self.pos) == Some(&b'N') {
+            self.pos += 1;
+        }
+        self.bytes.get(self.pos).copied()
+    }
+
+    fn value(&mut self) -> XGBResult<Value> {
+        self.peek_marker();
+        let marker = self.byte()?;
+        self.typed_value(marker)
+    }
+
+    fn typed_value(&mut self, marker: u8) -> XGBResult<Value> {
+        match marker {
+            b'Z' => Ok(Value::Null),
+            b'T' => Ok(Value::Bool(true)),
+            b'F' => Ok(Value::Bool(false)),
+            b'i' | b'U' | b'I' | b'l' | b'L' => Ok(Value::from(self.integer(marker)?)),
+            b'd' => {
+                let value = f32::from_be_bytes(self.take(4)?.try_into().unwrap());
+                Ok(float(value as f64))
+            }
+            b'D' => {
+                let value = f64::from_be_bytes(self.take(8)?.try_into().unwrap());
+                Ok(float(value))
+            }
+            b'C' => Ok(Value::String((self.byte()? as char).to_string())),
+            b'S' => Ok(Value::String(self.string()?)),
+            b'H' => {
+                let number = self.string()?;
+                serde_json::from_str(&number).map_err(|_| self.error("invalid high precision number"))
+            }
+            b'[' | b'{' => {
+                if self.depth == MAX_DEPTH {
+                    return Err(self.error("arrays and objects nested too deeply"));
+                }
+                self.depth += 1;
+                let value = if marker == b'[' { self.array() } else { self.object() };
+                self.depth -= 1;
+                value
+            }
+            _ => Err(self.error(&format!("unknown type marker {:?}", marker as char))),
+        }
+    }
+
+    fn integer(&mut self, marker: u8) -> XGBResult<i64> {
+        match marker {
+            b'i' => Ok(self.byte()? as i8 as i64),
+            b'U' => Ok(self.byte()? as i64),
+            b'I' => Ok(i16::from_be_bytes(self.take(2)?.try_into().unwrap()) as i64),
+            b'l' => Ok(i32::from_be_bytes(self.take(4)?.try_into().unwrap()) as i64),
+            b'L' => Ok(i64::from_be_bytes(self.take(8)?.try_into().unwrap())),
+            _ => Err(self.error(&format!("expected an integer, got type marker {:?}", marker as char))),
+        }
+    }
+
+    fn length(&mut self) -> XGBResult<usize> {
+        let marker = self.byte()?;
+        let len = self.integer(marker)?;
+        usize::try_from(len).map_err(|_| self.error("negative length"))
+    }
+
+    fn string(&mut self) -> XGBResult<String> {
+        let len = self.length()?;
+        let bytes = self.take(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| self.error("invalid UTF-8 string"))
+    }
+
+    /// Read the optional element type and count of an array or object.
+    fn container_header(&mut self) -> XGBResult<(Option<u8>, Option<usize>)> {
+        let mut element_type = None;
+        if self.bytes.get(self.pos) == Some(&b'$') {
+            self.pos += 1;
+            element_type = Some(self.byte()?);
+        }
+        let mut count = None;
+        if self.bytes.get(self.pos) == Some(&b'#') {
+            self.pos += 1;
+            let len = self.length()?;
+            // every element takes at least a byte, except for typed null and boolean values that don't need more than
+            // their (shared) type marker, so bound their count too as they'd otherwise be pushed without reading data
+            if len > self.bytes.len() - self.pos {
+                return Err(self.error("count exceeds the remaining data"));
+            }
+            count = Some(len);
+        } else if element_type.is_some() {
+            return Err(self.error("typed container without a count"));
+        }
+        Ok((element_type, count))
+    }
+
+    fn element(&mut self, element_type: Option<u8>) -> XGBResult<Value> {
+        match element_type {
+            Some(marker) => self.typed_value(marker),
+            None => self.value(),
+        }
+    }
+
+    fn array(&mut self) -> XGBResult<Value> {
+        let (element_type, count) = self.container_header()?;
+        let mut values = Vec::new();
+        match count {
+            Some(count) => {
+                values.reserve(count);
+                for _ in 0..count {
+                    values.push(self.element(element_type)?);
+                }
+            }
+            None => {
+                while self.peek_marker() != Some(b']') {
+                    values.push(self.value()?);
+                }
+                self.pos += 1;
+            }
+        }
+        Ok(Value::Array(values))
+    }
+
+    fn object(&mut self) -> XGBResult<Value> {
+        let (element_type, count) = self.container_header()?;
+        let mut map = Map::new();
+        match count {
+            Some(count) => {
+                for _ in 0..count {
+                    let key = self.string()?;
+                    map.insert(key, self.element(element_type)?);
+                }
+            }
+            None => {
+                while self.peek_marker() != Some(b'}') {
+                    let key = self.string()?;
+                    map.insert(key, self.value()?);
+                }
+                self.pos += 1;
+            }
+        }
+        Ok(Value::Object(map))
+    }
+}
+
+/// Convert a float to JSON, where non-finite values (which JSON can't represent) become `null`.
+fn float(value: f64) -> Value {
+    Number::from_f64(value).map(Value::Number).unwrap_or(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode() {
+        let mut bytes = b"{L\x00\x00\x00\x00\x00\x00\x00\x04nameSi\x06gbtree".to_vec();
+        // typed and counted array of floats
+        bytes.extend(b"i\x06valuesN[$d#i\x02");
+        bytes.extend(1.5f32.to_be_bytes());
+        bytes.extend((-2.0f32).to_be_bytes());
+        // unsized array of mixed values
+        bytes.extend(b"i\x05mixed[TZl\xff\xff\xff\xfeU\xc8I\x01\x00[]]");
+        bytes.extend(b"}");
+        assert_eq!(
+            to_json(&bytes).unwrap(),
+            serde_json::json!({"name": "gbtree", "values": [1.5, -2.0], "mixed": [true, null, -2, 200, 256, []]})
+        );
+
+        assert!(to_json(b"{i\x04name").is_err());
+        assert!(to_json(b"[$d").is_err());
+        assert!(to_json(b"X").is_err());
+    }
+
+    #[test]
+    fn limits() {
+        // counts of elements without data are bounded by the remaining bytes
+        assert!(to_json(b"[$Z#L\x7f\xff\xff\xff\xff\xff\xff\xff").is_err());
+        assert!(to_json(b"[$T#i\x02").is_err());
+        assert_eq!(
+            to_json(b"[[$F#i\x02i\x07]").unwrap(),
+            serde_json::json!([[false, false], 7])
+        );
+        assert!(to_json(b"{$Z#L\x7f\xff\xff\xff\xff\xff\xff\xff").is_err());
+
+        let nested = |depth| [vec![b'['; depth], vec![b']'; depth]].concat();
+        assert!(to_json(&nested(MAX_DEPTH)).is_ok());
+        assert!(to_json(&nested(MAX_DEPTH + 1)).is_err());
+        assert!(to_json(&vec![b'['; 1_000_000]).is_err());
+    }
+}
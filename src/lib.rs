@@ -8,7 +8,8 @@
 //!
 //! # Basic usage example
 //!
-//! ```
+#![cfg_attr(feature = "libxgboost", doc = "```")]
+#![cfg_attr(not(feature = "libxgboost"), doc = "```ignore")]
 //! extern crate xgb;
 //!
 //! use xgb::{parameters, DMatrix, Booster};
@@ -56,38 +57,136 @@
 //! See the [examples](https://github.com/davechallis/rust-xgboost/tree/master/examples) directory for
 //! more detailed examples of different features.
 //!
+//! # Optional features
+//!
+//! * `ndarray` - create [`DMatrix`](struct.DMatrix.html) instances from [`ndarray`](https://docs.rs/ndarray)
+//!   arrays and [`sprs`](https://docs.rs/sprs) sparse matrices, and get predictions as `ndarray` arrays (e.g.
+//!   [`Booster::predict_array`](struct.Booster.html#method.predict_array))
+//! * `arrow` - create [`DMatrix`](struct.DMatrix.html) instances from [Arrow](https://docs.rs/arrow) record batches
+//!   and arrays without copying them into a row-major buffer first (e.g.
+//!   [`DMatrix::from_record_batch`](struct.DMatrix.html#method.from_record_batch))
+//! * `inference` - predict with saved tree models in pure Rust (see [`inference`](inference/index.html)), which
+//!   together with disabling the default features avoids building and linking libxgboost
+//! * `codegen` - compile saved tree models to standalone Rust source code (see [`codegen`](codegen/index.html)),
+//!   e.g. from a build script
+//! * `onnx` - export tree models to ONNX tree ensemble operators (see [`onnx`](onnx/index.html)), e.g. for serving
+//!   with ONNX Runtime
+//! * `libxgboost` - bindings to libxgboost, needed for everything except [`model`](model/index.html) and
+//!   `inference`. Enabled by the default `use_prebuilt_xgb` feature, as well as by `local_build` and `cuda`
+//!
+#[cfg(feature = "arrow")]
+extern crate arrow_array;
+#[cfg(feature = "arrow")]
+extern crate arrow_buffer;
+#[cfg(feature = "arrow")]
+extern crate arrow_schema;
+#[cfg(feature = "libxgboost")]
 #[macro_use]
 extern crate derive_builder;
+#[cfg(feature = "libxgboost")]
 #[macro_use]
 extern crate log;
+#[cfg(feature = "libxgboost")]
 extern crate indexmap;
+#[cfg(feature = "libxgboost")]
 extern crate libc;
+#[cfg(feature = "ndarray")]
+extern crate ndarray;
+#[cfg(feature = "ndarray")]
+extern crate sprs;
+#[cfg(feature = "libxgboost")]
 extern crate tempfile;
+#[cfg(feature = "libxgboost")]
 extern crate xgboost_sys;
 
+#[cfg(feature = "libxgboost")]
 macro_rules! xgb_call {
     ($x:expr) => {
         XGBError::check_return_value(unsafe { $x })
     };
 }
 
+#[cfg(feature = "libxgboost")]
+mod array_interface;
 mod error;
 pub use error::{XGBError, XGBResult};
 
+#[cfg(feature = "libxgboost")]
 mod dmatrix;
-pub use dmatrix::DMatrix;
+#[cfg(feature = "libxgboost")]
+pub use dmatrix::{DMatrix, DMatrixParameters, DMatrixParametersBuilder};
+
+#[cfg(feature = "libxgboost")]
+mod data_iter;
+#[cfg(feature = "libxgboost")]
+pub use data_iter::{Batch, DataIter};
+
+#[cfg(feature = "libxgboost")]
+mod quantile_dmatrix;
+#[cfg(feature = "libxgboost")]
+pub use quantile_dmatrix::{QuantileDMatrix, QuantileDMatrixParameters, QuantileDMatrixParametersBuilder};
 
+#[cfg(feature = "libxgboost")]
 mod booster;
-pub use booster::{Booster, FeatureMap, FeatureType, PredictConfig, PredictType};
+#[cfg(feature = "libxgboost")]
+pub use booster::{
+    Booster, CustomEvaluation, CustomObjective, DumpFormat, EvaluationHistory, EvaluationResults, FeatureImportance,
+    FeatureMap, FeatureType, ImportanceType, MultiOutputObjective, PredictConfig, PredictType, Predictions,
+};
+#[cfg(feature = "libxgboost")]
+pub mod callback;
+#[cfg(feature = "libxgboost")]
+mod cv;
+#[cfg(feature = "libxgboost")]
+pub use cv::{cv, CrossValidationResults};
+#[cfg(feature = "codegen")]
+pub mod codegen;
+#[cfg(feature = "inference")]
+pub mod inference;
+pub mod model;
+#[cfg(feature = "onnx")]
+pub mod onnx;
+#[cfg(feature = "libxgboost")]
 pub mod parameters;
+#[cfg(feature = "libxgboost")]
 use std::{ffi, path::Path};
 
-#[cfg(not(target_os = "windows"))]
+#[cfg(all(feature = "libxgboost", not(target_os = "windows")))]
 pub fn path_to_c_str<P: AsRef<Path>>(path: P) -> ffi::CString {
     use std::os::unix::ffi::OsStrExt;
     ffi::CString::new(path.as_ref().as_os_str().as_bytes()).unwrap()
 }
-#[cfg(target_os = "windows")]
+#[cfg(all(feature = "libxgboost", target_os = "windows"))]
 pub fn path_to_c_str<P: AsRef<Path>>(path: P) -> ffi::CString {
     ffi::CString::new(path.as_ref().as_os_str().as_encoded_bytes()).unwrap()
 }
+
+#[cfg(feature = "libxgboost")]
+/// Format a float as a JSON number for XGBoost's JSON configs, which (like Python's `json` module) also accept `NaN`,
+/// `Infinity` and `-Infinity`.
+pub(crate) fn json_float(value: f32) -> String {
+    if value.is_nan() {
+        "NaN".to_owned()
+    } else if value.is_infinite() {
+        if value > 0.0 { "Infinity" } else { "-Infinity" }.to_owned()
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(feature = "libxgboost")]
+/// Format a string as a JSON string, escaping quotes, backslashes and control characters.
+pub(crate) fn json_string(value: &str) -> String {
+    let mut json = String::with_capacity(value.len() + 2);
+    json.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if c.is_control() => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
@@ -0,0 +1,850 @@
+//! Typed representation of tree models, for walking, analysing and transforming trees without parsing dumps by hand.
+//!
+//! Trees can be parsed from a JSON dump of a single tree (see
+//! [`Booster::dump_model_format`](../struct.Booster.html#method.dump_model_format)), or a whole ensemble can be
+//! parsed from a model saved as JSON by [`Booster::save`](../struct.Booster.html#method.save) or
+//! [`Booster::save_buffer`](../struct.Booster.html#method.save_buffer).
+//!
+//! # Example
+//!
+//! ```no_run
+//! use xgb::model::TreeModel;
+//!
+//! let model = TreeModel::from_json(&std::fs::read("xgb.json").unwrap()).unwrap();
+//! for tree in model.trees() {
+//!     for node in tree.nodes.iter().filter(|node| !node.is_leaf()) {
+//!         let split = node.split().unwrap();
+//!         println!("node {} splits on feature {} with gain {:?}", node.id, split.feature, split.gain);
+//!     }
+//! }
+//! ```
+use serde_json::Value;
+
+use super::{XGBError, XGBResult};
+
+/// Smallest category XGBoost treats as invalid, as larger integers can't all be represented exactly as an `f32`.
+const MAX_CATEGORY: f32 = (1 << f32::MANTISSA_DIGITS) as f32;
+
+/// A single decision tree.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tree {
+    /// Nodes of the tree ordered by their IDs, starting with the root.
+    pub nodes: Vec<Node>,
+}
+
+impl Tree {
+    /// Parse a tree from its JSON dump, as produced by
+    /// [`Booster::dump_model_format`](../struct.Booster.html#method.dump_model_format) with
+    /// [`DumpFormat::Json`](../enum.DumpFormat.html#variant.Json).
+    ///
+    /// Split features are dumped by name, which are mapped back to their index using `feature_names` (usually from
+    /// [`Booster::get_feature_names`](../struct.Booster.html#method.get_feature_names)). Features without a name are
+    /// dumped as `f0`, `f1`, etc.
+    pub fn from_json_dump(dump: &str, feature_names: &[String]) -> XGBResult<Self> {
+        let root: Value = serde_json::from_str(dump)
+            .map_err(|err| XGBError::new(format!("Failed to parse tree dump as JSON: {}", err)))?;
+
+        let mut nodes = Vec::new();
+        let mut pending = vec![&root];
+        while let Some(value) = pending.pop() {
+            nodes.push(Node::from_json_dump(value, feature_names)?);
+            if let Some(children) = value.get("children") {
+                let children = children
+                    .as_array()
+                    .ok_or_else(|| XGBError::new(format!("Invalid children in tree dump: {}", children)))?;
+                pending.extend(children);
+            }
+        }
+        nodes.sort_by_key(|node| node.id);
+        Tree::from_nodes(nodes)
+    }
+
+    /// Parse a tree from the `trees` of a saved JSON model.
+    fn from_saved_model(tree: &Value) -> XGBResult<Self> {
+        let leaf_size = tree
+            .get("tree_param")
+            .and_then(|param| param.get("size_leaf_vector"))
+            .map(parse_number::<usize>)
+            .transpose()?
+            .unwrap_or(1);
+        if leaf_size > 1 {
+            return Err(XGBError::new(
+                "Trees with vector leaves (multi_strategy) are not supported",
+            ));
+        }
+
+        let left_children = int_array(tree, "left_children")?;
+        let right_children = int_array(tree, "right_children")?;
+        let split_indices = int_array(tree, "split_indices")?;
+        let split_conditions = float_array(tree, "split_conditions")?;
+        let default_left = int_array(tree, "default_left")?;
+        let loss_changes = float_array(tree, "loss_changes")?;
+        let sum_hessian = float_array(tree, "sum_hessian")?;
+        let split_type = optional_int_array(tree, "split_type")?;
+        let categories = optional_int_array(tree, "categories")?;
+        let categories_nodes = optional_int_array(tree, "categories_nodes")?;
+        let categories_segments = optional_int_array(tree, "categories_segments")?;
+        let categories_sizes = optional_int_array(tree, "categories_sizes")?;
+
+        let num_nodes = left_children.len();
+        let arrays = [
+            right_children.len(),
+            split_indices.len(),
+            split_conditions.len(),
+            default_left.len(),
+            loss_changes.len(),
+            sum_hessian.len(),
+        ];
+        if arrays.iter().any(|&len| len != num_nodes) {
+            return Err(XGBError::new("Inconsistent number of nodes in saved tree"));
+        }
+
+        let mut nodes = Vec::with_capacity(num_nodes);
+        for id in 0..num_nodes {
+            let cover = Some(sum_hessian[id]);
+            if left_children[id] == -1 {
+                nodes.push(Node {
+                    id,
+                    kind: NodeKind::Leaf(split_conditions[id]),
+                    cover,
+                });
+                continue;
+            }
+
+            let left = node_id(left_children[id])?;
+            let right = node_id(right_children[id])?;
+            let missing = if default_left[id] != 0 { left } else { right };
+            let categorical = split_type.get(id).is_some_and(|&split_type| split_type == 1);
+            let split = if categorical {
+                let index = categories_nodes
+                    .iter()
+                    .position(|&node| node == id as i64)
+                    .ok_or_else(|| XGBError::new(format!("Missing categories for node {} of saved tree", id)))?;
+                let begin = *categories_segments.get(index).unwrap_or(&0) as usize;
+                let end = begin + *categories_sizes.get(index).unwrap_or(&0) as usize;
+                let set = categories
+                    .get(begin..end)
+                    .ok_or_else(|| XGBError::new(format!("Invalid categories for node {} of saved tree", id)))?;
+                // samples in the set of categories take the right branch
+                Split {
+                    feature: node_id(split_indices[id])?,
+                    condition: SplitCondition::Categories(set.iter().map(|&category| category as u32).collect()),
+                    yes: right,
+                    no: left,
+                    missing,
+                    gain: Some(loss_changes[id]),
+                }
+            } else {
+                Split {
+                    feature: node_id(split_indices[id])?,
+                    condition: SplitCondition::LessThan(split_conditions[id]),
+                    yes: left,
+                    no: right,
+                    missing,
+                    gain: Some(loss_changes[id]),
+                }
+            };
+            nodes.push(Node {
+                id,
+                kind: NodeKind::Split(split),
+                cover,
+            });
+        }
+        Tree::from_nodes(nodes)
+    }
+
+    /// Create a tree from nodes sorted by ID, checking that IDs are unique and that every child exists and has a
+    /// greater ID than its parent, as XGBoost assigns them. The latter rules out cycles, so walking the tree ends.
+    fn from_nodes(nodes: Vec<Node>) -> XGBResult<Self> {
+        let tree = Tree { nodes };
+        if tree.nodes.is_empty() {
+            return Err(XGBError::new("Tree has no nodes"));
+        }
+        if let Some(pair) = tree.nodes.windows(2).find(|pair| pair[0].id == pair[1].id) {
+            return Err(XGBError::new(format!("Tree has duplicate node {}", pair[0].id)));
+        }
+        for (node, split) in tree.nodes.iter().filter_map(|node| Some((node, node.split()?))) {
+            for child in [split.yes, split.no, split.missing] {
+                if tree.node(child).is_none() {
+                    return Err(XGBError::new(format!("Tree is missing node {}", child)));
+                }
+                if child <= node.id {
+                    return Err(XGBError::new(format!(
+                        "Node {} of tree has child {} with a lower ID",
+                        node.id, child
+                    )));
+                }
+            }
+        }
+        Ok(tree)
+    }
+
+    /// Get the root node of the tree.
+    pub fn root(&self) -> &Node {
+        &self.nodes[0]
+    }
+
+    /// Get the node with the given ID, if it's part of the tree.
+    pub fn node(&self, id: usize) -> Option<&Node> {
+        // IDs are usually contiguous, e.g. always for trees of saved models
+        if let Some(node) = self.nodes.get(id).filter(|node| node.id == id) {
+            return Some(node);
+        }
+        self.nodes
+            .binary_search_by_key(&id, |node| node.id)
+            .ok()
+            .map(|index| &self.nodes[index])
+    }
+
+    /// Get the child nodes of the given node, which are empty for leaves.
+    pub fn children(&self, node: &Node) -> Vec<&Node> {
+        match node.split() {
+            Some(split) => [split.yes, split.no].iter().filter_map(|&id| self.node(id)).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    /// Get the leaf reached by a sample with the given feature values, where `NaN` marks missing values. Features
+    /// past the end of `features` are also treated as missing.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a split refers to a child which isn't part of the tree, which can only happen if the nodes were
+    /// modified after parsing.
+    pub fn leaf(&self, features: &[f32]) -> &Node {
+        let mut node = self.root();
+        while let Some(split) = node.split() {
+            let value = features.get(split.feature).copied().unwrap_or(f32::NAN);
+            let child = split.child(value);
+            node = self
+                .node(child)
+                .unwrap_or_else(|| panic!("Tree is missing node {}", child));
+        }
+        node
+    }
+
+    /// Iterate over the leaves of the tree.
+    pub fn leaves(&self) -> impl Iterator<Item = &Node> {
+        self.nodes.iter().filter(|node| node.is_leaf())
+    }
+
+    /// Get the number of leaves in the tree.
+    pub fn num_leaves(&self) -> usize {
+        self.leaves().count()
+    }
+
+    /// Get the depth of the tree, i.e. the number of splits on the longest path from the root to a leaf.
+    pub fn depth(&self) -> usize {
+        let mut depth = 0;
+        let mut pending = vec![(self.root(), 0)];
+        while let Some((node, node_depth)) = pending.pop() {
+            depth = depth.max(node_depth);
+            pending.extend(self.children(node).into_iter().map(|child| (child, node_depth + 1)));
+        }
+        depth
+    }
+}
+
+/// A node of a [`Tree`](struct.Tree.html), either splitting samples between two children or holding a leaf value.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    /// ID of the node within its tree, with the root having ID 0.
+    pub id: usize,
+
+    /// Whether this node is a split or a leaf.
+    pub kind: NodeKind,
+
+    /// Sum of the hessians of the training samples that reached this node, if known (e.g. only included in dumps
+    /// with statistics).
+    pub cover: Option<f32>,
+}
+
+impl Node {
+    /// Parse a single node (without its children) from a JSON tree dump.
+    fn from_json_dump(value: &Value, feature_names: &[String]) -> XGBResult<Self> {
+        let id = dump_field(value, "nodeid")
+            .and_then(|id| id.as_u64().ok_or_else(|| invalid_dump_field("nodeid", id)))? as usize;
+        let cover = value.get("cover").map(|cover| dump_float("cover", cover)).transpose()?;
+
+        if let Some(leaf) = value.get("leaf") {
+            return Ok(Node {
+                id,
+                kind: NodeKind::Leaf(dump_float("leaf", leaf)?),
+                cover,
+            });
+        }
+
+        let feature = dump_field(value, "split")?;
+        let feature = feature
+            .as_str()
+            .and_then(|name| {
+                feature_names
+                    .iter()
+                    .position(|feature_name| feature_name == name)
+                    .or_else(|| name.strip_prefix('f').and_then(|index| index.parse().ok()))
+            })
+            .ok_or_else(|| XGBError::new(format!("Unknown feature in tree dump: {}", feature)))?;
+        let condition = dump_field(value, "split_condition")?;
+        let condition = match condition.as_array() {
+            Some(categories) => SplitCondition::Categories(
+                categories
+                    .iter()
+                    .map(|category| category.as_u64().map(|category| category as u32))
+                    .collect::<Option<_>>()
+                    .ok_or_else(|| invalid_dump_field("split_condition", condition))?,
+            ),
+            None => SplitCondition::LessThan(dump_float("split_condition", condition)?),
+        };
+        let child =
+            |key| dump_field(value, key).and_then(|child| child.as_u64().ok_or_else(|| invalid_dump_field(key, child)));
+        Ok(Node {
+            id,
+            kind: NodeKind::Split(Split {
+                feature,
+                condition,
+                yes: child("yes")? as usize,
+                no: child("no")? as usize,
+                missing: child("missing")? as usize,
+                gain: value.get("gain").map(|gain| dump_float("gain", gain)).transpose()?,
+            }),
+            cover,
+        })
+    }
+
+    /// Whether this node is a leaf.
+    pub fn is_leaf(&self) -> bool {
+        matches!(self.kind, NodeKind::Leaf(_))
+    }
+
+    /// Get the split of this node, or `None` for leaves.
+    pub fn split(&self) -> Option<&Split> {
+        match &self.kind {
+            NodeKind::Split(split) => Some(split),
+            NodeKind::Leaf(_) => None,
+        }
+    }
+
+    /// Get the value of this leaf, or `None` for splits.
+    pub fn leaf_value(&self) -> Option<f32> {
+        match self.kind {
+            NodeKind::Leaf(value) => Some(value),
+            NodeKind::Split(_) => None,
+        }
+    }
+}
+
+/// Whether a [`Node`](struct.Node.html) is a split or a leaf.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NodeKind {
+    /// Node splitting samples between two children.
+    Split(Split),
+
+    /// Leaf node, holding the value added to the margin of samples reaching it.
+    Leaf(f32),
+}
+
+/// Split of a non-leaf [`Node`](struct.Node.html).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Split {
+    /// Index of the feature used by the split.
+    pub feature: usize,
+
+    /// Condition on the feature value deciding whether a sample takes the `yes` or `no` branch.
+    pub condition: SplitCondition,
+
+    /// ID of the child taken by samples meeting the condition.
+    pub yes: usize,
+
+    /// ID of the child taken by samples not meeting the condition.
+    pub no: usize,
+
+    /// ID of the child taken by samples missing the feature (the default direction), same as either `yes` or `no`.
+    pub missing: usize,
+
+    /// Loss reduction from the split, if known (e.g. only included in dumps with statistics).
+    pub gain: Option<f32>,
+}
+
+impl Split {
+    /// Whether samples missing the feature take the `yes` branch.
+    pub fn default_yes(&self) -> bool {
+        self.missing == self.yes
+    }
+
+    /// Get the ID of the child taken by a sample with the given feature value, where `NaN` marks a missing value.
+    ///
+    /// As in XGBoost, categorical values are truncated to integers, and negative values or values too large to be
+    /// represented exactly as an `f32` never match the categories of a split.
+    pub fn child(&self, value: f32) -> usize {
+        if value.is_nan() {
+            return self.missing;
+        }
+        let matches = match &self.condition {
+            SplitCondition::LessThan(threshold) => value < *threshold,
+            SplitCondition::Categories(categories) => {
+                (0.0..MAX_CATEGORY).contains(&value) && categories.contains(&(value as u32))
+            }
+        };
+        if matches {
+            self.yes
+        } else {
+            self.no
+        }
+    }
+}
+
+/// Condition of a [`Split`](struct.Split.html) on a feature value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SplitCondition {
+    /// Numerical split, where samples with a value less than the threshold take the `yes` branch.
+    LessThan(f32),
+
+    /// Categorical split, where samples with one of the given categories take the `yes` branch.
+    Categories(Vec<u32>),
+}
+
+/// A tree ensemble (gbtree or dart model) parsed from a model saved as JSON.
+///
+/// # Example
+///
+/// ```no_run
+/// use xgb::model::TreeModel;
+///
+/// let model = TreeModel::from_json(&std::fs::read("xgb.json").unwrap()).unwrap();
+/// println!("{} trees for objective {}", model.trees().len(), model.objective());
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct TreeModel {
+    trees: Vec<Tree>,
+    tree_outputs: Vec<usize>,
+    tree_weights: Vec<f32>,
+    iteration_indptr: Vec<usize>,
+    num_features: usize,
+    num_outputs: usize,
+    base_score: Vec<f32>,
+    objective: String,
+    feature_names: Vec<String>,
+}
+
+impl TreeModel {
+    /// Parse a model saved as JSON by [`Booster::save`](../struct.Booster.html#method.save) (with a `.json`
+    /// extension) or [`Booster::save_buffer(false)`](../struct.Booster.html#method.save_buffer).
+    pub fn from_json(json: &[u8]) -> XGBResult<Self> {
+        let model: Value = serde_json::from_slice(json)
+            .map_err(|err| XGBError::new(format!("Failed to parse model as JSON: {}", err)))?;
+        TreeModel::from_value(&model)
+    }
+
+    /// Parse a model from its JSON document.
+    pub(crate) fn from_value(model: &Value) -> XGBResult<Self> {
+        let learner = model_field(model, "learner")?;
+        let model_param = model_field(learner, "learner_model_param")?;
+        let num_class: usize = parse_number(model_field(model_param, "num_class")?)?;
+        let num_target: usize = match model_param.get("num_target") {
+            Some(num_target) => parse_number(num_target)?,
+            None => 1,
+        };
+        let num_outputs = num_class.max(num_target).max(1);
+        let num_features = parse_number(model_field(model_param, "num_feature")?)?;
+        let base_score = parse_base_score(model_field(model_param, "base_score")?, num_outputs)?;
+        let objective = model_field(model_field(learner, "objective")?, "name")?
+            .as_str()
+            .unwrap_or_default()
+            .to_owned();
+        let feature_names = match learner.get("feature_names").and_then(Value::as_array) {
+            Some(names) => names
+                .iter()
+                .filter_map(|name| name.as_str().map(str::to_owned))
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let booster = model_field(learner, "gradient_booster")?;
+        let (gbtree, weight_drop) = match booster.get("name").and_then(Value::as_str) {
+            Some("gbtree") => (booster, None),
+            Some("dart") => (
+                model_field(booster, "gbtree")?,
+                Some(float_array(booster, "weight_drop")?),
+            ),
+            name => {
+                return Err(XGBError::new(format!(
+                    "Only gbtree and dart models are supported, got booster {}",
+                    name.unwrap_or_default()
+                )))
+            }
+        };
+        let gbtree_model = model_field(gbtree, "model")?;
+        let trees = model_field(gbtree_model, "trees")?
+            .as_array()
+            .ok_or_else(|| XGBError::new("Invalid trees in saved model"))?
+            .iter()
+            .map(Tree::from_saved_model)
+            .collect::<XGBResult<Vec<_>>>()?;
+        let tree_outputs = int_array(gbtree_model, "tree_info")?
+            .into_iter()
+            .map(|output| output as usize)
+            .collect::<Vec<_>>();
+        if tree_outputs.len() != trees.len() || tree_outputs.iter().any(|&output| output >= num_outputs) {
+            return Err(XGBError::new("Invalid tree_info in saved model"));
+        }
+        let tree_weights = weight_drop.unwrap_or_else(|| vec![1.0; trees.len()]);
+        if tree_weights.len() != trees.len() {
+            return Err(XGBError::new("Invalid weight_drop in saved model"));
+        }
+
+        let iteration_indptr: Vec<usize> = match gbtree_model.get("iteration_indptr") {
+            Some(_) => int_array(gbtree_model, "iteration_indptr")?
+                .into_iter()
+                .map(|index| index as usize)
+                .collect(),
+            None => {
+                // older models don't record rounds, which always have the same number of trees
+                let gbtree_model_param = model_field(gbtree_model, "gbtree_model_param")?;
+                let num_parallel_tree: usize = parse_number(model_field(gbtree_model_param, "num_parallel_tree")?)?;
+                let trees_per_round = num_parallel_tree.max(1) * num_outputs;
+                if trees.len() % trees_per_round != 0 {
+                    return Err(XGBError::new(format!(
+                        "Saved model has {} trees, which isn't a multiple of {} trees per round",
+                        trees.len(),
+                        trees_per_round
+                    )));
+                }
+                (0..=trees.len()).step_by(trees_per_round).collect()
+            }
+        };
+        let valid_rounds = iteration_indptr.first() == Some(&0)
+            && iteration_indptr.last() == Some(&trees.len())
+            && iteration_indptr.windows(2).all(|pair| pair[0] <= pair[1]);
+        if !valid_rounds {
+            return Err(XGBError::new("Invalid iteration_indptr in saved model"));
+        }
+
+        Ok(TreeModel {
+            trees,
+            tree_outputs,
+            tree_weights,
+            iteration_indptr,
+            num_features,
+            num_outputs,
+            base_score,
+            objective,
+            feature_names,
+        })
+    }
+
+    /// Get the trees of the ensemble, in the order they were trained.
+    pub fn trees(&self) -> &[Tree] {
+        &self.trees
+    }
+
+    /// Get the index of the output (e.g. the class for multiclass models) each tree contributes to.
+    pub fn tree_outputs(&self) -> &[usize] {
+        &self.tree_outputs
+    }
+
+    /// Get the weight each tree's leaf values are scaled by, which are all 1 except for dart models.
+    pub fn tree_weights(&self) -> &[f32] {
+        &self.tree_weights
+    }
+
+    /// Get the number of boosting rounds of the ensemble.
+    pub fn num_boosted_rounds(&self) -> usize {
+        self.iteration_indptr.len().saturating_sub(1)
+    }
+
+    /// Get the indices of the trees trained in the given boosting round, if the model has that many rounds.
+    pub fn round_trees(&self, round: usize) -> Option<std::ops::Range<usize>> {
+        let end = *self.iteration_indptr.get(round.checked_add(1)?)?;
+        Some(self.iteration_indptr[round]..end)
+    }
+
+    /// Get the number of features the model was trained on.
+    pub fn num_features(&self) -> usize {
+        self.num_features
+    }
+
+    /// Get the number of outputs of the model, e.g. the number of classes for multiclass models.
+    pub fn num_outputs(&self) -> usize {
+        self.num_outputs
+    }
+
+    /// Get the base score of each output, in the same space as transformed predictions (e.g. a probability for
+    /// logistic objectives).
+    pub fn base_score(&self) -> &[f32] {
+        &self.base_score
+    }
+
+    /// Get the name of the objective the model was trained with, e.g. `binary:logistic`.
+    pub fn objective(&self) -> &str {
+        &self.objective
+    }
+
+    /// Get the feature names stored in the model, which are empty if it has none.
+    pub fn feature_names(&self) -> &[String] {
+        &self.feature_names
+    }
+
+    /// Whether any tree of the ensemble splits on a categorical feature.
+    pub fn has_categorical_splits(&self) -> bool {
+        self.trees.iter().flat_map(|tree| tree.nodes.iter()).any(|node| {
+            node.split()
+                .is_some_and(|split| matches!(split.condition, SplitCondition::Categories(_)))
+        })
+    }
+}
+
+/// Parse the base score, stored as a single value or (since XGBoost 3.0) a list of values like `[5E-1]`.
+fn parse_base_score(value: &Value, num_outputs: usize) -> XGBResult<Vec<f32>> {
+    let invalid = || XGBError::new(format!("Invalid base_score in saved model: {}", value));
+    let scores = value
+        .as_str()
+        .ok_or_else(invalid)?
+        .trim_matches(|c| c == '[' || c == ']')
+        .split(',')
+        .map(|score| score.trim().parse().map_err(|_| invalid()))
+        .collect::<XGBResult<Vec<f32>>>()?;
+    match scores.len() {
+        1 => Ok(vec![scores[0]; num_outputs]),
+        len if len == num_outputs => Ok(scores),
+        _ => Err(invalid()),
+    }
+}
+
+/// Parse a number stored as a string, as done for the parameters of saved models.
+fn parse_number<T: std::str::FromStr>(value: &Value) -> XGBResult<T> {
+    value
+        .as_str()
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| XGBError::new(format!("Invalid number in saved model: {}", value)))
+}
+
+fn model_field<'a>(value: &'a Value, key: &str) -> XGBResult<&'a Value> {
+    value
+        .get(key)
+        .ok_or_else(|| XGBError::new(format!("Missing '{}' in saved model", key)))
+}
+
+fn int_array(value: &Value, key: &str) -> XGBResult<Vec<i64>> {
+    model_field(value, key)?
+        .as_array()
+        .and_then(|values| {
+            values
+                .iter()
+                .map(|value| value.as_i64().or_else(|| value.as_bool().map(i64::from)))
+                .collect()
+        })
+        .ok_or_else(|| XGBError::new(format!("Invalid '{}' in saved model", key)))
+}
+
+fn optional_int_array(value: &Value, key: &str) -> XGBResult<Vec<i64>> {
+    match value.get(key) {
+        Some(_) => int_array(value, key),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn float_array(value: &Value, key: &str) -> XGBResult<Vec<f32>> {
+    model_field(value, key)?
+        .as_array()
+        .and_then(|values| {
+            values
+                .iter()
+                .map(|value| value.as_f64().map(|value| value as f32))
+                .collect()
+        })
+        .ok_or_else(|| XGBError::new(format!("Invalid '{}' in saved model", key)))
+}
+
+fn node_id(id: i64) -> XGBResult<usize> {
+    usize::try_from(id).map_err(|_| XGBError::new(format!("Invalid node or feature index in saved model: {}", id)))
+}
+
+fn dump_field<'a>(value: &'a Value, key: &str) -> XGBResult<&'a Value> {
+    value
+        .get(key)
+        .ok_or_else(|| XGBError::new(format!("Missing '{}' in tree dump node: {}", key, value)))
+}
+
+fn dump_float(key: &str, value: &Value) -> XGBResult<f32> {
+    value
+        .as_f64()
+        .map(|value| value as f32)
+        .ok_or_else(|| invalid_dump_field(key, value))
+}
+
+fn invalid_dump_field(key: &str, value: &Value) -> XGBError {
+    XGBError::new(format!("Invalid '{}' in tree dump: {}", key, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DUMP: &str = r#"{ "nodeid": 0, "depth": 0, "split": "f29", "split_condition": 2.5, "yes": 1, "no": 2,
+        "missing": 2, "gain": 4000.5, "cover": 1628.25, "children": [
+          { "nodeid": 1, "depth": 1, "split": "odor", "split_condition": [1, 3], "yes": 4, "no": 3, "missing": 3,
+            "gain": 198.25, "cover": 703.75, "children": [
+              { "nodeid": 3, "leaf": 1.75, "cover": 13.25 },
+              { "nodeid": 4, "leaf": -1.5, "cover": 690.5 }
+          ]},
+          { "nodeid": 2, "leaf": 1.25, "cover": 924.5 }
+        ]}"#;
+
+    #[test]
+    fn tree_from_json_dump() {
+        let feature_names = vec!["odor".to_owned()];
+        let tree = Tree::from_json_dump(DUMP, &feature_names).unwrap();
+        assert_eq!(
+            tree.nodes.iter().map(|node| node.id).collect::<Vec<_>>(),
+            vec![0, 1, 2, 3, 4]
+        );
+        assert_eq!(tree.num_leaves(), 3);
+        assert_eq!(tree.depth(), 2);
+
+        let root = tree.root().split().unwrap();
+        assert_eq!(root.feature, 29);
+        assert_eq!(root.condition, SplitCondition::LessThan(2.5));
+        assert_eq!((root.yes, root.no, root.missing), (1, 2, 2));
+        assert!(!root.default_yes());
+        assert_eq!(root.gain, Some(4000.5));
+        assert_eq!(tree.root().cover, Some(1628.25));
+
+        let categorical = tree.node(1).unwrap().split().unwrap();
+        assert_eq!(categorical.feature, 0);
+        assert_eq!(categorical.condition, SplitCondition::Categories(vec![1, 3]));
+        assert_eq!(
+            tree.children(tree.node(1).unwrap())
+                .iter()
+                .map(|node| node.id)
+                .collect::<Vec<_>>(),
+            vec![4, 3]
+        );
+        assert_eq!(tree.node(4).unwrap().leaf_value(), Some(-1.5));
+        assert_eq!(tree.node(5), None);
+
+        // missing features take the default direction
+        assert_eq!(tree.leaf(&[]).id, 2);
+        let mut features = [0.0; 30];
+        features[29] = 2.5;
+        assert_eq!(tree.leaf(&features).id, 2);
+        features[29] = 1.0;
+        for (category, leaf) in [(3.0, 4), (3.9, 4), (1.0, 4), (2.0, 3), (-3.0, 3), (f32::NAN, 3)] {
+            features[0] = category;
+            assert_eq!(tree.leaf(&features).id, leaf, "{}", category);
+        }
+
+        assert!(Tree::from_json_dump(DUMP, &[]).is_err());
+        assert!(Tree::from_json_dump(r#"{ "nodeid": 0, "split": "f1", "split_condition": 1.0 }"#, &[]).is_err());
+    }
+
+    #[test]
+    fn tree_model_from_json() {
+        let json = serde_json::json!({
+            "learner": {
+                "feature_names": [],
+                "gradient_booster": {
+                    "model": {
+                        "gbtree_model_param": {"num_parallel_tree": "1", "num_trees": "2"},
+                        "iteration_indptr": [0, 1, 2],
+                        "tree_info": [0, 0],
+                        "trees": [
+                            {
+                                "default_left": [1, 0, 0],
+                                "left_children": [1, -1, -1],
+                                "right_children": [2, -1, -1],
+                                "split_indices": [3, 0, 0],
+                                "split_conditions": [0.5, -0.25, 0.75],
+                                "split_type": [0, 0, 0],
+                                "loss_changes": [10.0, 0.0, 0.0],
+                                "sum_hessian": [8.0, 3.0, 5.0],
+                                "tree_param": {"num_nodes": "3", "size_leaf_vector": "1"}
+                            },
+                            {
+                                "default_left": [0, 0, 0],
+                                "left_children": [1, -1, -1],
+                                "right_children": [2, -1, -1],
+                                "split_indices": [1, 0, 0],
+                                "split_conditions": [1.0, 0.5, -0.5],
+                                "split_type": [1, 0, 0],
+                                "categories": [2, 4],
+                                "categories_nodes": [0],
+                                "categories_segments": [0],
+                                "categories_sizes": [2],
+                                "loss_changes": [4.0, 0.0, 0.0],
+                                "sum_hessian": [8.0, 6.0, 2.0],
+                                "tree_param": {"num_nodes": "3", "size_leaf_vector": "1"}
+                            }
+                        ]
+                    },
+                    "name": "gbtree"
+                },
+                "learner_model_param": {
+                    "base_score": "[5E-1]",
+                    "num_class": "0",
+                    "num_feature": "4",
+                    "num_target": "1"
+                },
+                "objective": {"name": "binary:logistic"}
+            },
+            "version": [3, 0, 0]
+        });
+        let model = TreeModel::from_json(json.to_string().as_bytes()).unwrap();
+        assert_eq!(model.trees().len(), 2);
+        assert_eq!(model.tree_outputs(), &[0, 0]);
+        assert_eq!(model.tree_weights(), &[1.0, 1.0]);
+        assert_eq!(model.num_boosted_rounds(), 2);
+        assert_eq!(model.round_trees(1), Some(1..2));
+        assert_eq!(model.round_trees(2), None);
+        assert_eq!(model.num_features(), 4);
+        assert_eq!(model.num_outputs(), 1);
+        assert_eq!(model.base_score(), &[0.5]);
+        assert_eq!(model.objective(), "binary:logistic");
+
+        let numerical = model.trees()[0].root().split().unwrap();
+        assert_eq!(numerical.feature, 3);
+        assert_eq!(numerical.condition, SplitCondition::LessThan(0.5));
+        assert_eq!((numerical.yes, numerical.no, numerical.missing), (1, 2, 1));
+        assert_eq!(numerical.gain, Some(10.0));
+        assert_eq!(model.trees()[0].node(1).unwrap().leaf_value(), Some(-0.25));
+        assert_eq!(model.trees()[0].node(2).unwrap().cover, Some(5.0));
+
+        let categorical = model.trees()[1].root().split().unwrap();
+        assert_eq!(categorical.condition, SplitCondition::Categories(vec![2, 4]));
+        assert_eq!((categorical.yes, categorical.no, categorical.missing), (2, 1, 2));
+        assert!(categorical.default_yes());
+
+        let mut json = json;
+        let gbtree_model = &mut json["learner"]["gradient_booster"]["model"];
+        gbtree_model["iteration_indptr"] = serde_json::json!([0, 1]);
+        assert!(TreeModel::from_json(json.to_string().as_bytes()).is_err());
+
+        // without iteration_indptr, rounds are derived from the number of trees per round
+        let gbtree_model = &mut json["learner"]["gradient_booster"]["model"];
+        gbtree_model.as_object_mut().unwrap().remove("iteration_indptr");
+        let model = TreeModel::from_json(json.to_string().as_bytes()).unwrap();
+        assert_eq!(model.num_boosted_rounds(), 2);
+        json["learner"]["gradient_booster"]["model"]["gbtree_model_param"]["num_parallel_tree"] = "2".into();
+        let model = TreeModel::from_json(json.to_string().as_bytes()).unwrap();
+        assert_eq!(model.round_trees(0), Some(0..2));
+        json["learner"]["gradient_booster"]["model"]["gbtree_model_param"]["num_parallel_tree"] = "3".into();
+        assert!(TreeModel::from_json(json.to_string().as_bytes()).is_err());
+    }
+
+    #[test]
+    fn invalid_tree_from_json_dump() {
+        let cyclic = r#"{ "nodeid": 0, "split": "f0", "split_condition": 1.0, "yes": 1, "no": 2, "missing": 1,
+          "children": [
+            { "nodeid": 1, "split": "f1", "split_condition": 0.5, "yes": 0, "no": 2, "missing": 2 },
+            { "nodeid": 2, "leaf": 0.5 }
+        ]}"#;
+        assert!(Tree::from_json_dump(cyclic, &[]).is_err());
+
+        let duplicate = r#"{ "nodeid": 0, "split": "f0", "split_condition": 1.0, "yes": 1, "no": 2, "missing": 1,
+          "children": [
+            { "nodeid": 1, "leaf": -0.5 },
+            { "nodeid": 1, "leaf": 0.5 },
+            { "nodeid": 2, "leaf": 0.25 }
+        ]}"#;
+        assert!(Tree::from_json_dump(duplicate, &[]).is_err());
+    }
+}
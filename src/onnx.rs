@@ -0,0 +1,717 @@
+//! Export tree models to [ONNX](https://onnx.ai), for serving with e.g. ONNX Runtime.
+//!
+//! Models are converted to a single `TreeEnsembleRegressor` or `TreeEnsembleClassifier` operator from the `ai.onnx.ml`
+//! domain, following the same conventions as `onnxmltools`:
+//!
+//! * `binary:logistic` becomes a classifier with labels `0` and `1`, and a `LOGISTIC` post transform, outputting a
+//!   `label` (int64) and `probabilities` (with shape `[N, 2]`)
+//! * `multi:softprob` and `multi:softmax` become a classifier with a `SOFTMAX` post transform, outputting a `label`
+//!   and `probabilities` (with shape `[N, number of classes]`)
+//! * `reg:logistic` becomes a regressor with a `LOGISTIC` post transform, outputting `variable`
+//! * `count:poisson`, `reg:gamma`, `reg:tweedie`, `survival:cox` and `survival:aft` become a regressor followed by an
+//!   `Exp` operator, as ONNX has no exponential post transform
+//! * other objectives supported by [`inference`](../inference/index.html) (except `binary:hinge`) become a regressor
+//!   outputting the margins as `variable`, with shape `[N, number of outputs]`
+//!
+//! The input is called `input`, with shape `[N, number of features]` and `NaN` for missing values. The base score is
+//! converted to a margin and stored in `base_values`. Models with categorical splits aren't supported.
+//!
+//! Models are serialized the way the reference protocol buffers implementation serializes ONNX's proto2 schema
+//! (fields in order of their numbers, repeated numbers unpacked), with attributes sorted by name like `onnx.helper`.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use xgb::inference::Ensemble;
+//!
+//! let ensemble = Ensemble::load("xgb.ubj").unwrap();
+//! std::fs::write("xgb.onnx", xgb::onnx::export(&ensemble).unwrap()).unwrap();
+//! ```
+use std::collections::HashMap;
+
+use super::inference::{Ensemble, Objective};
+use super::model::SplitCondition;
+use super::{XGBError, XGBResult};
+
+/// ONNX IR version of exported models.
+const IR_VERSION: i64 = 8;
+
+/// Version of the default ONNX operator set used by exported models (for `Exp`).
+const OPSET_VERSION: i64 = 15;
+
+/// Version of the `ai.onnx.ml` operator set used by exported models.
+const ML_OPSET_VERSION: i64 = 3;
+
+const ML_DOMAIN: &str = "ai.onnx.ml";
+
+/// `TensorProto.DataType` values.
+const FLOAT: i64 = 1;
+const INT64: i64 = 7;
+
+/// `AttributeProto.AttributeType` values.
+const ATTRIBUTE_INT: i64 = 2;
+const ATTRIBUTE_STRING: i64 = 3;
+const ATTRIBUTE_FLOATS: i64 = 6;
+const ATTRIBUTE_INTS: i64 = 7;
+const ATTRIBUTE_STRINGS: i64 = 8;
+
+/// Export a tree ensemble as a serialized ONNX model.
+///
+/// Fails for models with categorical splits, or trained with `binary:hinge`.
+pub fn export(ensemble: &Ensemble) -> XGBResult<Vec<u8>> {
+    let model = ensemble.model();
+    let num_outputs = model.num_outputs();
+    let objective = ensemble.objective();
+    let classifier = matches!(objective, Objective::SoftProb | Objective::SoftMax)
+        || (objective == Objective::Logistic && model.objective() == "binary:logistic");
+    let post_transform = match objective {
+        Objective::Logistic => "LOGISTIC",
+        Objective::SoftProb | Objective::SoftMax => "SOFTMAX",
+        Objective::Identity | Objective::Exp => "NONE",
+        Objective::Hinge => {
+            return Err(XGBError::new(format!(
+                "Objective {} is not supported for ONNX export",
+                model.objective()
+            )))
+        }
+    };
+
+    // flatten the trees into the parallel arrays used by the operators, with node IDs being positions in each tree
+    let mut nodes = TreeNodes::default();
+    let mut leaves = Leaves::default();
+    let trees = model.trees().iter().zip(model.tree_outputs()).zip(model.tree_weights());
+    for (tree_id, ((tree, &output), &weight)) in trees.enumerate() {
+        let positions: HashMap<usize, i64> = tree
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(position, node)| (node.id, position as i64))
+            .collect();
+        let position = |id| {
+            positions
+                .get(&id)
+                .copied()
+                .ok_or_else(|| XGBError::new(format!("Tree {} is missing node {}", tree_id, id)))
+        };
+        for (node_id, node) in tree.nodes.iter().enumerate() {
+            nodes.tree_ids.push(tree_id as i64);
+            nodes.node_ids.push(node_id as i64);
+            match node.split() {
+                Some(split) => {
+                    let threshold = match split.condition {
+                        SplitCondition::LessThan(threshold) => threshold,
+                        SplitCondition::Categories(_) => {
+                            return Err(XGBError::new("Categorical splits are not supported for ONNX export"))
+                        }
+                    };
+                    nodes.feature_ids.push(split.feature as i64);
+                    nodes.modes.push("BRANCH_LT");
+                    nodes.values.push(threshold);
+                    nodes.true_node_ids.push(position(split.yes)?);
+                    nodes.false_node_ids.push(position(split.no)?);
+                    nodes.missing_tracks_true.push(split.default_yes() as i64);
+                }
+                None => {
+                    nodes.feature_ids.push(0);
+                    nodes.modes.push("LEAF");
+                    nodes.values.push(0.0);
+                    nodes.true_node_ids.push(0);
+                    nodes.false_node_ids.push(0);
+                    nodes.missing_tracks_true.push(0);
+
+                    leaves.tree_ids.push(tree_id as i64);
+                    leaves.node_ids.push(node_id as i64);
+                    leaves.ids.push(output as i64);
+                    leaves.weights.push(weight * node.leaf_value().unwrap());
+                }
+            }
+        }
+    }
+
+    let mut attributes = nodes.attributes();
+    attributes.push(floats_attribute("base_values", ensemble.base_margin()));
+    attributes.push(string_attribute("post_transform", post_transform));
+    let num_features = model.num_features() as i64;
+    let mut operators = Vec::new();
+    let mut outputs = Vec::new();
+    if classifier {
+        // binary models have a single score per row, for the probability of class 1
+        let num_classes = num_outputs.max(2) as i64;
+        let class_ids = if num_outputs == 1 { vec![0; leaves.ids.len()] } else { leaves.ids };
+        attributes.push(ints_attribute(
+            "classlabels_int64s",
+            &(0..num_classes).collect::<Vec<_>>(),
+        ));
+        attributes.push(ints_attribute("class_ids", &class_ids));
+        attributes.push(ints_attribute("class_nodeids", &leaves.node_ids));
+        attributes.push(ints_attribute("class_treeids", &leaves.tree_ids));
+        attributes.push(floats_attribute("class_weights", &leaves.weights));
+        operators.push(node(
+            "TreeEnsembleClassifier",
+            &["input"],
+            &["label", "probabilities"],
+            attributes,
+        ));
+        outputs.push(tensor_value_info("label", INT64, &[None]));
+        outputs.push(tensor_value_info("probabilities", FLOAT, &[None, Some(num_classes)]));
+    } else {
+        let output = if objective == Objective::Exp { "margin" } else { "variable" };
+        attributes.push(int_attribute("n_targets", num_outputs as i64));
+        attributes.push(ints_attribute("target_ids", &leaves.ids));
+        attributes.push(ints_attribute("target_nodeids", &leaves.node_ids));
+        attributes.push(ints_attribute("target_treeids", &leaves.tree_ids));
+        attributes.push(floats_attribute("target_weights", &leaves.weights));
+        attributes.push(string_attribute("aggregate_function", "SUM"));
+        operators.push(node("TreeEnsembleRegressor", &["input"], &[output], attributes));
+        if objective == Objective::Exp {
+            let mut exp = Message::default();
+            exp.string(1, "margin");
+            exp.string(2, "variable");
+            exp.string(3, "Exp");
+            exp.string(4, "Exp");
+            operators.push(exp);
+        }
+        outputs.push(tensor_value_info("variable", FLOAT, &[None, Some(num_outputs as i64)]));
+    }
+
+    let mut graph = Message::default();
+    for operator in &operators {
+        graph.message(1, operator);
+    }
+    graph.string(2, &format!("xgboost {}", model.objective()));
+    graph.message(11, &tensor_value_info("input", FLOAT, &[None, Some(num_features)]));
+    for output in &outputs {
+        graph.message(12, output);
+    }
+
+    let mut onnx = Message::default();
+    onnx.int(1, IR_VERSION);
+    onnx.string(2, "xgb");
+    onnx.string(3, env!("CARGO_PKG_VERSION"));
+    onnx.message(7, &graph);
+    onnx.message(8, &opset("", OPSET_VERSION));
+    onnx.message(8, &opset(ML_DOMAIN, ML_OPSET_VERSION));
+    Ok(onnx.0)
+}
+
+/// Node attributes of the tree ensemble operators, one entry per node of every tree.
+#[derive(Default)]
+struct TreeNodes {
+    tree_ids: Vec<i64>,
+    node_ids: Vec<i64>,
+    feature_ids: Vec<i64>,
+    modes: Vec<&'static str>,
+    values: Vec<f32>,
+    true_node_ids: Vec<i64>,
+    false_node_ids: Vec<i64>,
+    missing_tracks_true: Vec<i64>,
+}
+
+impl TreeNodes {
+    fn attributes(&self) -> Vec<Attribute> {
+        vec![
+            ints_attribute("nodes_treeids", &self.tree_ids),
+            ints_attribute("nodes_nodeids", &self.node_ids),
+            ints_attribute("nodes_featureids", &self.feature_ids),
+            strings_attribute("nodes_modes", &self.modes),
+            floats_attribute("nodes_values", &self.values),
+            ints_attribute("nodes_truenodeids", &self.true_node_ids),
+            ints_attribute("nodes_falsenodeids", &self.false_node_ids),
+            ints_attribute("nodes_missing_value_tracks_true", &self.missing_tracks_true),
+        ]
+    }
+}
+
+/// Leaf values of every tree, along with the output (target or class) they contribute to.
+#[derive(Default)]
+struct Leaves {
+    tree_ids: Vec<i64>,
+    node_ids: Vec<i64>,
+    ids: Vec<i64>,
+    weights: Vec<f32>,
+}
+
+fn opset(domain: &str, version: i64) -> Message {
+    let mut opset = Message::default();
+    opset.string(1, domain);
+    opset.int(2, version);
+    opset
+}
+
+/// Create an operator from the `ai.onnx.ml` domain, with its attributes sorted by name like `onnx.helper` does.
+fn node(op_type: &str, inputs: &[&str], outputs: &[&str], mut attributes: Vec<Attribute>) -> Message {
+    let mut node = Message::default();
+    for input in inputs {
+        node.string(1, input);
+    }
+    for output in outputs {
+        node.string(2, output);
+    }
+    node.string(3, op_type);
+    node.string(4, op_type);
+    attributes.sort_by_key(|attribute| attribute.name);
+    for attribute in &attributes {
+        node.message(5, &attribute.message);
+    }
+    node.string(7, ML_DOMAIN);
+    node
+}
+
+/// Describe a tensor, where dimensions of unknown size are named `N`.
+fn tensor_value_info(name: &str, elem_type: i64, dims: &[Option<i64>]) -> Message {
+    let mut shape = Message::default();
+    for dim in dims {
+        let mut dimension = Message::default();
+        match dim {
+            Some(size) => dimension.int(1, *size),
+            None => dimension.string(2, "N"),
+        }
+        shape.message(1, &dimension);
+    }
+    let mut tensor = Message::default();
+    tensor.int(1, elem_type);
+    tensor.message(2, &shape);
+    let mut tensor_type = Message::default();
+    tensor_type.message(1, &tensor);
+
+    let mut value_info = Message::default();
+    value_info.string(1, name);
+    value_info.message(2, &tensor_type);
+    value_info
+}
+
+/// An operator attribute, kept along with its name to sort attributes.
+struct Attribute {
+    name: &'static str,
+    message: Message,
+}
+
+/// Create an attribute, whose value is added by `add_value` between its name and type to keep fields in order.
+fn attribute(name: &'static str, attribute_type: i64, add_value: impl FnOnce(&mut Message)) -> Attribute {
+    let mut message = Message::default();
+    message.string(1, name);
+    add_value(&mut message);
+    message.int(20, attribute_type);
+    Attribute { name, message }
+}
+
+fn int_attribute(name: &'static str, value: i64) -> Attribute {
+    attribute(name, ATTRIBUTE_INT, |attribute| attribute.int(3, value))
+}
+
+fn string_attribute(name: &'static str, value: &str) -> Attribute {
+    attribute(name, ATTRIBUTE_STRING, |attribute| attribute.string(4, value))
+}
+
+fn floats_attribute(name: &'static str, values: &[f32]) -> Attribute {
+    attribute(name, ATTRIBUTE_FLOATS, |attribute| {
+        for &value in values {
+            attribute.float(7, value);
+        }
+    })
+}
+
+fn ints_attribute(name: &'static str, values: &[i64]) -> Attribute {
+    attribute(name, ATTRIBUTE_INTS, |attribute| {
+        for &value in values {
+            attribute.int(8, value);
+        }
+    })
+}
+
+fn strings_attribute(name: &'static str, values: &[&str]) -> Attribute {
+    attribute(name, ATTRIBUTE_STRINGS, |attribute| {
+        for value in values {
+            attribute.string(9, value);
+        }
+    })
+}
+
+/// A protocol buffers message, encoded as fields are added.
+#[derive(Default)]
+struct Message(Vec<u8>);
+
+impl Message {
+    fn key(&mut self, field: u32, wire_type: u8) {
+        write_varint(&mut self.0, ((field as u64) << 3) | wire_type as u64);
+    }
+
+    fn int(&mut self, field: u32, value: i64) {
+        self.key(field, 0);
+        write_varint(&mut self.0, value as u64);
+    }
+
+    fn float(&mut self, field: u32, value: f32) {
+        self.key(field, 5);
+        self.0.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn bytes(&mut self, field: u32, value: &[u8]) {
+        self.key(field, 2);
+        write_varint(&mut self.0, value.len() as u64);
+        self.0.extend_from_slice(value);
+    }
+
+    fn string(&mut self, field: u32, value: &str) {
+        self.bytes(field, value.as_bytes());
+    }
+
+    fn message(&mut self, field: u32, message: &Message) {
+        self.bytes(field, &message.0);
+    }
+}
+
+fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Decoded protocol buffers field value.
+    #[derive(Clone, Debug, PartialEq)]
+    enum Field<'a> {
+        Varint(u64),
+        Bytes(&'a [u8]),
+        Fixed32(u32),
+    }
+
+    fn read_varint(bytes: &[u8], pos: &mut usize) -> u64 {
+        let mut value = 0;
+        let mut shift = 0;
+        loop {
+            let byte = bytes[*pos];
+            *pos += 1;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte < 0x80 {
+                return value;
+            }
+            shift += 7;
+        }
+    }
+
+    fn decode(bytes: &[u8]) -> Vec<(u32, Field<'_>)> {
+        let mut fields = Vec::new();
+        let mut pos = 0;
+        while pos < bytes.len() {
+            let key = read_varint(bytes, &mut pos);
+            let field = match key & 7 {
+                0 => Field::Varint(read_varint(bytes, &mut pos)),
+                2 => {
+                    let len = read_varint(bytes, &mut pos) as usize;
+                    pos += len;
+                    Field::Bytes(&bytes[pos - len..pos])
+                }
+                5 => {
+                    pos += 4;
+                    Field::Fixed32(u32::from_le_bytes(bytes[pos - 4..pos].try_into().unwrap()))
+                }
+                wire_type => panic!("unexpected wire type {}", wire_type),
+            };
+            fields.push(((key >> 3) as u32, field));
+        }
+        fields
+    }
+
+    fn messages(bytes: &[u8], number: u32) -> Vec<&[u8]> {
+        decode(bytes)
+            .into_iter()
+            .filter_map(|(field, value)| match value {
+                Field::Bytes(bytes) if field == number => Some(bytes),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn string(bytes: &[u8], number: u32) -> String {
+        String::from_utf8(messages(bytes, number)[0].to_vec()).unwrap()
+    }
+
+    /// Attribute values of an operator, decoded by type.
+    #[derive(Default)]
+    struct Attributes {
+        ints: HashMap<String, Vec<i64>>,
+        floats: HashMap<String, Vec<f32>>,
+        strings: HashMap<String, Vec<String>>,
+    }
+
+    fn attributes(node: &[u8]) -> Attributes {
+        let mut attributes = Attributes::default();
+        for attribute in messages(node, 5) {
+            let name = string(attribute, 1);
+            let fields = decode(attribute);
+            let attribute_type = fields
+                .iter()
+                .find_map(|(field, value)| match value {
+                    Field::Varint(value) if *field == 20 => Some(*value as i64),
+                    _ => None,
+                })
+                .unwrap();
+            match attribute_type {
+                ATTRIBUTE_INT => {
+                    let value = fields.iter().find(|(field, _)| *field == 3).unwrap();
+                    let Field::Varint(value) = value.1 else { panic!() };
+                    attributes.ints.insert(name, vec![value as i64]);
+                }
+                ATTRIBUTE_INTS => {
+                    let values = fields
+                        .iter()
+                        .filter_map(|(field, value)| match value {
+                            Field::Varint(value) if *field == 8 => Some(*value as i64),
+                            _ => None,
+                        })
+                        .collect();
+                    attributes.ints.insert(name, values);
+                }
+                ATTRIBUTE_FLOATS => {
+                    let values = fields
+                        .iter()
+                        .filter_map(|(field, value)| match value {
+                            Field::Fixed32(bits) if *field == 7 => Some(f32::from_bits(*bits)),
+                            _ => None,
+                        })
+                        .collect();
+                    attributes.floats.insert(name, values);
+                }
+                ATTRIBUTE_STRING | ATTRIBUTE_STRINGS => {
+                    let values = messages(attribute, if attribute_type == ATTRIBUTE_STRING { 4 } else { 9 })
+                        .iter()
+                        .map(|value| String::from_utf8(value.to_vec()).unwrap())
+                        .collect();
+                    attributes.strings.insert(name, values);
+                }
+                _ => panic!("unexpected attribute type {}", attribute_type),
+            }
+        }
+        attributes
+    }
+
+    /// Evaluate an exported model with the semantics of the ONNX tree ensemble operators, returning the scores
+    /// before the post transform, the post transform and the name of the operator.
+    fn evaluate(onnx: &[u8], rows: &[&[f32]]) -> (Vec<Vec<f32>>, String, String) {
+        let graph = messages(onnx, 7)[0];
+        let node = messages(graph, 1)[0];
+        let op_type = string(node, 4);
+        let attributes = attributes(node);
+        let prefix = if op_type == "TreeEnsembleClassifier" { "class" } else { "target" };
+        let ints = |name: &str| &attributes.ints[name];
+        let (tree_ids, node_ids) = (ints("nodes_treeids"), ints("nodes_nodeids"));
+        let index: HashMap<(i64, i64), usize> = tree_ids
+            .iter()
+            .zip(node_ids)
+            .enumerate()
+            .map(|(i, (&tree, &node))| ((tree, node), i))
+            .collect();
+        let modes = &attributes.strings["nodes_modes"];
+        let base_values = &attributes.floats["base_values"];
+
+        let scores = rows
+            .iter()
+            .map(|row| {
+                let mut scores = base_values.clone();
+                let leaves = ints(&format!("{}_treeids", prefix))
+                    .iter()
+                    .zip(ints(&format!("{}_nodeids", prefix)));
+                let ids = ints(&format!("{}_ids", prefix));
+                let weights = &attributes.floats[&format!("{}_weights", prefix)];
+                for (i, (&tree, &leaf)) in leaves.enumerate() {
+                    // walk from the root to find the leaf reached by the row
+                    let mut node = index[&(tree, 0)];
+                    while modes[node] != "LEAF" {
+                        assert_eq!(modes[node], "BRANCH_LT");
+                        let value = row[ints("nodes_featureids")[node] as usize];
+                        let branch = if value.is_nan() {
+                            ints("nodes_missing_value_tracks_true")[node] == 1
+                        } else {
+                            value < attributes.floats["nodes_values"][node]
+                        };
+                        let next = if branch { "nodes_truenodeids" } else { "nodes_falsenodeids" };
+                        node = index[&(tree, ints(next)[node])];
+                    }
+                    if node_ids[node] == leaf {
+                        scores[ids[i] as usize] += weights[i];
+                    }
+                }
+                scores
+            })
+            .collect();
+        (scores, attributes.strings["post_transform"][0].clone(), op_type)
+    }
+
+    fn tree_model(objective: &str, num_class: usize, split_type: u8) -> Ensemble {
+        let tree = serde_json::json!({
+            "default_left": [1, 0, 0, 0, 0],
+            "left_children": [1, 3, -1, -1, -1],
+            "right_children": [2, 4, -1, -1, -1],
+            "split_indices": [2, 0, 0, 0, 0],
+            "split_conditions": [0.5, -1.25, 0.75, 0.1, -0.3],
+            "split_type": [split_type, 0, 0, 0, 0],
+            "categories": [1],
+            "categories_nodes": [0],
+            "categories_segments": [0],
+            "categories_sizes": [1],
+            "loss_changes": [10.0, 3.0, 0.0, 0.0, 0.0],
+            "sum_hessian": [8.0, 3.0, 5.0, 1.0, 2.0],
+            "tree_param": {"num_nodes": "5", "size_leaf_vector": "1"}
+        });
+        let num_trees = num_class.max(1) * 2;
+        let model = serde_json::json!({
+            "learner": {
+                "gradient_booster": {
+                    "model": {
+                        "gbtree_model_param": {"num_parallel_tree": "1", "num_trees": num_trees.to_string()},
+                        "tree_info": (0..num_trees).map(|i| i % num_class.max(1)).collect::<Vec<_>>(),
+                        "trees": vec![tree; num_trees]
+                    },
+                    "name": "gbtree"
+                },
+                "learner_model_param": {
+                    "base_score": "[6E-1]",
+                    "num_class": num_class.to_string(),
+                    "num_feature": "3",
+                    "num_target": "1"
+                },
+                "objective": {"name": objective}
+            },
+            "version": [3, 0, 0]
+        });
+        Ensemble::from_json(model.to_string().as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn export_tree_ensembles() {
+        let nan = f32::NAN;
+        let rows: &[&[f32]] = &[&[0.0, 0.0, 0.0], &[nan, nan, nan], &[-2.0, 2.0, 0.25], &[1.0, 4.0, 1.0]];
+        for (objective, num_class, op_type, post_transform) in [
+            ("binary:logistic", 0, "TreeEnsembleClassifier", "LOGISTIC"),
+            ("reg:logistic", 0, "TreeEnsembleRegressor", "LOGISTIC"),
+            ("reg:squarederror", 0, "TreeEnsembleRegressor", "NONE"),
+            ("count:poisson", 0, "TreeEnsembleRegressor", "NONE"),
+            ("multi:softprob", 3, "TreeEnsembleClassifier", "SOFTMAX"),
+        ] {
+            let ensemble = tree_model(objective, num_class, 0);
+            let onnx = export(&ensemble).unwrap();
+            let (scores, transform, op) = evaluate(&onnx, rows);
+            assert_eq!(
+                (op.as_str(), transform.as_str()),
+                (op_type, post_transform),
+                "{}",
+                objective
+            );
+            for (row, scores) in rows.iter().zip(scores) {
+                assert_eq!(scores, ensemble.predict_row_margin(row), "{}", objective);
+            }
+
+            let graph = messages(&onnx, 7)[0];
+            let outputs: Vec<String> = messages(graph, 12).iter().map(|output| string(output, 1)).collect();
+            let expected_outputs = if op_type == "TreeEnsembleClassifier" {
+                vec!["label", "probabilities"]
+            } else {
+                vec!["variable"]
+            };
+            assert_eq!(outputs, expected_outputs);
+            // exponential objectives have an extra Exp operator
+            let ops: Vec<String> = messages(graph, 1).iter().map(|node| string(node, 4)).collect();
+            assert_eq!(ops.len(), if objective == "count:poisson" { 2 } else { 1 });
+        }
+
+        assert!(export(&tree_model("binary:hinge", 0, 0)).is_err());
+        assert!(export(&tree_model("binary:logistic", 0, 1)).is_err());
+    }
+
+    #[test]
+    fn varints() {
+        let mut buffer = Vec::new();
+        for value in [0, 1, 127, 128, 300, u64::MAX] {
+            write_varint(&mut buffer, value);
+        }
+        let mut pos = 0;
+        for value in [0, 1, 127, 128, 300, u64::MAX] {
+            assert_eq!(read_varint(&buffer, &mut pos), value);
+        }
+        assert_eq!(pos, buffer.len());
+    }
+
+    /// Compare exports with models encoded independently by `testdata/onnx/make_fixtures.py`, ignoring the producer
+    /// version.
+    #[test]
+    fn export_matches_golden_models() {
+        fn without_version(fields: Vec<(u32, Field<'_>)>) -> Vec<(u32, Field<'_>)> {
+            fields.into_iter().filter(|(field, _)| *field != 3).collect()
+        }
+
+        for (ensemble, golden) in [
+            (
+                tree_model("reg:squarederror", 0, 0),
+                &include_bytes!("../testdata/onnx/regressor.onnx")[..],
+            ),
+            (
+                tree_model("multi:softprob", 2, 0),
+                &include_bytes!("../testdata/onnx/classifier.onnx")[..],
+            ),
+        ] {
+            let onnx = export(&ensemble).unwrap();
+            let fields = decode(&onnx);
+            assert!(fields.contains(&(3, Field::Bytes(env!("CARGO_PKG_VERSION").as_bytes()))));
+            assert_eq!(without_version(fields), without_version(decode(golden)));
+        }
+    }
+
+    #[cfg(feature = "libxgboost")]
+    #[test]
+    fn export_booster() {
+        use crate::parameters::{self, learning};
+        use crate::{Booster, DMatrix};
+
+        let path = "xgboost-sys/xgboost/demo/data/agaricus.txt";
+        let dtrain = DMatrix::load(format!(r#"{{"uri": "{}.train?format=libsvm"}}"#, path)).unwrap();
+        let dtest = DMatrix::load(format!(r#"{{"uri": "{}.test?format=libsvm"}}"#, path)).unwrap();
+        let learning_params = learning::LearningTaskParametersBuilder::default()
+            .objective(learning::Objective::BinaryLogistic)
+            .build()
+            .unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .learning_params(learning_params)
+            .verbose(false)
+            .build()
+            .unwrap();
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .dtrain(&dtrain)
+            .booster_params(booster_params)
+            .boost_rounds(5)
+            .build()
+            .unwrap();
+        let booster = Booster::train(&training_params).unwrap();
+
+        let num_features = booster.num_features().unwrap();
+        let mut data = Vec::new();
+        for line in std::fs::read_to_string(format!("{}.test", path)).unwrap().lines() {
+            let mut row = vec![f32::NAN; num_features];
+            for entry in line.split_whitespace().skip(1) {
+                let (index, value) = entry.split_once(':').unwrap();
+                row[index.parse::<usize>().unwrap()] = value.parse().unwrap();
+            }
+            data.push(row);
+        }
+        let rows: Vec<&[f32]> = data.iter().map(Vec::as_slice).collect();
+
+        let (scores, transform, op_type) = evaluate(&booster.to_onnx().unwrap(), &rows);
+        assert_eq!(
+            (op_type.as_str(), transform.as_str()),
+            ("TreeEnsembleClassifier", "LOGISTIC")
+        );
+        let margins = booster.predict_margin(&dtest).unwrap();
+        let predictions = booster.predict(&dtest).unwrap();
+        for ((scores, margin), prediction) in scores.iter().zip(margins).zip(predictions) {
+            assert!((scores[0] - margin).abs() <= 1e-5 * margin.abs().max(1.0));
+            let probability = 1.0 / (1.0 + (-scores[0]).exp());
+            assert!((probability - prediction).abs() <= 1e-6);
+        }
+    }
+}
@@ -59,4 +59,23 @@ impl BoosterType {
             BoosterType::Dart(ref p) => p.as_string_pairs(),
         }
     }
+
+    /// Whether training on features marked as categorical is allowed, which is only the case for tree and dart
+    /// boosters with `enable_categorical` set.
+    pub(crate) fn enable_categorical(&self) -> bool {
+        match *self {
+            BoosterType::Tree(ref p) => p.enable_categorical(),
+            BoosterType::Dart(ref p) => p.enable_categorical(),
+            BoosterType::Linear(_) => false,
+        }
+    }
+
+    /// Allow or disallow training on categorical features, linear boosters never support them.
+    pub(crate) fn set_enable_categorical(&mut self, enable_categorical: bool) {
+        match *self {
+            BoosterType::Tree(ref mut p) => p.set_enable_categorical(enable_categorical),
+            BoosterType::Dart(ref mut p) => p.set_enable_categorical(enable_categorical),
+            BoosterType::Linear(_) => (),
+        }
+    }
 }
@@ -3,7 +3,8 @@
 
 use std::default::Default;
 
-use super::Interval;
+use super::{config_flag, config_option, config_param, Interval};
+use crate::XGBResult;
 
 /// Type of sampling algorithm.
 #[derive(Clone, Default)]
@@ -26,6 +27,17 @@ impl std::fmt::Display for SampleType {
     }
 }
 
+impl SampleType {
+    /// Parse a sampling algorithm from its name in XGBoost's configuration.
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "uniform" => Some(SampleType::Uniform),
+            "weighted" => Some(SampleType::Weighted),
+            _ => None,
+        }
+    }
+}
+
 /// Type of normalization algorithm.
 #[derive(Clone, Default)]
 pub enum NormalizeType {
@@ -52,6 +64,17 @@ impl std::fmt::Display for NormalizeType {
     }
 }
 
+impl NormalizeType {
+    /// Parse a normalization algorithm from its name in XGBoost's configuration.
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "tree" => Some(NormalizeType::Tree),
+            "forest" => Some(NormalizeType::Forest),
+            _ => None,
+        }
+    }
+}
+
 /// Additional parameters for Dart Booster.
 #[derive(Builder, Clone)]
 #[builder(build_fn(validate = "Self::validate"))]
@@ -76,6 +99,13 @@ pub struct DartBoosterParameters {
     /// Note that non-zero skip_drop has higher priority than rate_drop or one_drop.
     /// * range: [0.0, 1.0]
     skip_drop: f32,
+
+    /// Whether to allow training on features marked as categorical with
+    /// [`DMatrix::set_feature_types`](../../struct.DMatrix.html#method.set_feature_types), in the same way as
+    /// [`TreeBoosterParameters`](../tree/struct.TreeBoosterParametersBuilder.html#method.enable_categorical).
+    ///
+    /// * default: false
+    enable_categorical: bool,
 }
 
 impl Default for DartBoosterParameters {
@@ -86,11 +116,36 @@ impl Default for DartBoosterParameters {
             rate_drop: 0.0,
             one_drop: false,
             skip_drop: 0.0,
+            enable_categorical: false,
         }
     }
 }
 
 impl DartBoosterParameters {
+    /// Read parameters from the gradient booster section of a model's JSON configuration.
+    pub(crate) fn from_config(booster: &serde_json::Value) -> XGBResult<Self> {
+        let dart = &booster["dart_train_param"];
+        let default = DartBoosterParameters::default();
+        Ok(DartBoosterParameters {
+            sample_type: config_option(dart, "sample_type", SampleType::from_name)?.unwrap_or(default.sample_type),
+            normalize_type: config_option(dart, "normalize_type", NormalizeType::from_name)?
+                .unwrap_or(default.normalize_type),
+            rate_drop: config_param(dart, "rate_drop")?.unwrap_or(default.rate_drop),
+            one_drop: config_flag(dart, "one_drop")?.unwrap_or(default.one_drop),
+            skip_drop: config_param(dart, "skip_drop")?.unwrap_or(default.skip_drop),
+            enable_categorical: default.enable_categorical,
+        })
+    }
+
+    /// Whether training on categorical features is allowed.
+    pub(crate) fn enable_categorical(&self) -> bool {
+        self.enable_categorical
+    }
+
+    pub(crate) fn set_enable_categorical(&mut self, enable_categorical: bool) {
+        self.enable_categorical = enable_categorical;
+    }
+
     pub(crate) fn as_string_pairs(&self) -> Vec<(String, String)> {
         vec![
             ("booster".to_owned(), "dart".to_owned()),
@@ -4,7 +4,8 @@
 use std;
 use std::default::Default;
 
-use super::Interval;
+use super::{config_param, Interval};
+use crate::{XGBError, XGBResult};
 
 /// Learning objective used when training a booster model.
 #[derive(Default)]
@@ -101,6 +102,32 @@ impl std::fmt::Display for Objective {
     }
 }
 
+impl Objective {
+    /// Parse an objective from its name in XGBoost's configuration, along with the parameters stored separately.
+    fn from_config(name: &str, num_class: u32, tweedie_variance_power: Option<f32>) -> Option<Self> {
+        let objective = match name {
+            // older name of squared error regression
+            "reg:squarederror" | "reg:linear" => Objective::RegLinear,
+            "reg:logistic" => Objective::RegLogistic,
+            "binary:logistic" => Objective::BinaryLogistic,
+            "binary:logitraw" => Objective::BinaryLogisticRaw,
+            "gpu:reg:squarederror" => Objective::GpuRegLinear,
+            "gpu:reg:logistic" => Objective::GpuRegLogistic,
+            "gpu:binary:logistic" => Objective::GpuBinaryLogistic,
+            "gpu:binary:logitraw" => Objective::GpuBinaryLogisticRaw,
+            "count:poisson" => Objective::CountPoisson,
+            "survival:cox" => Objective::SurvivalCox,
+            "multi:softmax" => Objective::MultiSoftmax(num_class),
+            "multi:softprob" => Objective::MultiSoftprob(num_class),
+            "rank:pairwise" => Objective::RankPairwise,
+            "reg:gamma" => Objective::RegGamma,
+            "reg:tweedie" => Objective::RegTweedie(tweedie_variance_power),
+            _ => return None,
+        };
+        Some(objective)
+    }
+}
+
 /// Type of evaluation metrics to use during learning.
 #[derive(Clone)]
 pub enum Metrics {
@@ -214,6 +241,62 @@ impl std::fmt::Display for EvaluationMetric {
     }
 }
 
+impl EvaluationMetric {
+    /// Parse a metric from its name as reported by XGBoost, the inverse of its `Display` implementation.
+    fn from_name(name: &str) -> Option<Self> {
+        let metric = match name {
+            "rmse" => EvaluationMetric::RMSE,
+            "mae" => EvaluationMetric::MAE,
+            "logloss" => EvaluationMetric::LogLoss,
+            "error" => EvaluationMetric::BinaryErrorRate(0.5),
+            "merror" => EvaluationMetric::MultiClassErrorRate,
+            "mlogloss" => EvaluationMetric::MultiClassLogLoss,
+            "auc" => EvaluationMetric::AUC,
+            "ndcg" => EvaluationMetric::NDCG,
+            "ndcg-" => EvaluationMetric::NDCGNegative,
+            "map" => EvaluationMetric::MAP,
+            "map-" => EvaluationMetric::MAPNegative,
+            "poisson-nloglik" => EvaluationMetric::PoissonLogLoss,
+            "gamma-nloglik" => EvaluationMetric::GammaLogLoss,
+            "cox-nloglik" => EvaluationMetric::CoxLogLoss,
+            "gamma-deviance" => EvaluationMetric::GammaDeviance,
+            _ => {
+                let (prefix, param) = name.split_once('@')?;
+                let (param, negative) = match param.strip_suffix('-') {
+                    Some(param) => (param, true),
+                    None => (param, false),
+                };
+                match (prefix, negative) {
+                    ("error", false) => EvaluationMetric::BinaryErrorRate(param.parse().ok()?),
+                    ("ndcg", false) => EvaluationMetric::NDCGCut(param.parse().ok()?),
+                    ("ndcg", true) => EvaluationMetric::NDCGCutNegative(param.parse().ok()?),
+                    ("map", false) => EvaluationMetric::MAPCut(param.parse().ok()?),
+                    ("map", true) => EvaluationMetric::MAPCutNegative(param.parse().ok()?),
+                    // reported along with the variance power, which is a parameter of the objective
+                    ("tweedie-nloglik", false) => EvaluationMetric::TweedieLogLoss,
+                    _ => return None,
+                }
+            }
+        };
+        Some(metric)
+    }
+
+    /// Whether higher values of this metric indicate a better model (e.g. AUC, NDCG, MAP), as opposed to lower
+    /// values (e.g. RMSE, log loss, error rates).
+    pub fn maximize(&self) -> bool {
+        maximize_metric(&self.to_string())
+    }
+}
+
+/// Whether higher values are better for the metric with given name, as reported by XGBoost during evaluation.
+pub(crate) fn maximize_metric(name: &str) -> bool {
+    // "map" has to be matched exactly or with a cut off, to tell it apart from "mape"
+    ["auc", "ndcg", "pre", "map@", "map-"]
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+        || name == "map"
+}
+
 /// BoosterParameters that configure the learning objective.
 ///
 /// See [`LearningTaskParametersBuilder`](struct.LearningTaskParametersBuilder.html), for details
@@ -287,6 +370,61 @@ impl LearningTaskParameters {
         self.seed = seed;
     }
 
+    /// Read parameters from the learner section of a model's JSON configuration.
+    pub(crate) fn from_config(learner: &serde_json::Value) -> XGBResult<Self> {
+        let default = LearningTaskParameters::default();
+        let model_param = &learner["learner_model_param"];
+
+        let objective = match learner["learner_train_param"]["objective"].as_str() {
+            Some(name) => {
+                let num_class = config_param(model_param, "num_class")?.unwrap_or(0);
+                let tweedie_variance_power = config_param(&learner["objective"], "tweedie_variance_power")?;
+                Objective::from_config(name, num_class, tweedie_variance_power)
+                    .ok_or_else(|| XGBError::new(format!("Unsupported objective in model configuration: {}", name)))?
+            }
+            None => default.objective,
+        };
+
+        // stored as a list with one value per target by recent versions of XGBoost
+        let base_score = match config_param::<String>(model_param, "base_score")? {
+            Some(base_score) => {
+                let first = base_score.trim_matches(|c| c == '[' || c == ']').split(',').next();
+                first.and_then(|score| score.trim().parse().ok()).ok_or_else(|| {
+                    XGBError::new(format!("Invalid base score in model configuration: {}", base_score))
+                })?
+            }
+            None => default.base_score,
+        };
+
+        let metric_names: Vec<&str> = learner["metrics"]
+            .as_array()
+            .map(|metrics| metrics.iter().filter_map(|metric| metric["name"].as_str()).collect())
+            .unwrap_or_default();
+        let eval_metrics = if metric_names.is_empty() {
+            Metrics::Auto
+        } else {
+            let metrics = metric_names
+                .iter()
+                .map(|name| {
+                    EvaluationMetric::from_name(name).ok_or_else(|| {
+                        XGBError::new(format!(
+                            "Unsupported evaluation metric in model configuration: {}",
+                            name
+                        ))
+                    })
+                })
+                .collect::<XGBResult<Vec<_>>>()?;
+            Metrics::Custom(metrics)
+        };
+
+        Ok(LearningTaskParameters {
+            objective,
+            base_score,
+            eval_metrics,
+            seed: config_param(&learner["generic_param"], "seed")?.unwrap_or(default.seed),
+        })
+    }
+
     pub(crate) fn as_string_pairs(&self) -> Vec<(String, String)> {
         let mut v = Vec::new();
 
@@ -320,3 +458,23 @@ impl LearningTaskParametersBuilder {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maximize_metrics() {
+        for name in [
+            "auc", "aucpr", "ndcg", "ndcg@5", "map", "map@3", "map-", "map@3-", "pre@2",
+        ] {
+            assert!(maximize_metric(name), "{}", name);
+        }
+        for name in ["rmse", "mae", "mape", "logloss", "error", "merror"] {
+            assert!(!maximize_metric(name), "{}", name);
+        }
+        assert!(EvaluationMetric::MAPCut(3).maximize());
+        assert!(EvaluationMetric::MAPNegative.maximize());
+        assert!(!EvaluationMetric::RMSE.maximize());
+    }
+}
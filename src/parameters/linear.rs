@@ -2,6 +2,9 @@
 
 use std::default::Default;
 
+use super::{config_option, config_param};
+use crate::XGBResult;
+
 /// Linear model algorithm.
 #[derive(Clone, Default)]
 pub enum LinearUpdate {
@@ -46,7 +49,29 @@ pub struct LinearBoosterParameters {
     updater: LinearUpdate,
 }
 
+impl LinearUpdate {
+    /// Parse a linear model algorithm from its name in XGBoost's configuration.
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "shotgun" => Some(LinearUpdate::Shotgun),
+            "coord_descent" => Some(LinearUpdate::CoordDescent),
+            _ => None,
+        }
+    }
+}
+
 impl LinearBoosterParameters {
+    /// Read parameters from the gradient booster section of a model's JSON configuration.
+    pub(crate) fn from_config(booster: &serde_json::Value) -> XGBResult<Self> {
+        let default = LinearBoosterParameters::default();
+        Ok(LinearBoosterParameters {
+            lambda: config_param(booster, "lambda")?.unwrap_or(default.lambda),
+            alpha: config_param(booster, "alpha")?.unwrap_or(default.alpha),
+            updater: config_option(&booster["gblinear_train_param"], "updater", LinearUpdate::from_name)?
+                .unwrap_or(default.updater),
+        })
+    }
+
     pub(crate) fn as_string_pairs(&self) -> Vec<(String, String)> {
         vec![
             ("booster".to_owned(), "gblinear".to_owned()),
@@ -7,6 +7,7 @@
 //! Parameters are generally created through builders that provide sensible defaults, and ensure that
 //! any given settings are valid when built.
 use std::fmt::{self, Display};
+use std::str::FromStr;
 
 mod booster;
 pub mod dart;
@@ -15,8 +16,9 @@ pub mod linear;
 pub mod tree;
 
 pub use self::booster::BoosterType;
-use super::booster::CustomObjective;
-use super::DMatrix;
+use super::booster::{CustomEvaluation, CustomObjective};
+use super::callback::TrainingCallback;
+use super::{DMatrix, XGBError, XGBResult};
 
 /// Parameters for training boosters.
 /// Created using [`BoosterParametersBuilder`](struct.BoosterParametersBuilder.html).
@@ -87,6 +89,53 @@ impl BoosterParameters {
         self.threads = threads.into();
     }
 
+    /// Rebuild parameters from a model's JSON configuration, as returned by
+    /// [`Booster::save_config`](../struct.Booster.html#method.save_config).
+    ///
+    /// The configuration holds the values XGBoost resolved for every parameter, so this reports e.g. the objective
+    /// and tree method a loaded model was trained with. Parameters which aren't part of the configuration (like
+    /// `verbose` or `enable_categorical`) keep their default values.
+    ///
+    /// In particular the parameters of a model trained on categorical features don't allow training on them again,
+    /// as the configuration doesn't include feature types or splits. Use
+    /// [`Booster::params`](../struct.Booster.html#method.params) to read the parameters of such a model.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use xgb::{parameters::BoosterParameters, Booster};
+    ///
+    /// let booster = Booster::load("xgb.model").unwrap();
+    /// let params = BoosterParameters::from_config(&booster.save_config().unwrap()).unwrap();
+    /// println!("objective: {}", params.learning_params().objective());
+    /// ```
+    pub fn from_config(config: &str) -> XGBResult<Self> {
+        let config: serde_json::Value = serde_json::from_str(config)
+            .map_err(|err| XGBError::new(format!("Invalid model configuration: {}", err)))?;
+        let learner = &config["learner"];
+        let booster_type = match learner["learner_train_param"]["booster"].as_str() {
+            Some("gbtree") | None => BoosterType::Tree(tree::TreeBoosterParameters::from_config(learner)?),
+            Some("gblinear") => BoosterType::Linear(linear::LinearBoosterParameters::from_config(
+                &learner["gradient_booster"],
+            )?),
+            Some("dart") => BoosterType::Dart(dart::DartBoosterParameters::from_config(&learner["gradient_booster"])?),
+            Some(name) => {
+                return Err(XGBError::new(format!(
+                    "Unsupported booster in model configuration: {}",
+                    name
+                )))
+            }
+        };
+
+        Ok(BoosterParameters {
+            booster_type,
+            learning_params: learning::LearningTaskParameters::from_config(learner)?,
+            verbose: false,
+            // 0 lets XGBoost pick the number of threads
+            threads: config_param(&learner["generic_param"], "nthread")?.filter(|&nthread| nthread > 0),
+        })
+    }
+
     pub(crate) fn as_string_pairs(&self) -> Vec<(String, String)> {
         let mut v = Vec::new();
 
@@ -103,8 +152,6 @@ impl BoosterParameters {
     }
 }
 
-type CustomEvaluation = fn(&[f32], &DMatrix) -> f32;
-
 /// Parameters used by the [`Booster::train`](../struct.Booster.html#method.train) method for training new models.
 /// Created using [`TrainingParametersBuilder`](struct.TrainingParametersBuilder.html).
 #[derive(Builder, Clone)]
@@ -133,18 +180,45 @@ pub struct TrainingParameters<'a> {
     /// *default*: `None`
     pub(crate) evaluation_sets: Option<&'a [(&'a DMatrix, &'a str)]>,
 
-    /// Optional custom objective function to use for training.
+    /// Optional custom objective function to use for training, called once per boosting round.
+    ///
+    /// Any function or closure of the form `Fn(&[f32], &DMatrix) -> (Vec<f32>, Vec<f32>)` can be used, see
+    /// [`CustomObjective`](../trait.CustomObjective.html).
     ///
     /// *default*: `None`
     #[builder(default = "None")]
-    pub(crate) custom_objective_fn: Option<CustomObjective>,
+    pub(crate) custom_objective_fn: Option<&'a dyn CustomObjective>,
 
-    /// Optional custom evaluation function to use during training.
+    /// Optional custom evaluation function to use during training, called for each evaluation set once per boosting
+    /// round.
+    ///
+    /// Any function or closure of the form `Fn(&[f32], &DMatrix) -> f32` can be used, see
+    /// [`CustomEvaluation`](../trait.CustomEvaluation.html).
     ///
     /// *default*: `None`
     #[builder(default = "None")]
-    pub(crate) custom_evaluation_fn: Option<CustomEvaluation>,
-    // TODO: callbacks
+    pub(crate) custom_evaluation_fn: Option<&'a dyn CustomEvaluation>,
+
+    /// Where evaluation results of each round are written to during training.
+    ///
+    /// *default*: [`EvaluationOutput::Stdout`](enum.EvaluationOutput.html#variant.Stdout)
+    #[builder(default = "EvaluationOutput::default()")]
+    pub(crate) evaluation_output: EvaluationOutput,
+
+    /// Optional early stopping configuration, which stops training once the watched evaluation metric hasn't
+    /// improved for a given number of rounds. Requires at least one evaluation set.
+    ///
+    /// *default*: `None`
+    #[builder(default = "None")]
+    pub(crate) early_stopping: Option<EarlyStoppingParameters>,
+
+    /// Callbacks invoked before training, before and after each boosting round, and after training.
+    ///
+    /// See the [`callback`](../callback/index.html) module for built-in callbacks.
+    ///
+    /// *default*: `&[]`
+    #[builder(default = "&[]")]
+    pub(crate) callbacks: &'a [&'a dyn TrainingCallback],
 }
 
 impl<'a> TrainingParameters<'a> {
@@ -180,21 +254,407 @@ impl<'a> TrainingParameters<'a> {
         self.evaluation_sets = evaluation_sets;
     }
 
-    pub fn custom_objective_fn(&self) -> Option<CustomObjective> {
+    pub fn custom_objective_fn(&self) -> Option<&'a dyn CustomObjective> {
+        self.custom_objective_fn
+    }
+
+    pub fn set_custom_objective_fn(&mut self, custom_objective_fn: Option<&'a dyn CustomObjective>) {
+        self.custom_objective_fn = custom_objective_fn;
+    }
+
+    pub fn custom_evaluation_fn(&self) -> Option<&'a dyn CustomEvaluation> {
+        self.custom_evaluation_fn
+    }
+
+    pub fn set_custom_evaluation_fn(&mut self, custom_evaluation_fn: Option<&'a dyn CustomEvaluation>) {
+        self.custom_evaluation_fn = custom_evaluation_fn;
+    }
+
+    pub fn evaluation_output(&self) -> EvaluationOutput {
+        self.evaluation_output
+    }
+
+    pub fn set_evaluation_output(&mut self, evaluation_output: EvaluationOutput) {
+        self.evaluation_output = evaluation_output;
+    }
+
+    pub fn early_stopping(&self) -> Option<&EarlyStoppingParameters> {
+        self.early_stopping.as_ref()
+    }
+
+    pub fn set_early_stopping(&mut self, early_stopping: Option<EarlyStoppingParameters>) {
+        self.early_stopping = early_stopping;
+    }
+
+    pub fn callbacks(&self) -> &'a [&'a dyn TrainingCallback] {
+        self.callbacks
+    }
+
+    pub fn set_callbacks(&mut self, callbacks: &'a [&'a dyn TrainingCallback]) {
+        self.callbacks = callbacks;
+    }
+}
+
+/// Where [`Booster::train`](../struct.Booster.html#method.train) writes the evaluation results of each round.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum EvaluationOutput {
+    /// Print results to stdout.
+    #[default]
+    Stdout,
+
+    /// Write results through the [`log`](https://docs.rs/log) crate, at info level.
+    Log,
+
+    /// Don't output results.
+    Silent,
+}
+
+/// Parameters for k-fold cross-validation with [`cv`](../fn.cv.html).
+/// Created using [`CrossValidationParametersBuilder`](struct.CrossValidationParametersBuilder.html).
+///
+/// # Example
+///
+/// ```
+/// use xgb::parameters::{CrossValidationParametersBuilder, FoldType};
+/// use xgb::DMatrix;
+///
+/// let mut dtrain = DMatrix::from_dense(&[1.0, 0.0, 1.0, 1.0, 0.0, 0.0], 3).unwrap();
+/// dtrain.set_labels(&[1.0, 0.0, 1.0]).unwrap();
+///
+/// let cv_params = CrossValidationParametersBuilder::default()
+///     .dtrain(&dtrain)
+///     .nfold(3)
+///     .fold_type(FoldType::Stratified)
+///     .boost_rounds(20)
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Builder, Clone)]
+#[builder(build_fn(validate = "Self::validate"))]
+pub struct CrossValidationParameters<'a> {
+    /// Matrix to split into folds.
+    pub(crate) dtrain: &'a DMatrix,
+
+    /// Number of folds, must be at least 2.
+    ///
+    /// *default*: 3
+    #[builder(default = "3")]
+    pub(crate) nfold: u32,
+
+    /// How rows are assigned to folds.
+    ///
+    /// *default*: [`Random`](enum.FoldType.html#variant.Random)
+    #[builder(default = "FoldType::default()")]
+    pub(crate) fold_type: FoldType,
+
+    /// Whether to shuffle rows (or groups) before assigning them to folds.
+    ///
+    /// *default*: `true`
+    #[builder(default = "true")]
+    pub(crate) shuffle: bool,
+
+    /// Seed used when shuffling rows before assigning them to folds.
+    ///
+    /// *default*: 0
+    #[builder(default = "0")]
+    pub(crate) seed: u64,
+
+    /// Number of boosting rounds to train each fold's model for.
+    ///
+    /// *default*: 10
+    #[builder(default = "10")]
+    pub(crate) boost_rounds: u32,
+
+    /// Configuration for the models trained on each fold.
+    ///
+    /// *default*: [`BoosterParameters::default()`](struct.BoosterParameters.html)
+    #[builder(default = "BoosterParameters::default()")]
+    pub(crate) booster_params: BoosterParameters,
+
+    /// Optional custom objective function to use for training, as an alternative to the learning objective set in
+    /// the booster parameters.
+    ///
+    /// *default*: `None`
+    #[builder(default = "None")]
+    pub(crate) custom_objective_fn: Option<&'a dyn CustomObjective>,
+
+    /// Optional custom evaluation function, evaluated on the train and test matrices of each fold in addition to
+    /// the booster's metrics.
+    ///
+    /// *default*: `None`
+    #[builder(default = "None")]
+    pub(crate) custom_evaluation_fn: Option<&'a dyn CustomEvaluation>,
+
+    /// Where the mean and standard deviation of each metric are written after each round.
+    ///
+    /// *default*: [`Stdout`](enum.EvaluationOutput.html#variant.Stdout)
+    #[builder(default = "EvaluationOutput::default()")]
+    pub(crate) evaluation_output: EvaluationOutput,
+
+    /// Optional early stopping, based on the mean score of a metric across folds. The watched evaluation set
+    /// defaults to `"test"`.
+    ///
+    /// *default*: `None`
+    #[builder(default = "None")]
+    pub(crate) early_stopping: Option<EarlyStoppingParameters>,
+}
+
+impl<'a> CrossValidationParameters<'a> {
+    pub fn dtrain(&self) -> &'a DMatrix {
+        self.dtrain
+    }
+
+    pub fn set_dtrain(&mut self, dtrain: &'a DMatrix) {
+        self.dtrain = dtrain;
+    }
+
+    pub fn nfold(&self) -> u32 {
+        self.nfold
+    }
+
+    pub fn set_nfold(&mut self, nfold: u32) {
+        self.nfold = nfold;
+    }
+
+    pub fn fold_type(&self) -> FoldType {
+        self.fold_type
+    }
+
+    pub fn set_fold_type(&mut self, fold_type: FoldType) {
+        self.fold_type = fold_type;
+    }
+
+    pub fn shuffle(&self) -> bool {
+        self.shuffle
+    }
+
+    pub fn set_shuffle(&mut self, shuffle: bool) {
+        self.shuffle = shuffle;
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+    }
+
+    pub fn boost_rounds(&self) -> u32 {
+        self.boost_rounds
+    }
+
+    pub fn set_boost_rounds(&mut self, boost_rounds: u32) {
+        self.boost_rounds = boost_rounds;
+    }
+
+    pub fn booster_params(&self) -> &BoosterParameters {
+        &self.booster_params
+    }
+
+    pub fn set_booster_params<T: Into<BoosterParameters>>(&mut self, booster_params: T) {
+        self.booster_params = booster_params.into();
+    }
+
+    pub fn custom_objective_fn(&self) -> Option<&'a dyn CustomObjective> {
         self.custom_objective_fn
     }
 
-    pub fn set_custom_objective_fn(&mut self, custom_objective_fn: Option<CustomObjective>) {
+    pub fn set_custom_objective_fn(&mut self, custom_objective_fn: Option<&'a dyn CustomObjective>) {
         self.custom_objective_fn = custom_objective_fn;
     }
 
-    pub fn custom_evaluation_fn(&self) -> Option<CustomEvaluation> {
+    pub fn custom_evaluation_fn(&self) -> Option<&'a dyn CustomEvaluation> {
         self.custom_evaluation_fn
     }
 
-    pub fn set_custom_evaluation_fn(&mut self, custom_evaluation_fn: Option<CustomEvaluation>) {
+    pub fn set_custom_evaluation_fn(&mut self, custom_evaluation_fn: Option<&'a dyn CustomEvaluation>) {
         self.custom_evaluation_fn = custom_evaluation_fn;
     }
+
+    pub fn evaluation_output(&self) -> EvaluationOutput {
+        self.evaluation_output
+    }
+
+    pub fn set_evaluation_output(&mut self, evaluation_output: EvaluationOutput) {
+        self.evaluation_output = evaluation_output;
+    }
+
+    pub fn early_stopping(&self) -> Option<&EarlyStoppingParameters> {
+        self.early_stopping.as_ref()
+    }
+
+    pub fn set_early_stopping(&mut self, early_stopping: Option<EarlyStoppingParameters>) {
+        self.early_stopping = early_stopping;
+    }
+}
+
+impl CrossValidationParametersBuilder<'_> {
+    fn validate(&self) -> Result<(), String> {
+        if let Some(nfold) = self.nfold {
+            if nfold < 2 {
+                return Err(format!(
+                    "Invalid value for 'nfold' parameter, must be at least 2 (got {}).",
+                    nfold
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// How rows are assigned to folds during cross-validation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FoldType {
+    /// Assign rows to folds at random (or in order, if not shuffling).
+    #[default]
+    Random,
+
+    /// Assign rows to folds so each fold has roughly the same proportion of each label value, for
+    /// classification tasks.
+    Stratified,
+
+    /// Keep the rows of each query group (as set by
+    /// [`DMatrix::set_group`](../struct.DMatrix.html#method.set_group)) together in the same fold, for ranking
+    /// tasks.
+    Grouped,
+}
+
+/// Parameters controlling early stopping in [`Booster::train`](../struct.Booster.html#method.train).
+/// Created using [`EarlyStoppingParametersBuilder`](struct.EarlyStoppingParametersBuilder.html).
+///
+/// Once training has finished, the best iteration and its score are stored as the `best_iteration` and
+/// `best_score` attributes of the trained [`Booster`](../struct.Booster.html).
+///
+/// # Example
+///
+/// ```
+/// use xgb::parameters::EarlyStoppingParametersBuilder;
+///
+/// let early_stopping = EarlyStoppingParametersBuilder::default()
+///     .rounds(10)
+///     .data_name(Some("test".to_owned()))
+///     .metric_name(Some("logloss".to_owned()))
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Builder, Clone, Debug)]
+#[builder(build_fn(validate = "Self::validate"))]
+pub struct EarlyStoppingParameters {
+    /// Number of rounds without improvement of the watched metric after which training is stopped.
+    rounds: u32,
+
+    /// Name of the evaluation set to watch, as given in
+    /// [`TrainingParameters::evaluation_sets`](struct.TrainingParameters.html#method.evaluation_sets).
+    ///
+    /// *default*: `None` (the last evaluation set is watched)
+    #[builder(default = "None")]
+    data_name: Option<String>,
+
+    /// Name of the evaluation metric to watch, as reported by XGBoost (e.g. `"logloss"` or `"auc"`), or `"custom"`
+    /// for the custom evaluation function.
+    ///
+    /// *default*: `None` (the last metric evaluated on the watched set is used)
+    #[builder(default = "None")]
+    metric_name: Option<String>,
+
+    /// Whether higher values of the watched metric are better.
+    ///
+    /// *default*: `None` (inferred from the metric, see
+    /// [`EvaluationMetric::maximize`](learning/enum.EvaluationMetric.html#method.maximize))
+    #[builder(default = "None")]
+    maximize: Option<bool>,
+}
+
+impl EarlyStoppingParameters {
+    pub fn rounds(&self) -> u32 {
+        self.rounds
+    }
+
+    pub fn set_rounds(&mut self, rounds: u32) {
+        self.rounds = rounds;
+    }
+
+    pub fn data_name(&self) -> Option<&str> {
+        self.data_name.as_deref()
+    }
+
+    pub fn set_data_name(&mut self, data_name: Option<String>) {
+        self.data_name = data_name;
+    }
+
+    pub fn metric_name(&self) -> Option<&str> {
+        self.metric_name.as_deref()
+    }
+
+    pub fn set_metric_name(&mut self, metric_name: Option<String>) {
+        self.metric_name = metric_name;
+    }
+
+    pub fn maximize(&self) -> Option<bool> {
+        self.maximize
+    }
+
+    pub fn set_maximize(&mut self, maximize: Option<bool>) {
+        self.maximize = maximize;
+    }
+}
+
+impl EarlyStoppingParametersBuilder {
+    fn validate(&self) -> Result<(), String> {
+        if self.rounds == Some(0) {
+            return Err("Invalid value for 'rounds' parameter, must be at least 1.".to_owned());
+        }
+        Ok(())
+    }
+}
+
+/// Find the first value stored under given key in a model's JSON configuration, searching nested objects.
+pub(crate) fn find_config<'a>(config: &'a serde_json::Value, key: &str) -> Option<&'a serde_json::Value> {
+    match config {
+        serde_json::Value::Object(map) => map
+            .get(key)
+            .or_else(|| map.values().find_map(|value| find_config(value, key))),
+        serde_json::Value::Array(values) => values.iter().find_map(|value| find_config(value, key)),
+        _ => None,
+    }
+}
+
+/// Parse a parameter from a model's JSON configuration, where XGBoost stores all values as strings.
+///
+/// Returns `None` if the parameter isn't part of the configuration.
+pub(crate) fn config_param<T: FromStr>(config: &serde_json::Value, key: &str) -> XGBResult<Option<T>> {
+    match find_config(config, key) {
+        None => Ok(None),
+        Some(value) => value.as_str().and_then(|s| s.parse().ok()).map(Some).ok_or_else(|| {
+            XGBError::new(format!(
+                "Invalid value for '{}' parameter in model configuration: {}",
+                key, value
+            ))
+        }),
+    }
+}
+
+/// Parse a flag from a model's JSON configuration, stored as `"0"` or `"1"`.
+pub(crate) fn config_flag(config: &serde_json::Value, key: &str) -> XGBResult<Option<bool>> {
+    Ok(config_param::<u8>(config, key)?.map(|flag| flag != 0))
+}
+
+/// Parse a named option from a model's JSON configuration, returning an error for unknown names.
+pub(crate) fn config_option<T>(
+    config: &serde_json::Value,
+    key: &str,
+    parse: impl Fn(&str) -> Option<T>,
+) -> XGBResult<Option<T>> {
+    match config_param::<String>(config, key)? {
+        None => Ok(None),
+        Some(name) => parse(&name).map(Some).ok_or_else(|| {
+            XGBError::new(format!(
+                "Unsupported value for '{}' parameter in model configuration: {}",
+                key, name
+            ))
+        }),
+    }
 }
 
 enum Inclusion {
@@ -3,7 +3,8 @@
 //!
 use std::default::Default;
 
-use super::Interval;
+use super::{config_flag, config_option, config_param, find_config, Interval};
+use crate::XGBResult;
 
 /// The tree construction algorithm used in XGBoost (see description in the
 /// [reference paper](http://arxiv.org/abs/1603.02754)).
@@ -51,6 +52,21 @@ impl std::fmt::Display for TreeMethod {
     }
 }
 
+impl TreeMethod {
+    /// Parse a tree method from its name in XGBoost's configuration.
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "auto" => Some(TreeMethod::Auto),
+            "exact" => Some(TreeMethod::Exact),
+            "approx" => Some(TreeMethod::Approx),
+            "hist" => Some(TreeMethod::Hist),
+            "gpu_exact" => Some(TreeMethod::GpuExact),
+            "gpu_hist" => Some(TreeMethod::GpuHist),
+            _ => None,
+        }
+    }
+}
+
 impl From<String> for TreeMethod {
     fn from(s: String) -> Self {
         use std::borrow::Borrow;
@@ -118,6 +134,23 @@ impl std::fmt::Display for TreeUpdater {
     }
 }
 
+impl TreeUpdater {
+    /// Parse an updater from its name in XGBoost's configuration.
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "grow_colmaker" => Some(TreeUpdater::GrowColMaker),
+            "distcol" => Some(TreeUpdater::DistCol),
+            "grow_histmaker" => Some(TreeUpdater::GrowHistMaker),
+            "grow_local_histmaker" => Some(TreeUpdater::GrowLocalHistMaker),
+            "grow_skmaker" => Some(TreeUpdater::GrowSkMaker),
+            "sync" => Some(TreeUpdater::Sync),
+            "refresh" => Some(TreeUpdater::Refresh),
+            "prune" => Some(TreeUpdater::Prune),
+            _ => None,
+        }
+    }
+}
+
 /// A type of boosting process to run.
 #[derive(Clone, Default)]
 pub enum ProcessType {
@@ -144,6 +177,17 @@ impl std::fmt::Display for ProcessType {
     }
 }
 
+impl ProcessType {
+    /// Parse a process type from its name in XGBoost's configuration.
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(ProcessType::Default),
+            "update" => Some(ProcessType::Update),
+            _ => None,
+        }
+    }
+}
+
 /// Controls the way new nodes are added to the tree.
 #[derive(Clone, Default)]
 pub enum GrowPolicy {
@@ -165,6 +209,17 @@ impl std::fmt::Display for GrowPolicy {
     }
 }
 
+impl GrowPolicy {
+    /// Parse a grow policy from its name in XGBoost's configuration.
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "depthwise" => Some(GrowPolicy::Depthwise),
+            "lossguide" => Some(GrowPolicy::LossGuide),
+            _ => None,
+        }
+    }
+}
+
 /// The type of predictor algorithm to use. Provides the same results but allows the use of GPU or CPU.
 #[derive(Clone, Default)]
 pub enum Predictor {
@@ -186,6 +241,18 @@ impl std::fmt::Display for Predictor {
     }
 }
 
+impl Predictor {
+    /// Parse a predictor from its name in XGBoost's configuration.
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            // older versions of XGBoost pick the predictor automatically
+            "cpu_predictor" | "auto" => Some(Predictor::Cpu),
+            "gpu_predictor" => Some(Predictor::Gpu),
+            _ => None,
+        }
+    }
+}
+
 /// BoosterParameters for Tree Booster. Create using
 /// [`TreeBoosterParametersBuilder`](struct.TreeBoosterParametersBuilder.html).
 #[derive(Builder, Clone)]
@@ -329,6 +396,20 @@ pub struct TreeBoosterParameters {
     ///
     /// * default: [`Predictor::Cpu`](enum.Predictor.html#variant.Cpu)
     predictor: Predictor,
+
+    /// Whether to allow training on features marked as categorical with
+    /// [`DMatrix::set_feature_types`](../../struct.DMatrix.html#method.set_feature_types), which are then split on
+    /// natively instead of being treated as ordered numbers. Training fails on such matrices if this isn't set.
+    ///
+    /// * default: false
+    enable_categorical: bool,
+
+    /// Maximum number of categories of a categorical feature for which one-hot encoding based splits (one category
+    /// vs. the rest) are used. Features with more categories are split by partitioning their categories into two
+    /// groups.
+    ///
+    /// * default: 4
+    max_cat_to_onehot: u32,
 }
 
 impl Default for TreeBoosterParameters {
@@ -356,6 +437,8 @@ impl Default for TreeBoosterParameters {
             max_bin: 256,
             num_parallel_tree: 1,
             predictor: Predictor::default(),
+            enable_categorical: false,
+            max_cat_to_onehot: 4,
         }
     }
 }
@@ -385,6 +468,7 @@ impl TreeBoosterParameters {
             ("max_bin".to_owned(), self.max_bin.to_string()),
             ("num_parallel_tree".to_owned(), self.num_parallel_tree.to_string()),
             ("predictor".to_owned(), self.predictor.to_string()),
+            ("max_cat_to_onehot".to_owned(), self.max_cat_to_onehot.to_string()),
         ];
 
         // Don't pass anything to XGBoost if the user didn't specify anything.
@@ -404,6 +488,69 @@ impl TreeBoosterParameters {
 
         v
     }
+
+    /// Whether training on categorical features is allowed.
+    pub(crate) fn enable_categorical(&self) -> bool {
+        self.enable_categorical
+    }
+
+    pub(crate) fn set_enable_categorical(&mut self, enable_categorical: bool) {
+        self.enable_categorical = enable_categorical;
+    }
+
+    /// Read parameters from the learner section of a model's JSON configuration.
+    pub(crate) fn from_config(learner: &serde_json::Value) -> XGBResult<Self> {
+        let booster = &learner["gradient_booster"];
+        let default = TreeBoosterParameters::default();
+
+        // updaters are only part of the parameters if the user chose them, XGBoost picks them otherwise
+        let specified_updater = find_config(booster, "specified_updater").and_then(|flag| flag.as_bool());
+        let updater = if specified_updater == Some(true) {
+            let train_param = find_config(booster, "gbtree_train_param").unwrap_or(&serde_json::Value::Null);
+            let names = config_param::<String>(train_param, "updater")?.unwrap_or_default();
+            names
+                .split(',')
+                .filter(|name| !name.is_empty())
+                .map(|name| {
+                    TreeUpdater::from_name(name).ok_or_else(|| {
+                        crate::XGBError::new(format!("Unsupported tree updater in model configuration: {}", name))
+                    })
+                })
+                .collect::<XGBResult<Vec<_>>>()?
+        } else {
+            Vec::new()
+        };
+
+        Ok(TreeBoosterParameters {
+            eta: config_param(booster, "eta")?.unwrap_or(default.eta),
+            gamma: config_param(booster, "gamma")?.unwrap_or(default.gamma),
+            max_depth: config_param(booster, "max_depth")?.unwrap_or(default.max_depth),
+            min_child_weight: config_param(booster, "min_child_weight")?.unwrap_or(default.min_child_weight),
+            max_delta_step: config_param(booster, "max_delta_step")?.unwrap_or(default.max_delta_step),
+            subsample: config_param(booster, "subsample")?.unwrap_or(default.subsample),
+            colsample_bytree: config_param(booster, "colsample_bytree")?.unwrap_or(default.colsample_bytree),
+            colsample_bylevel: config_param(booster, "colsample_bylevel")?.unwrap_or(default.colsample_bylevel),
+            colsample_bynode: config_param(booster, "colsample_bynode")?.unwrap_or(default.colsample_bynode),
+            lambda: config_param(booster, "lambda")?.unwrap_or(default.lambda),
+            alpha: config_param(booster, "alpha")?.unwrap_or(default.alpha),
+            tree_method: config_option(booster, "tree_method", TreeMethod::from_name)?.unwrap_or(default.tree_method),
+            sketch_eps: config_param(booster, "sketch_eps")?.unwrap_or(default.sketch_eps),
+            // stored with the objective's parameters
+            scale_pos_weight: config_param(&learner["objective"], "scale_pos_weight")?
+                .unwrap_or(default.scale_pos_weight),
+            updater,
+            refresh_leaf: config_flag(booster, "refresh_leaf")?.unwrap_or(default.refresh_leaf),
+            process_type: config_option(booster, "process_type", ProcessType::from_name)?
+                .unwrap_or(default.process_type),
+            grow_policy: config_option(booster, "grow_policy", GrowPolicy::from_name)?.unwrap_or(default.grow_policy),
+            max_leaves: config_param(booster, "max_leaves")?.unwrap_or(default.max_leaves),
+            max_bin: config_param(booster, "max_bin")?.unwrap_or(default.max_bin),
+            num_parallel_tree: config_param(booster, "num_parallel_tree")?.unwrap_or(default.num_parallel_tree),
+            predictor: config_option(booster, "predictor", Predictor::from_name)?.unwrap_or(default.predictor),
+            enable_categorical: default.enable_categorical,
+            max_cat_to_onehot: config_param(booster, "max_cat_to_onehot")?.unwrap_or(default.max_cat_to_onehot),
+        })
+    }
 }
 
 impl TreeBoosterParametersBuilder {
@@ -429,4 +576,40 @@ mod tests {
         let p = TreeBoosterParametersBuilder::default().build().unwrap();
         assert_eq!(p.eta, 0.3);
     }
+
+    #[test]
+    fn tree_params_from_config() {
+        let learner = serde_json::json!({
+            "gradient_booster": {
+                "gbtree_model_param": {"num_parallel_tree": "2", "num_trees": "20"},
+                "gbtree_train_param": {
+                    "process_type": "default", "tree_method": "hist", "updater": "grow_quantile_histmaker"
+                },
+                "name": "gbtree",
+                "specified_updater": false,
+                "tree_train_param": {
+                    "alpha": "0", "colsample_bytree": "0.800000012", "eta": "0.100000001", "gamma": "0",
+                    "grow_policy": "lossguide", "lambda": "1", "max_bin": "64", "max_depth": "4", "refresh_leaf": "0"
+                },
+                "updater": [{"name": "grow_quantile_histmaker"}]
+            },
+            "objective": {"name": "binary:logistic", "reg_loss_param": {"scale_pos_weight": "3"}}
+        });
+        let p = TreeBoosterParameters::from_config(&learner).unwrap();
+        assert_eq!(p.eta, 0.1);
+        assert_eq!(p.colsample_bytree, 0.8);
+        assert_eq!(p.max_depth, 4);
+        assert_eq!(p.max_bin, 64);
+        assert_eq!(p.num_parallel_tree, 2);
+        assert_eq!(p.scale_pos_weight, 3.0);
+        assert!(!p.refresh_leaf);
+        assert!(matches!(p.tree_method, TreeMethod::Hist));
+        assert!(matches!(p.grow_policy, GrowPolicy::LossGuide));
+        assert!(p.updater.is_empty());
+        // not part of the configuration
+        assert_eq!(p.min_child_weight, 1.0);
+
+        let learner = serde_json::json!({"gradient_booster": {"tree_train_param": {"grow_policy": "sideways"}}});
+        assert!(TreeBoosterParameters::from_config(&learner).is_err());
+    }
 }
@@ -0,0 +1,348 @@
+use std::ops::{Deref, DerefMut};
+use std::ptr;
+
+use super::{DMatrix, DMatrixParameters, DataIter, XGBError, XGBResult};
+use crate::data_iter::{self, DataIterProxy, SliceBatch};
+
+/// Parameters used when creating a [`QuantileDMatrix`](struct.QuantileDMatrix.html).
+/// Created using [`QuantileDMatrixParametersBuilder`](struct.QuantileDMatrixParametersBuilder.html).
+///
+/// # Example
+///
+/// ```
+/// use xgb::{QuantileDMatrix, QuantileDMatrixParametersBuilder};
+///
+/// let params = QuantileDMatrixParametersBuilder::default().max_bin(64).build().unwrap();
+/// let dtrain = QuantileDMatrix::from_dense(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 3, &params).unwrap();
+///
+/// // validation data is quantised using the same bins as the training data
+/// let params = QuantileDMatrixParametersBuilder::default()
+///     .max_bin(64)
+///     .reference(&dtrain)
+///     .build()
+///     .unwrap();
+/// let dtest = QuantileDMatrix::from_dense(&[2.0, 1.0], 1, &params).unwrap();
+/// ```
+#[derive(Builder, Clone, Debug)]
+#[builder(build_fn(validate = "Self::validate"))]
+pub struct QuantileDMatrixParameters<'a> {
+    /// Maximum number of bins each feature is quantised into. Must match the
+    /// [`max_bin`](parameters/tree/struct.TreeBoosterParametersBuilder.html#method.max_bin) parameter of boosters
+    /// trained with the matrix.
+    ///
+    /// *default*: `256`
+    #[builder(default = "256")]
+    max_bin: u32,
+
+    /// Matrix whose bins are reused to quantise this one, typically the training matrix when creating validation
+    /// matrices. Without a reference, bins are computed from the data itself.
+    ///
+    /// *default*: `None`
+    #[builder(default = "None", setter(strip_option))]
+    reference: Option<&'a DMatrix>,
+
+    /// Value in the input data treated as missing. Values which aren't stored in sparse matrices are always
+    /// treated as missing.
+    ///
+    /// *default*: `f32::NAN`
+    #[builder(default = "f32::NAN")]
+    missing: f32,
+
+    /// Number of threads used to create the matrix.
+    ///
+    /// *default*: `None` (use all available threads)
+    #[builder(default = "None")]
+    nthread: Option<u32>,
+}
+
+impl Default for QuantileDMatrixParameters<'_> {
+    fn default() -> Self {
+        QuantileDMatrixParameters {
+            max_bin: 256,
+            reference: None,
+            missing: f32::NAN,
+            nthread: None,
+        }
+    }
+}
+
+impl<'a> QuantileDMatrixParameters<'a> {
+    pub fn max_bin(&self) -> u32 {
+        self.max_bin
+    }
+
+    pub fn set_max_bin(&mut self, max_bin: u32) {
+        self.max_bin = max_bin;
+    }
+
+    pub fn reference(&self) -> Option<&'a DMatrix> {
+        self.reference
+    }
+
+    pub fn set_reference(&mut self, reference: Option<&'a DMatrix>) {
+        self.reference = reference;
+    }
+
+    pub fn missing(&self) -> f32 {
+        self.missing
+    }
+
+    pub fn set_missing(&mut self, missing: f32) {
+        self.missing = missing;
+    }
+
+    pub fn nthread(&self) -> Option<u32> {
+        self.nthread
+    }
+
+    pub fn set_nthread(&mut self, nthread: Option<u32>) {
+        self.nthread = nthread;
+    }
+}
+
+impl QuantileDMatrixParametersBuilder<'_> {
+    fn validate(&self) -> Result<(), String> {
+        if let Some(max_bin) = self.max_bin {
+            if max_bin < 2 {
+                return Err("Invalid value for 'max_bin' parameter, must be at least 2.".to_owned());
+            }
+        }
+        if self.nthread == Some(Some(0)) {
+            return Err("Invalid value for 'nthread' parameter, must be at least 1.".to_owned());
+        }
+        Ok(())
+    }
+}
+
+/// Data matrix which only stores its features quantised into bins, for memory efficient training with the `hist`
+/// tree method.
+///
+/// Quantised matrices take several times less memory than a [`DMatrix`](struct.DMatrix.html) of the same data, and
+/// can be used anywhere a `DMatrix` can through [`Deref`](std::ops::Deref), e.g. as the training matrix of
+/// [`Booster::train`](struct.Booster.html#method.train) or with
+/// [`Booster::update`](struct.Booster.html#method.update). Boosters trained with them must use the
+/// [`Hist`](parameters/tree/enum.TreeMethod.html#variant.Hist) tree method and the same `max_bin`.
+///
+/// # Example
+///
+/// ```
+/// use xgb::parameters::{self, tree};
+/// use xgb::{Booster, QuantileDMatrix, QuantileDMatrixParametersBuilder};
+///
+/// let params = QuantileDMatrixParametersBuilder::default().max_bin(32).build().unwrap();
+/// let mut dtrain = QuantileDMatrix::from_dense(&[1.0, 0.0, 1.0, 1.0, 0.0, 0.0], 3, &params).unwrap();
+/// dtrain.set_labels(&[1.0, 1.0, 0.0]).unwrap();
+///
+/// let tree_params = tree::TreeBoosterParametersBuilder::default()
+///     .tree_method(tree::TreeMethod::Hist)
+///     .max_bin(32)
+///     .build()
+///     .unwrap();
+/// let booster_params = parameters::BoosterParametersBuilder::default()
+///     .booster_type(parameters::BoosterType::Tree(tree_params))
+///     .build()
+///     .unwrap();
+/// let evaluation_sets = &[(&*dtrain, "train")];
+/// let training_params = parameters::TrainingParametersBuilder::default()
+///     .dtrain(&dtrain)
+///     .booster_params(booster_params)
+///     .evaluation_sets(Some(evaluation_sets))
+///     .build()
+///     .unwrap();
+/// let booster = Booster::train(&training_params).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct QuantileDMatrix {
+    dmat: DMatrix,
+    max_bin: u32,
+}
+
+impl QuantileDMatrix {
+    /// Create a new `QuantileDMatrix` from a dense array in row-major order, in the same way as
+    /// [`DMatrix::from_dense`](struct.DMatrix.html#method.from_dense).
+    pub fn from_dense(data: &[f32], num_rows: usize, params: &QuantileDMatrixParameters) -> XGBResult<Self> {
+        let data_iter = DataIterProxy::from_slices(SliceBatch::Dense { data, num_rows })?;
+        QuantileDMatrix::from_proxy(data_iter, params)
+    }
+
+    /// Create a new `QuantileDMatrix` from a sparse CSR matrix with `num_cols` columns, in the same way as
+    /// [`DMatrix::from_csr`](struct.DMatrix.html#method.from_csr).
+    pub fn from_csr(
+        indptr: &[usize],
+        indices: &[usize],
+        data: &[f32],
+        num_cols: usize,
+        params: &QuantileDMatrixParameters,
+    ) -> XGBResult<Self> {
+        let data_iter = DataIterProxy::from_slices(SliceBatch::Csr {
+            indptr,
+            indices,
+            data,
+            num_cols,
+        })?;
+        QuantileDMatrix::from_proxy(data_iter, params)
+    }
+
+    /// Create a new `QuantileDMatrix` from batches of data yielded by given iterator, see
+    /// [`DataIter`](trait.DataIter.html).
+    ///
+    /// XGBoost goes over the data twice, first to find the bins (unless a reference matrix is given) and then to
+    /// quantise it, so only one batch of raw data needs to be in memory at a time. Returns the first error raised by
+    /// the iterator, if any.
+    pub fn from_data_iter<I: DataIter>(iter: I, params: &QuantileDMatrixParameters) -> XGBResult<Self> {
+        let data_iter = DataIterProxy::from_iter(iter)?;
+        QuantileDMatrix::from_proxy(data_iter, params)
+    }
+
+    fn from_proxy(data_iter: Box<DataIterProxy>, params: &QuantileDMatrixParameters) -> XGBResult<Self> {
+        let mut dmat_params = DMatrixParameters::default();
+        dmat_params.set_missing(params.missing);
+        dmat_params.set_nthread(params.nthread);
+        let config = dmat_params.as_json_with(&[("max_bin", params.max_bin.to_string())]);
+
+        let reference = params.reference.map_or(ptr::null_mut(), |dmat| dmat.handle);
+        let mut handle = ptr::null_mut();
+        let result = xgb_call!(xgboost_sys::XGQuantileDMatrixCreateFromCallback(
+            data_iter.as_handle(),
+            data_iter.proxy_handle(),
+            reference,
+            Some(data_iter::reset_callback),
+            Some(data_iter::next_callback),
+            config.as_ptr(),
+            &mut handle
+        ));
+        let dmat = DMatrix::from_proxy_result(result, handle, &data_iter)?;
+        Ok(QuantileDMatrix {
+            dmat,
+            max_bin: params.max_bin,
+        })
+    }
+
+    /// Get the maximum number of bins each feature was quantised into.
+    pub fn max_bin(&self) -> u32 {
+        self.max_bin
+    }
+
+    pub(crate) fn into_dmatrix(self) -> DMatrix {
+        self.dmat
+    }
+}
+
+impl Deref for QuantileDMatrix {
+    type Target = DMatrix;
+
+    fn deref(&self) -> &DMatrix {
+        &self.dmat
+    }
+}
+
+impl DerefMut for QuantileDMatrix {
+    fn deref_mut(&mut self) -> &mut DMatrix {
+        &mut self.dmat
+    }
+}
+
+impl AsRef<DMatrix> for QuantileDMatrix {
+    fn as_ref(&self) -> &DMatrix {
+        &self.dmat
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parameters::{self, tree};
+    use crate::{Batch, Booster};
+
+    fn params(max_bin: u32) -> QuantileDMatrixParameters<'static> {
+        QuantileDMatrixParametersBuilder::default()
+            .max_bin(max_bin)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn from_dense_and_csr() {
+        let data = [1.0, 0.0, 2.0, 0.0, 0.0, 3.0, 4.0, 5.0, 6.0];
+        let dmat = QuantileDMatrix::from_dense(&data, 3, &params(16)).unwrap();
+        assert_eq!(dmat.shape(), (3, 3));
+        assert_eq!(dmat.max_bin(), 16);
+
+        let reference = QuantileDMatrixParametersBuilder::default()
+            .max_bin(16)
+            .reference(&dmat)
+            .build()
+            .unwrap();
+        let indptr = [0, 2, 3, 6];
+        let indices = [0, 2, 2, 0, 1, 2];
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0];
+        let dmat = QuantileDMatrix::from_csr(&indptr, &indices, &values, 3, &reference).unwrap();
+        assert_eq!(dmat.shape(), (3, 3));
+
+        assert!(QuantileDMatrix::from_dense(&data, 2, &params(16)).is_err());
+        assert!(QuantileDMatrixParametersBuilder::default().max_bin(1).build().is_err());
+    }
+
+    #[test]
+    fn from_data_iter() {
+        struct Rows(Vec<Vec<f32>>, usize);
+        impl DataIter for Rows {
+            fn next(&mut self) -> Result<Option<Batch>, Box<dyn std::error::Error>> {
+                let batch = self
+                    .0
+                    .get(self.1)
+                    .map(|row| Batch::from_dense(row.clone(), 1).with_labels(vec![row[0]]));
+                self.1 += 1;
+                Ok(batch)
+            }
+
+            fn reset(&mut self) {
+                self.1 = 0;
+            }
+        }
+
+        let rows = (0..8).map(|i| vec![(i % 2) as f32, i as f32]).collect();
+        let dmat = QuantileDMatrix::from_data_iter(Rows(rows, 0), &params(8)).unwrap();
+        assert_eq!(dmat.shape(), (8, 2));
+        assert_eq!(dmat.get_labels().unwrap(), &[0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn train() {
+        let data: Vec<f32> = (0..40).map(|i| (i % 5) as f32).collect();
+        let labels: Vec<f32> = (0..20).map(|i| (i % 2) as f32).collect();
+        let mut dtrain = QuantileDMatrix::from_dense(&data, 20, &params(16)).unwrap();
+        dtrain.set_labels(&labels).unwrap();
+        let test_params = QuantileDMatrixParametersBuilder::default()
+            .max_bin(16)
+            .reference(&dtrain)
+            .build()
+            .unwrap();
+        let mut dtest = QuantileDMatrix::from_dense(&data[..10], 5, &test_params).unwrap();
+        dtest.set_labels(&labels[..5]).unwrap();
+
+        let tree_params = tree::TreeBoosterParametersBuilder::default()
+            .tree_method(tree::TreeMethod::Hist)
+            .max_bin(16)
+            .build()
+            .unwrap();
+        let booster_params = parameters::BoosterParametersBuilder::default()
+            .booster_type(parameters::BoosterType::Tree(tree_params))
+            .build()
+            .unwrap();
+        let evaluation_sets = &[(&*dtrain, "train"), (&*dtest, "test")];
+        let training_params = parameters::TrainingParametersBuilder::default()
+            .dtrain(&dtrain)
+            .booster_params(booster_params)
+            .boost_rounds(3)
+            .evaluation_sets(Some(evaluation_sets))
+            .evaluation_output(parameters::EvaluationOutput::Silent)
+            .build()
+            .unwrap();
+        let (mut booster, history) = Booster::train_with_history(&training_params).unwrap();
+        assert_eq!(history["test"].values().next().unwrap().len(), 3);
+
+        booster.update(&dtrain, 3).unwrap();
+        assert_eq!(booster.predict(&dtest).unwrap().len(), 5);
+    }
+}
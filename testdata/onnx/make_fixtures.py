@@ -0,0 +1,278 @@
+"""Generate the golden ONNX models used by the tests of `src/onnx.rs`.
+
+The models are encoded independently of the crate, with a small proto2 encoder driven by the field numbers of
+`onnx.proto` and constructors mirroring `onnx.helper` (e.g. `make_node` sorts attributes by name). With the `onnx`
+package installed, `--check` builds the same models with `onnx.helper` and verifies the bytes match.
+
+Run from this directory: python3 make_fixtures.py [--check]
+"""
+
+import struct
+import sys
+
+# message name -> field name -> (number, type, repeated), from onnx.proto (proto2, so repeated numbers aren't packed)
+SCHEMA = {
+    "ModelProto": {
+        "ir_version": (1, "int64", False),
+        "producer_name": (2, "string", False),
+        "producer_version": (3, "string", False),
+        "graph": (7, "GraphProto", False),
+        "opset_import": (8, "OperatorSetIdProto", True),
+    },
+    "OperatorSetIdProto": {
+        "domain": (1, "string", False),
+        "version": (2, "int64", False),
+    },
+    "GraphProto": {
+        "node": (1, "NodeProto", True),
+        "name": (2, "string", False),
+        "input": (11, "ValueInfoProto", True),
+        "output": (12, "ValueInfoProto", True),
+    },
+    "NodeProto": {
+        "input": (1, "string", True),
+        "output": (2, "string", True),
+        "name": (3, "string", False),
+        "op_type": (4, "string", False),
+        "attribute": (5, "AttributeProto", True),
+        "domain": (7, "string", False),
+    },
+    "AttributeProto": {
+        "name": (1, "string", False),
+        "i": (3, "int64", False),
+        "s": (4, "string", False),
+        "floats": (7, "float", True),
+        "ints": (8, "int64", True),
+        "strings": (9, "string", True),
+        "type": (20, "int32", False),
+    },
+    "ValueInfoProto": {
+        "name": (1, "string", False),
+        "type": (2, "TypeProto", False),
+    },
+    "TypeProto": {
+        "tensor_type": (1, "TypeProto.Tensor", False),
+    },
+    "TypeProto.Tensor": {
+        "elem_type": (1, "int32", False),
+        "shape": (2, "TensorShapeProto", False),
+    },
+    "TensorShapeProto": {
+        "dim": (1, "TensorShapeProto.Dimension", True),
+    },
+    "TensorShapeProto.Dimension": {
+        "dim_value": (1, "int64", False),
+        "dim_param": (2, "string", False),
+    },
+}
+
+FLOAT, INT64 = 1, 7
+ATTRIBUTE_TYPES = {"i": 2, "s": 3, "floats": 6, "ints": 7, "strings": 8}
+
+
+def varint(value):
+    value &= (1 << 64) - 1
+    out = bytearray()
+    while value >= 0x80:
+        out.append((value & 0x7F) | 0x80)
+        value >>= 7
+    out.append(value)
+    return bytes(out)
+
+
+def encode_value(number, kind, value):
+    if kind in ("int64", "int32"):
+        return varint(number << 3) + varint(value)
+    if kind == "float":
+        return varint(number << 3 | 5) + struct.pack("<f", value)
+    payload = value.encode() if kind == "string" else encode(kind, value)
+    return varint(number << 3 | 2) + varint(len(payload)) + payload
+
+
+def encode(message, fields):
+    """Serialize a message given as a dict of set fields, in order of field numbers like protobuf does."""
+    schema = SCHEMA[message]
+    unknown = set(fields) - set(schema)
+    assert not unknown, "unknown fields of %s: %s" % (message, unknown)
+    out = b""
+    for name, (number, kind, repeated) in sorted(schema.items(), key=lambda item: item[1][0]):
+        if name in fields:
+            for value in fields[name] if repeated else [fields[name]]:
+                out += encode_value(number, kind, value)
+    return out
+
+
+def make_attribute(name, value):
+    if isinstance(value, int):
+        field = "i"
+    elif isinstance(value, str):
+        field = "s"
+    elif all(isinstance(v, float) for v in value):
+        field = "floats"
+    elif all(isinstance(v, int) for v in value):
+        field = "ints"
+    else:
+        field = "strings"
+    return {"name": name, field: value, "type": ATTRIBUTE_TYPES[field]}
+
+
+def make_node(op_type, inputs, outputs, name, domain=None, **kwargs):
+    node = {"input": inputs, "output": outputs, "name": name, "op_type": op_type}
+    if domain is not None:
+        node["domain"] = domain
+    node["attribute"] = [make_attribute(key, value) for key, value in sorted(kwargs.items())]
+    return node
+
+
+def make_tensor_value_info(name, elem_type, shape):
+    dims = [{"dim_value": d} if isinstance(d, int) else {"dim_param": d} for d in shape]
+    return {"name": name, "type": {"tensor_type": {"elem_type": elem_type, "shape": {"dim": dims}}}}
+
+
+def make_model(graph, opsets):
+    return encode(
+        "ModelProto",
+        {
+            "ir_version": 8,
+            "producer_name": "xgb",
+            "producer_version": "3.0.5",
+            "graph": graph,
+            "opset_import": [{"domain": domain, "version": version} for domain, version in opsets],
+        },
+    )
+
+
+# the trees of `tree_model` in the tests: node 0 splits on feature 2 < 0.5 (missing goes left), node 1 on
+# feature 0 < -1.25 (missing goes right), and nodes 2, 3 and 4 are leaves
+TREE = {
+    "nodes_featureids": [2, 0, 0, 0, 0],
+    "nodes_modes": ["BRANCH_LT", "BRANCH_LT", "LEAF", "LEAF", "LEAF"],
+    "nodes_values": [0.5, -1.25, 0.0, 0.0, 0.0],
+    "nodes_truenodeids": [1, 3, 0, 0, 0],
+    "nodes_falsenodeids": [2, 4, 0, 0, 0],
+    "nodes_missing_value_tracks_true": [1, 0, 0, 0, 0],
+}
+LEAVES = [2, 3, 4]
+LEAF_VALUES = [0.75, 0.1, -0.3]
+
+
+def tree_attributes(tree_outputs):
+    attributes = {"nodes_treeids": [], "nodes_nodeids": []}
+    for name in TREE:
+        attributes[name] = []
+    leaves = {"treeids": [], "nodeids": [], "ids": [], "weights": []}
+    for tree_id, output in enumerate(tree_outputs):
+        attributes["nodes_treeids"] += [tree_id] * 5
+        attributes["nodes_nodeids"] += list(range(5))
+        for name, values in TREE.items():
+            attributes[name] += values
+        leaves["treeids"] += [tree_id] * len(LEAVES)
+        leaves["nodeids"] += LEAVES
+        leaves["ids"] += [output] * len(LEAVES)
+        leaves["weights"] += LEAF_VALUES
+    return attributes, leaves
+
+
+def regressor(helper):
+    """`reg:squarederror` model with two trees and a base score of 0.6."""
+    attributes, leaves = tree_attributes([0, 0])
+    node = helper.make_node(
+        "TreeEnsembleRegressor",
+        ["input"],
+        ["variable"],
+        name="TreeEnsembleRegressor",
+        domain="ai.onnx.ml",
+        aggregate_function="SUM",
+        base_values=[0.6],
+        n_targets=1,
+        post_transform="NONE",
+        target_ids=leaves["ids"],
+        target_nodeids=leaves["nodeids"],
+        target_treeids=leaves["treeids"],
+        target_weights=leaves["weights"],
+        **attributes,
+    )
+    graph = helper.make_graph(
+        [node],
+        "xgboost reg:squarederror",
+        [helper.make_tensor_value_info("input", FLOAT, ["N", 3])],
+        [helper.make_tensor_value_info("variable", FLOAT, ["N", 1])],
+    )
+    return helper.make_model(graph, [("", 15), ("ai.onnx.ml", 3)])
+
+
+def classifier(helper):
+    """`multi:softprob` model with two classes, one tree per class and round, and a base score of 0.6."""
+    attributes, leaves = tree_attributes([0, 1, 0, 1])
+    node = helper.make_node(
+        "TreeEnsembleClassifier",
+        ["input"],
+        ["label", "probabilities"],
+        name="TreeEnsembleClassifier",
+        domain="ai.onnx.ml",
+        base_values=[0.6, 0.6],
+        class_ids=leaves["ids"],
+        class_nodeids=leaves["nodeids"],
+        class_treeids=leaves["treeids"],
+        class_weights=leaves["weights"],
+        classlabels_int64s=[0, 1],
+        post_transform="SOFTMAX",
+        **attributes,
+    )
+    graph = helper.make_graph(
+        [node],
+        "xgboost multi:softprob",
+        [helper.make_tensor_value_info("input", FLOAT, ["N", 3])],
+        [
+            helper.make_tensor_value_info("label", INT64, ["N"]),
+            helper.make_tensor_value_info("probabilities", FLOAT, ["N", 2]),
+        ],
+    )
+    return helper.make_model(graph, [("", 15), ("ai.onnx.ml", 3)])
+
+
+class Helper:
+    """Builds models with the encoder above."""
+
+    make_node = staticmethod(make_node)
+    make_tensor_value_info = staticmethod(make_tensor_value_info)
+
+    @staticmethod
+    def make_graph(nodes, name, inputs, outputs):
+        return {"node": nodes, "name": name, "input": inputs, "output": outputs}
+
+    make_model = staticmethod(make_model)
+
+
+class OnnxHelper:
+    """Builds models with `onnx.helper`, to check the encoder above."""
+
+    def __init__(self):
+        import onnx.helper
+
+        self.helper = onnx.helper
+
+    def make_node(self, *args, **kwargs):
+        return self.helper.make_node(*args, **kwargs)
+
+    def make_tensor_value_info(self, name, elem_type, shape):
+        return self.helper.make_tensor_value_info(name, elem_type, shape)
+
+    def make_graph(self, nodes, name, inputs, outputs):
+        return self.helper.make_graph(nodes, name, inputs, outputs)
+
+    def make_model(self, graph, opsets):
+        opsets = [self.helper.make_opsetid(domain, version) for domain, version in opsets]
+        model = self.helper.make_model(
+            graph, producer_name="xgb", producer_version="3.0.5", opset_imports=opsets, ir_version=8
+        )
+        return model.SerializeToString()
+
+
+if __name__ == "__main__":
+    for name, build in [("regressor", regressor), ("classifier", classifier)]:
+        model = build(Helper)
+        if "--check" in sys.argv:
+            assert build(OnnxHelper()) == model, name
+        with open(name + ".onnx", "wb") as file:
+            file.write(model)